@@ -68,6 +68,7 @@ use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use std::io::BufReader;
 
@@ -81,8 +82,6 @@ use graphembed::validation::anndensity::*;
 
 use hnsw_rs::hnswio::*;
 
-use hnsw_rs::prelude::DistPtr;
-
 use hdrhistogram::Histogram;
 
 /// Directory containing the 2 data files
@@ -311,7 +310,7 @@ pub fn main() {
     //
     let path = Path::new(DUMP_DIR);
     let reloader = HnswIo::new(path, "orkuthnsw");
-    let mydist = DistPtr::<usize, f64>::new(jaccard_distance::<usize>);
+    let mydist = HnswDistance::<usize>::new(Arc::new(jaccard_distance::<usize>));
 
     let hnsw_loaded = reloader.load_hnsw_with_dist(mydist);
     if hnsw_loaded.is_err() {
@@ -322,7 +321,7 @@ pub fn main() {
     //
     //  now we can check how are embedded blocks
     //
-    let d_res = density_analysis::<usize, DistPtr<usize, f64>, DefaultIx>(
+    let d_res = density_analysis::<usize, DefaultIx>(
         &orkut_graph,
         orkut_embedding.get_embedded_data(),
         Some(hnsw_loaded),
@@ -242,7 +242,7 @@ pub fn main() {
     let max_nb_connection: usize = decomposition.get_mean_block_size().min(64);
     log::info!("hnsw construction using max_nb_onnection : {max_nb_connection}");
     let ef_construction: usize = 64;
-    let hnsw_res = embeddedtohnsw::<usize, DistPtr<usize, f64>>(
+    let hnsw_res = embeddedtohnsw::<usize>(
         orkut_embedding.get_embedded_data(),
         max_nb_connection,
         ef_construction,
@@ -86,6 +86,7 @@ def validate_hope_rank(
     nbpass: int = 1,
     skip_frac: float = 0.2,
     centric: bool = False,
+    seed: int = 456231,
 ) -> float: ...
     """
     Compute a HOPE embedding using a target rank, rank algorithm in GSVD and validate accuracy (AUC).
@@ -106,6 +107,8 @@ def validate_hope_rank(
         Fraction of edges to skip when computing AUC.
     centric:
         If ``True``, use a centric AUC method for link prediction evaluation (default: False).
+    seed:
+        Seed of random generators used for edge deletion, fixing it makes runs reproducible.
     """
 def validate_hope_precision(
     csv: str,
@@ -116,6 +119,7 @@ def validate_hope_precision(
     nbpass: int = 1,
     skip_frac: float = 0.2,
     centric: bool = False,
+    seed: int = 456231,
 ) -> float: ...
     """
     Compute a HOPE embedding using a precision, range approximation algorithm in GSVD and validate accuracy (AUC).
@@ -138,6 +142,8 @@ def validate_hope_precision(
         Fraction of edges to skip when computing AUC.
     centric:
         If ``True``, use a centric AUC method for link prediction evaluation (default: False).
+    seed:
+        Seed of random generators used for edge deletion, fixing it makes runs reproducible.
     """
 def validate_sketching(
     csv: str,
@@ -148,6 +154,7 @@ def validate_sketching(
     nbpass: int = 1,
     skip_frac: float = 0.2,
     centric: bool = False,
+    seed: int = 456231,
 ) -> float: ...
     """
     Compute a node embedding using NodeSketch algorithm and validate accuracy (AUC).
@@ -170,6 +177,8 @@ def validate_sketching(
         Fraction of edges to skip when computing AUC.
     centric:
         If ``True``, use a centric AUC method for link prediction evaluation (default: False).  
+    seed:
+        Seed of random generators used for edge deletion, fixing it makes runs reproducible.
     """
 
 # ---------- VCMPR (precision/recall curves) ----------
@@ -181,6 +190,7 @@ def estimate_vcmpr_hope_rank(
     nbpass: int = 1,
     nb_edges: int = 10,
     skip_frac: float = 0.2,
+    seed: int = 456231,
 ) -> None: ...
     """
     Compute a HOPE embedding using a target rank, rank algorithm in GSVD and validate accuracy (VCMPR).
@@ -201,6 +211,8 @@ def estimate_vcmpr_hope_rank(
         Number of times to run the embedding for benchmarking.
     skip_frac:
         Fraction of edges to skip when computing AUC.
+    seed:
+        Seed of random generators used for edge deletion and node sampling.
     """
 def estimate_vcmpr_sketching(
     csv: str,
@@ -211,6 +223,7 @@ def estimate_vcmpr_sketching(
     nbpass: int = 1,
     nb_edges: int = 10,
     skip_frac: float = 0.2,
+    seed: int = 456231,
 ) -> None: ...
     """
     Compute a node embedding using NodeSketch algorithm and validate accuracy (VCMPR).
@@ -233,6 +246,8 @@ def estimate_vcmpr_sketching(
         Number of times to run the embedding for benchmarking.
     skip_frac:
         Fraction of edges to skip when computing VCMPR.
+    seed:
+        Seed of random generators used for edge deletion and node sampling.
     """
 def load_embedding_bson(
     path: str | pathlib.Path,
@@ -49,6 +49,31 @@
 //!   can be smaller as we must not make isolated points.
 //! - --centric  
 //!   This flag is optional and asks for one pass of centric AUC computation after standard AUC link prediction (See [graphembed::validation::link::estimate_centric_auc()])
+//! - --sampling uniform|degree|coreness  
+//!   This optional argument sets how the nodes examined by the centric pass are sampled, default is uniform.
+//!   With coreness, nodes are stratified by their core number so that nodes of the dense inner cores are examined
+//!   (See [graphembed::validation::link::NodeSampling]).
+//! - --seed  
+//!   This optional argument sets the seed of the random generators used for edge deletion. Default is 456231.
+//!   Passes are run in parallel when memory allows, and results depend only on the seed.
+//! - --report filename  
+//!   This optional argument dumps the link prediction reports (AUC, average precision, Hits@k, MRR with confidence intervals) in json format
+//!   in file filename. See [graphembed::validation::linkreport::LinkPredictionReport]
+//! - --labels filename  
+//!   This optional argument replaces link prediction by the node label consistency validation (ValidationMode::NODELABEL).
+//!   The file contains lines "node label" (a node with many labels appears on many lines, see [graphembed::io::csv::csv_to_node_labels()]).
+//!   The whole graph is embedded once and the label-homophily@k of the embedding is compared to the one of the graph
+//!   neighbourhood (See [graphembed::validation::nodelabel::estimate_label_homophily()]). --nbpass and --skip are then not required.
+//!   The --report argument then dumps the [graphembed::validation::nodelabel::LabelHomophilyReport].
+//! - --knbn k  
+//!   The number of neighbours examined in node label validation, default to 10.
+//! - --direction noderank|sourcetarget  
+//!   This optional argument replaces link prediction by the direction validation of asymetric embeddings (ValidationMode::DIRECTION),
+//!   so it requires a hope embedding or a sketching embedding of an asymetric graph.
+//!   Each deleted edge i->j whose reversal is not an edge is compared with its reversal j->i
+//!   (See [graphembed::validation::link::estimate_direction_auc()]). The value selects the score :
+//!   noderank is the distance of the embedding, sourcetarget the distance from the source vector of i to the target vector of j
+//!   (See [graphembed::validation::link::source_target_distance()]).
 //!
 //!
 //!     embed --csv "p2p-Gnutella08.txt" --symetric "true" validation [--centric] --nbpass 10 --skip 0.1 hope  precision --epsil 0.2 --maxrank 200  --blockiter 3
@@ -57,6 +82,21 @@
 //!
 //!     embed --csv wiki-Vote.txt --symetric false validation [--centric] --nbpass 20 --skip 0.15 sketching --decay 0.25 --dim 500 --nbiter 2
 //!
+//!     embed --csv email-Eu-core.txt --symetric false validation --labels email-Eu-core-department-labels.txt --knbn 10 sketching --decay 0.25 --dim 500 --nbiter 2
+//!
+//!     embed --csv wiki-Vote.txt --symetric false validation --direction sourcetarget --nbpass 10 --skip 0.15 sketching --decay 0.25 --dim 500 --nbiter 2
+//!
+//! 3. **Compare mode with paired statistical comparison of embedders**.
+//!
+//!  The compare command takes the arguments --nbpass, --skip, --seed and --report of the validation command and at least 2 --embedder arguments.
+//!  Each --embedder argument is a quoted hope or sketching subcommand as in the embedding mode.
+//!  All embedders are evaluated on the same edge deletions and the differences of AUC, average precision, Hits@10 and MRR
+//!  are tested with a paired t-test and a Wilcoxon signed-rank test (See [graphembed::validation::linkcompare::compare_embedders()]).
+//!
+//!     embed --csv "p2p-Gnutella08.txt" --symetric "true" compare --nbpass 10 --skip 0.1 --embedder "sketching --decay 0.1 --dim 500 --nbiter 3" --embedder "sketching --decay 0.3 --dim 500 --nbiter 3"
+//!
+//!     embed --csv "p2p-Gnutella08.txt" --symetric "true" compare --nbpass 10 --skip 0.1 --embedder "hope rank --targetrank 100 --nbiter 10" --embedder "sketching --decay 0.2 --dim 500 --nbiter 3"
+//!
 //! The module can be launched (and it is recommended) by preceding the command by setting the variable RUST_LOG to info (normal information) or debug (to get related info)
 //! as for example :  *RUST_LOG=graphembed=debug embed ....*
 //!
@@ -73,7 +113,11 @@ use graphembed::prelude::*;
 use sprs::TriMatI;
 
 use graphembed::io;
+use graphembed::validation::anndensity::embeddedasymtohnsw;
+use graphembed::validation::link::{NodeSampling, ValidationMode};
 
+// max number of labeled nodes examined in node label validation
+const NB_LABEL_SAMPLE: usize = 10000;
 
 #[doc(hidden)]
 fn parse_sketching(
@@ -179,11 +223,27 @@ impl From<NodeSketchParams> for EmbeddingParams {
 
 //=================================================================
 
+// score of an edge in direction validation
+#[doc(hidden)]
+#[derive(Copy, Clone, Debug)]
+enum DirectionScore {
+    /// distance of the embedding, see [EmbeddedAsym::get_noderank_distance()]
+    NodeRank,
+    /// distance from source vector to target vector, see [graphembed::validation::link::source_target_distance()]
+    SourceTarget,
+}
+
 #[doc(hidden)]
 #[derive(Debug)]
 struct ValidationCmd {
     validation_params: ValidationParams,
     embedding_params: EmbeddingParams,
+    /// file in which to dump the json reports
+    report_file: Option<String>,
+    /// file of node labels, for node label validation
+    label_file: Option<String>,
+    /// score of direction validation
+    direction_score: Option<DirectionScore>,
 } // end of struct ValidationCmd
 
 // parsing of valdation command
@@ -194,14 +254,10 @@ fn parse_validation_cmd(
 ) -> Result<ValidationCmd, anyhow::Error> {
     //
     log::debug!("in parse_validation_cmd");
-    // for now only link prediction is implemented
-    let nbpass = *matches
-        .get_one::<usize>("nbpass")
-        .expect("number of validation pass required");
-    //
-    let delete_proba = *matches
-        .get_one::<f64>("skip")
-        .expect("could not parse skip parameter");
+    // nbpass and skip are required for link prediction, not for node label validation
+    let label_file = matches.get_one::<String>("labels").cloned();
+    let nbpass = matches.get_one::<usize>("nbpass").copied().unwrap_or(0);
+    let delete_proba = matches.get_one::<f64>("skip").copied().unwrap_or(0.);
     //
     let centric: bool = matches.get_flag("centric");
     if centric {
@@ -210,7 +266,49 @@ fn parse_validation_cmd(
         log::info!("no centric pass on link prediction");
     }
     //
-    let validation_params = ValidationParams::new(delete_proba, nbpass, symetric, centric);
+    let seed = *matches
+        .get_one::<u64>("seed")
+        .expect("could not parse seed parameter");
+    log::info!("validation seed : {}", seed);
+    //
+    let sampling = match matches.get_one::<String>("sampling").map(|s| s.as_str()) {
+        Some("degree") => NodeSampling::Degree,
+        Some("coreness") => NodeSampling::Coreness,
+        _ => NodeSampling::Uniform,
+    };
+    if centric {
+        log::info!("sampling of nodes in centric pass : {:?}", sampling);
+    }
+    let mut validation_params = ValidationParams::new(delete_proba, nbpass, symetric, centric)
+        .with_seed(seed)
+        .with_sampling(sampling);
+    if label_file.is_some() {
+        let knbn = *matches
+            .get_one::<usize>("knbn")
+            .expect("could not parse knbn parameter");
+        log::info!(
+            "node label validation with labels in {:?}, knbn : {}",
+            label_file,
+            knbn
+        );
+        validation_params = validation_params
+            .with_mode(ValidationMode::NODELABEL)
+            .with_label_knbn(knbn);
+    }
+    let direction_score = match matches.get_one::<String>("direction").map(|s| s.as_str()) {
+        Some("noderank") => Some(DirectionScore::NodeRank),
+        Some("sourcetarget") => Some(DirectionScore::SourceTarget),
+        _ => None,
+    };
+    if direction_score.is_some() {
+        log::info!("direction validation with score : {:?}", direction_score);
+        validation_params = validation_params.with_mode(ValidationMode::DIRECTION);
+    }
+    //
+    let report_file = matches.get_one::<String>("report").cloned();
+    if report_file.is_some() {
+        log::info!("will dump validation reports in : {:?}", report_file);
+    }
     //
     let embedding_cmd_res = parse_embedding_cmd(matches, symetric);
 
@@ -220,6 +318,9 @@ fn parse_validation_cmd(
         Ok(ValidationCmd {
             validation_params,
             embedding_params: embedding_cmd.0,
+            report_file,
+            label_file,
+            direction_score,
         })
     } else {
         log::info!("parse_embedding_cmd failed");
@@ -228,6 +329,320 @@ fn parse_validation_cmd(
     //
 } // end of parse_validation_cmd
 
+#[doc(hidden)]
+#[derive(Debug)]
+struct CompareCmd {
+    validation_params: ValidationParams,
+    /// each embedder with its name (the string describing it on the command line)
+    embedders: Vec<(String, EmbeddingParams)>,
+    /// file in which to dump the json comparison
+    report_file: Option<String>,
+} // end of struct CompareCmd
+
+// parsing of compare command. embedder_cmd is the command used to parse each --embedder argument
+#[doc(hidden)]
+fn parse_compare_cmd(
+    matches: &ArgMatches,
+    embedder_cmd: &Command,
+    symetric: bool,
+) -> Result<CompareCmd, anyhow::Error> {
+    //
+    log::debug!("in parse_compare_cmd");
+    let nbpass = *matches
+        .get_one::<usize>("nbpass")
+        .expect("number of validation pass required");
+    let delete_proba = *matches
+        .get_one::<f64>("skip")
+        .expect("could not parse skip parameter");
+    let seed = *matches
+        .get_one::<u64>("seed")
+        .expect("could not parse seed parameter");
+    let validation_params =
+        ValidationParams::new(delete_proba, nbpass, symetric, false).with_seed(seed);
+    let report_file = matches.get_one::<String>("report").cloned();
+    //
+    let mut embedders = Vec::<(String, EmbeddingParams)>::new();
+    for spec in matches.get_many::<String>("embedder").into_iter().flatten() {
+        let args = std::iter::once("embedder").chain(spec.split_whitespace());
+        let embedder_m = embedder_cmd.clone().try_get_matches_from(args)?;
+        let params = match embedder_m.subcommand() {
+            Some(("hope", sub_m)) => EmbeddingParams::from(parse_hope_args(sub_m)?),
+            Some(("sketching", sub_m)) => EmbeddingParams::from(parse_sketching(sub_m, symetric)?),
+            _ => {
+                log::error!("could not parse embedder : {}", spec);
+                return Err(anyhow!("parse_compare_cmd could not parse embedder {}", spec));
+            }
+        };
+        embedders.push((spec.clone(), params));
+    }
+    if embedders.len() < 2 {
+        return Err(anyhow!("compare command needs at least 2 embedders"));
+    }
+    //
+    Ok(CompareCmd {
+        validation_params,
+        embedders,
+        report_file,
+    })
+} // end of parse_compare_cmd
+
+// runs the comparison of embedders asked by a compare command
+#[doc(hidden)]
+fn run_comparison(trimat: &TriMatI<f64, usize>, cmd: &CompareCmd, symetric_graph: bool) {
+    //
+    let embedders: Vec<NamedEmbedder<f64>> = cmd
+        .embedders
+        .iter()
+        .map(|(name, embedding_params)| match embedding_params.mode {
+            EmbeddingMode::Hope => {
+                let hope_params = embedding_params.hope.unwrap();
+                NamedEmbedder::new(name, move |trimat: TriMatI<f64, usize>| {
+                    let mut hope = Hope::new(hope_params, trimat);
+                    hope.embed().unwrap()
+                })
+            }
+            EmbeddingMode::NodeSketch => {
+                let sketching_params = embedding_params.sketching.unwrap();
+                if sketching_params.is_symetric() {
+                    NamedEmbedder::new(name, move |trimat: TriMatI<f64, usize>| {
+                        let mut nodesketch = NodeSketch::new(sketching_params, trimat);
+                        nodesketch.embed().unwrap()
+                    })
+                } else {
+                    NamedEmbedder::new(name, move |trimat: TriMatI<f64, usize>| {
+                        let mut nodesketch = NodeSketchAsym::new(sketching_params, trimat);
+                        nodesketch.embed().unwrap()
+                    })
+                }
+            }
+        })
+        .collect();
+    //
+    let params = &cmd.validation_params;
+    let comparison = compare_embedders(
+        &trimat.to_csr(),
+        params.get_nbpass(),
+        params.get_delete_fraction(),
+        symetric_graph,
+        params.get_seed(),
+        &embedders,
+    );
+    //
+    for (name, report) in comparison
+        .get_names()
+        .iter()
+        .zip(comparison.get_reports().iter())
+    {
+        let auc = report.get_auc().unwrap();
+        println!(
+            "embedder {} : mean auc {:.3e}, 95% interval : {:.3e} {:.3e}",
+            name,
+            auc.get_mean(),
+            auc.get_confidence_interval().0,
+            auc.get_confidence_interval().1
+        );
+    }
+    for c in comparison.get_comparisons() {
+        let (first, second) = c.get_names();
+        let auc = c.get_auc();
+        println!(
+            "{} vs {} : auc difference {:.3e}, 95% interval : {:.3e} {:.3e}, t-test p-value : {:.3e}, wilcoxon p-value : {:.3e}",
+            first,
+            second,
+            auc.get_mean_diff(),
+            auc.get_confidence_interval().0,
+            auc.get_confidence_interval().1,
+            auc.get_t_test().1,
+            auc.get_wilcoxon().1
+        );
+    }
+    //
+    if let Some(report_file) = &cmd.report_file {
+        let res = comparison.dump_json(std::path::Path::new(report_file));
+        if res.is_err() {
+            log::error!("dump of comparison in {} failed", report_file);
+        }
+    }
+} // end of run_comparison
+
+// node label validation : the whole graph is embedded and label-homophily@k is estimated
+#[doc(hidden)]
+fn run_label_validation(
+    trimat: TriMatI<f64, usize>,
+    node_index: &NodeIndexation<usize>,
+    embedding_params: &EmbeddingParams,
+    params: &ValidationParams,
+    label_file: &String,
+    report_file: Option<&String>,
+) {
+    //
+    let labels = match csv_to_node_labels(std::path::Path::new(label_file), node_index) {
+        Ok(labels) => labels,
+        Err(e) => {
+            log::error!("reading node labels in {} failed : {:?}", label_file, e);
+            std::process::exit(1);
+        }
+    };
+    let csrmat = trimat.to_csr();
+    let knbn = params.get_label_knbn();
+    let seed = params.get_seed();
+    // asymetric embeddings need their hnsw, symetric ones get it built by estimate_label_homophily
+    let max_nb_connection = 32;
+    let ef_construction = 64;
+    let res = match embedding_params.mode {
+        EmbeddingMode::Hope => {
+            log::info!("node label validation for hope embedding");
+            let mut hope = Hope::new(embedding_params.hope.unwrap(), trimat);
+            let embedded = hope.embed().unwrap();
+            embeddedasymtohnsw(&embedded, max_nb_connection, ef_construction).and_then(|hnsw| {
+                estimate_label_homophily(
+                    &csrmat,
+                    &labels,
+                    &embedded,
+                    Some(hnsw),
+                    knbn,
+                    NB_LABEL_SAMPLE,
+                    seed,
+                )
+            })
+        }
+        EmbeddingMode::NodeSketch => {
+            let sketching_params = embedding_params.sketching.unwrap();
+            if sketching_params.is_symetric() {
+                log::info!("node label validation for nodesketch embedding");
+                let mut nodesketch = NodeSketch::new(sketching_params, trimat);
+                let embedded = nodesketch.embed().unwrap();
+                estimate_label_homophily(
+                    &csrmat,
+                    &labels,
+                    &embedded,
+                    None,
+                    knbn,
+                    NB_LABEL_SAMPLE,
+                    seed,
+                )
+            } else {
+                log::info!("node label validation for nodesketchasym embedding");
+                let mut nodesketch = NodeSketchAsym::new(sketching_params, trimat);
+                let embedded = nodesketch.embed().unwrap();
+                embeddedasymtohnsw(&embedded, max_nb_connection, ef_construction).and_then(|hnsw| {
+                    estimate_label_homophily(
+                        &csrmat,
+                        &labels,
+                        &embedded,
+                        Some(hnsw),
+                        knbn,
+                        NB_LABEL_SAMPLE,
+                        seed,
+                    )
+                })
+            }
+        }
+    };
+    let report = match res {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("node label validation failed : {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "label homophily@{} on {} nodes, embedded : {:.3e}, graph : {:.3e}, random : {:.3e}",
+        report.get_k(),
+        report.get_nb_nodes(),
+        report.get_embedded_homophily().get_mean(),
+        report.get_graph_homophily().get_mean(),
+        report.get_random_homophily().get_mean()
+    );
+    //
+    if let Some(report_file) = report_file {
+        let res = report.dump_json(std::path::Path::new(report_file));
+        if res.is_err() {
+            log::error!("dump of label homophily report in {} failed", report_file);
+        }
+    }
+} // end of run_label_validation
+
+// runs direction auc estimation with the asked score
+#[doc(hidden)]
+fn estimate_direction_with_score<G: Sync>(
+    csrmat: &sprs::CsMatI<f64, usize>,
+    params: &ValidationParams,
+    embedder: &(dyn Fn(TriMatI<f64, usize>) -> EmbeddedAsym<G> + Sync),
+    score: DirectionScore,
+) -> LinkPredictionReport {
+    let noderank_distance =
+        |embedded: &EmbeddedAsym<G>, i: usize, j: usize| embedded.get_noderank_distance(i, j);
+    let score: &(dyn Fn(&EmbeddedAsym<G>, usize, usize) -> f64 + Sync) = match score {
+        DirectionScore::NodeRank => &noderank_distance,
+        DirectionScore::SourceTarget => &link::source_target_distance,
+    };
+    link::estimate_direction_auc(
+        csrmat,
+        params.get_nbpass(),
+        params.get_delete_fraction(),
+        params.get_seed(),
+        embedder,
+        score,
+    )
+} // end of estimate_direction_with_score
+
+// direction validation of an asymetric embedding : deleted edges are compared with their reversal
+#[doc(hidden)]
+fn run_direction_validation(
+    trimat: &TriMatI<f64, usize>,
+    embedding_params: &EmbeddingParams,
+    params: &ValidationParams,
+    score: DirectionScore,
+    report_file: Option<&String>,
+) {
+    //
+    let csrmat = trimat.to_csr();
+    let report = match embedding_params.mode {
+        EmbeddingMode::Hope => {
+            log::info!("direction validation for hope embedding");
+            let hope_params = embedding_params.hope.unwrap();
+            let f = |trimat: TriMatI<f64, usize>| -> EmbeddedAsym<f64> {
+                let mut hope = Hope::new(hope_params, trimat);
+                hope.embed().unwrap()
+            };
+            estimate_direction_with_score(&csrmat, params, &f, score)
+        }
+        EmbeddingMode::NodeSketch => {
+            let sketching_params = embedding_params.sketching.unwrap();
+            if sketching_params.is_symetric() {
+                log::error!(
+                    "direction validation needs an asymetric embedding, got symetric sketching"
+                );
+                std::process::exit(1);
+            }
+            log::info!("direction validation for nodesketchasym embedding");
+            let f = |trimat: TriMatI<f64, usize>| -> EmbeddedAsym<usize> {
+                let mut nodesketch = NodeSketchAsym::new(sketching_params, trimat);
+                nodesketch.embed().unwrap()
+            };
+            estimate_direction_with_score(&csrmat, params, &f, score)
+        }
+    };
+    match report.get_direction_auc() {
+        Some(direction_auc) => println!(
+            "direction auc with score {:?} : mean {:.3e}, 95% interval : {:.3e} {:.3e}",
+            score,
+            direction_auc.get_mean(),
+            direction_auc.get_confidence_interval().0,
+            direction_auc.get_confidence_interval().1
+        ),
+        None => println!("no direction auc computed"),
+    }
+    //
+    if let Some(report_file) = report_file {
+        let res = dump_reports_json(&[report], std::path::Path::new(report_file));
+        if res.is_err() {
+            log::error!("dump of direction report in {} failed", report_file);
+        }
+    }
+} // end of run_direction_validation
+
 // parsing of embedding command
 #[doc(hidden)]
 fn parse_embedding_cmd(
@@ -376,7 +791,7 @@ pub fn main() {
         .subcommand_required(true)
         .arg(
             Arg::new("nbpass")
-                .required(true)
+                .required_unless_present("labels")
                 .long("nbpass")
                 .help("number  of passes of validation")
                 .action(ArgAction::Set)
@@ -384,7 +799,7 @@ pub fn main() {
         )
         .arg(
             Arg::new("skip")
-                .required(true)
+                .required_unless_present("labels")
                 .long("skip")
                 .help("fraction of edges to skip in training set")
                 .action(ArgAction::Set)
@@ -396,9 +811,102 @@ pub fn main() {
                .action(clap::ArgAction::SetTrue)
                 .help("--centric To ask for a centric validation pass after standard one, require no value")
             )
+        .arg(
+            Arg::new("sampling")
+                .long("sampling")
+                .help("sampling of nodes in centric pass : uniform, degree or coreness")
+                .default_value("uniform")
+                .action(ArgAction::Set)
+                .value_parser(["uniform", "degree", "coreness"]),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("seed of random generators for edge deletion")
+                .default_value("456231")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Set)
+                .help("--report fname to dump validation reports in json format in fname"),
+        )
+        .arg(
+            Arg::new("labels")
+                .long("labels")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Set)
+                .help("--labels fname to run node label validation with node labels in fname"),
+        )
+        .arg(
+            Arg::new("knbn")
+                .long("knbn")
+                .help("number of neighbours examined in node label validation")
+                .default_value("10")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("direction")
+                .long("direction")
+                .conflicts_with("labels")
+                .help("--direction noderank|sourcetarget to run direction validation of an asymetric embedding with the given score")
+                .action(ArgAction::Set)
+                .value_parser(["noderank", "sourcetarget"]),
+        )
         .subcommand(hope_cmd.clone())
         .subcommand(sketch_cmd.clone());
 
+    // the compare command takes at least 2 embedders, each one described by a hope or sketching subcommand
+    let embedder_cmd = Command::new("embedder")
+        .subcommand_required(true)
+        .subcommand(hope_cmd.clone())
+        .subcommand(sketch_cmd.clone());
+    let compare_cmd = Command::new("compare")
+        .about("Paired comparison of embedders on identical edge deletions")
+        .arg(
+            Arg::new("nbpass")
+                .required(true)
+                .long("nbpass")
+                .help("number  of passes of validation")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("skip")
+                .required(true)
+                .long("skip")
+                .help("fraction of edges to skip in training set")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("seed of random generators for edge deletion")
+                .default_value("456231")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Set)
+                .help("--report fname to dump the comparison in json format in fname"),
+        )
+        .arg(
+            Arg::new("embedder")
+                .long("embedder")
+                .required(true)
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("--embedder \"sketching --decay 0.1 --dim 500 --nbiter 3\", at least 2 embedders"),
+        );
+
     // the embedding command does just the embedding
     let embedding_command = Command::new("embedding")
         .about("Graph/Network Embedding")
@@ -440,6 +948,7 @@ pub fn main() {
         .subcommand_required(true)
         .subcommand(embedding_command)
         .subcommand(validation_cmd)
+        .subcommand(compare_cmd)
         .get_matches();
 
     //
@@ -456,6 +965,10 @@ pub fn main() {
     let embedding_parameters: Option<EmbeddingParams>;
     let mut validation_params: Option<ValidationParams> = None;
     let mut output_params: Option<io::output::Output> = None;
+    let mut report_file: Option<String> = None;
+    let mut label_file: Option<String> = None;
+    let mut direction_score: Option<DirectionScore> = None;
+    let mut compare_params: Option<CompareCmd> = None;
     //
     match matches.subcommand() {
         Some(("validation", sub_m)) => {
@@ -465,6 +978,9 @@ pub fn main() {
                 Ok(cmd) => {
                     validation_params = Some(cmd.validation_params);
                     embedding_parameters = Some(cmd.embedding_params);
+                    report_file = cmd.report_file;
+                    label_file = cmd.label_file;
+                    direction_score = cmd.direction_score;
                 }
                 _ => {
                     log::error!(
@@ -476,6 +992,24 @@ pub fn main() {
             }
         }
 
+        Some(("compare", sub_m)) => {
+            log::debug!("got compare command");
+            let res = parse_compare_cmd(sub_m, &embedder_cmd, symetric_graph);
+            match res {
+                Ok(cmd) => {
+                    compare_params = Some(cmd);
+                    embedding_parameters = None;
+                }
+                _ => {
+                    log::error!(
+                        "exiting with error in parsing compare command {}",
+                        res.err().unwrap()
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+
         Some(("embedding", sub_m)) => {
             log::debug!("got embedding command");
             let res = parse_embedding_cmd(sub_m, symetric_graph);
@@ -558,7 +1092,39 @@ pub fn main() {
     //
     // we have our graph in trimat format, we must pass info on symetry or asymetry
     //
+    if let Some(compare_params) = compare_params {
+        run_comparison(&trimat, &compare_params, symetric_graph);
+        return;
+    }
+    //
     let embedding_parameters = embedding_parameters.unwrap();
+    if let Some(params) = validation_params.as_ref()
+        && params.get_mode() == ValidationMode::NODELABEL
+    {
+        run_label_validation(
+            trimat,
+            &node_index,
+            &embedding_parameters,
+            params,
+            label_file.as_ref().unwrap(),
+            report_file.as_ref(),
+        );
+        return;
+    }
+    if let Some(params) = validation_params.as_ref()
+        && params.get_mode() == ValidationMode::DIRECTION
+    {
+        run_direction_validation(
+            &trimat,
+            &embedding_parameters,
+            params,
+            direction_score.unwrap(),
+            report_file.as_ref(),
+        );
+        return;
+    }
+    // reports of validation estimators
+    let mut reports = Vec::<LinkPredictionReport>::new();
 
     match embedding_parameters.mode {
         EmbeddingMode::Hope => {
@@ -595,7 +1161,9 @@ pub fn main() {
                         params.get_nbpass(),
                         symetric_graph,
                         params.do_centric(),
-                    );
+                    )
+                    .with_seed(params.get_seed())
+                    .with_sampling(params.get_sampling());
                 }
                 log::debug!("validation parameters : {:?}", params);
                 // have to run validation simulations
@@ -606,13 +1174,14 @@ pub fn main() {
                     let res = hope.embed();
                     res.unwrap()
                 };
-                link::estimate_auc(
+                reports.push(link::estimate_auc(
                     &trimat.to_csr(),
                     params.get_nbpass(),
                     params.get_delete_fraction(),
                     symetric_graph,
+                    params.get_seed(),
                     &f,
-                );
+                ));
                 if params.do_centric() {
                     if do_vcmpr {
                         // if vcmpr is asked we produce also standard precision and recall for comparison
@@ -622,26 +1191,31 @@ pub fn main() {
                             params.get_nbpass(),
                             params.get_delete_fraction(),
                             symetric_graph,
+                            params.get_seed(),
                             &f,
                         );
                         */
-                        link::estimate_vcmpr(
+                        reports.push(link::estimate_vcmpr_with_sampling(
                             &trimat.to_csr(),
                             params.get_nbpass(),
                             10,
                             params.get_delete_fraction(),
                             symetric_graph,
+                            params.get_seed(),
+                            params.get_sampling(),
                             &f,
-                        );
+                        ));
                     }
                     //
-                    link::estimate_centric_auc(
+                    reports.push(link::estimate_centric_auc_with_sampling(
                         &trimat.to_csr(),
                         params.get_nbpass(),
                         params.get_delete_fraction(),
                         symetric_graph,
+                        params.get_seed(),
+                        params.get_sampling(),
                         &f,
-                    );
+                    ));
                 }
             }
         } // end case Hope
@@ -712,13 +1286,14 @@ pub fn main() {
                         let res = nodesketch.embed();
                         res.unwrap()
                     };
-                    link::estimate_auc(
+                    reports.push(link::estimate_auc(
                         &trimat.to_csr(),
                         validation_params.get_nbpass(),
                         validation_params.get_delete_fraction(),
                         symetric_graph,
+                        validation_params.get_seed(),
                         &f,
-                    );
+                    ));
                 }
                 // end case asymetric
                 else {
@@ -728,13 +1303,14 @@ pub fn main() {
                         let res = nodesketch.embed();
                         res.unwrap()
                     };
-                    link::estimate_auc(
+                    reports.push(link::estimate_auc(
                         &trimat.to_csr(),
                         validation_params.get_nbpass(),
                         validation_params.get_delete_fraction(),
                         symetric_graph,
+                        validation_params.get_seed(),
                         &f,
-                    );
+                    ));
                     // we compare with VCMPR
                     if validation_params.do_centric() {
                         log::info!("doing precision estimation normal and centric modes ");
@@ -746,26 +1322,31 @@ pub fn main() {
                                 2,
                                 validation_params.get_delete_fraction(),
                                 symetric_graph,
+                                validation_params.get_seed(),
                                 &f,
                             );
                             */
-                            link::estimate_vcmpr(
+                            reports.push(link::estimate_vcmpr_with_sampling(
                                 &trimat.to_csr(),
                                 2,
                                 10,
                                 validation_params.get_delete_fraction(),
                                 symetric_graph,
+                                validation_params.get_seed(),
+                                validation_params.get_sampling(),
                                 &f,
-                            );
+                            ));
                         }
                         //
-                        link::estimate_centric_auc(
+                        reports.push(link::estimate_centric_auc_with_sampling(
                             &trimat.to_csr(),
                             validation_params.get_nbpass(),
                             validation_params.get_delete_fraction(),
                             symetric_graph,
+                            validation_params.get_seed(),
+                            validation_params.get_sampling(),
                             &f,
-                        );
+                        ));
                     }
                 }
                 // TODO precision estimation too costly must subsample
@@ -774,5 +1355,11 @@ pub fn main() {
         } // end case sketching_params
     };
     //
+    if let Some(report_file) = report_file {
+        let res = dump_reports_json(&reports, std::path::Path::new(&report_file));
+        if res.is_err() {
+            log::error!("dump of reports in {} failed", report_file);
+        }
+    }
     //
 } // end fo main
@@ -29,7 +29,7 @@ use annembed::tools::svdapprox::{MatMode, MatRepr, RangeApproxMode, SvdApprox, S
 
 use super::orderingf::*;
 use super::randgsvd::{GSvdApprox, GSvdResult};
-use crate::embedding::{EmbeddedAsym, EmbedderT};
+use crate::embedding::{AsymDistance, EmbeddedAsym, EmbedderT, dot_product_dissimilarity};
 
 /// The dissimilarity corresponding to hope. Note that it is not a distance, nor is it guaranteed to be positive.
 /// Basically it is the opposite of the similarity estimated (and constructed in the Hope matrix)
@@ -89,6 +89,8 @@ pub struct HopeParams {
     range_m: RangeApproxMode,
     /// decay factor taking account number of hops away from a node
     decay_f: f64,
+    /// strategy for node dissimilarity in the embedding
+    asym_distance: AsymDistance,
 } //
 
 impl HopeParams {
@@ -97,9 +99,20 @@ impl HopeParams {
             hope_m,
             range_m,
             decay_f,
+            asym_distance: AsymDistance::default(),
         }
     } // end of new
 
+    /// sets the strategy used by the embedding to compute node dissimilarities (default is [AsymDistance::Average]).
+    /// With Hope [AsymDistance::DotProduct] corresponds to the similarity approximated by the embedding.
+    pub fn set_asym_distance(&mut self, asym_distance: AsymDistance) {
+        self.asym_distance = asym_distance;
+    }
+
+    pub fn get_asym_distance(&self) -> AsymDistance {
+        self.asym_distance
+    }
+
     pub fn get_hope_mode(&self) -> HopeMode {
         self.hope_m
     }
@@ -352,7 +365,8 @@ where
             log::trace!("\n target {} {:?}", i, target.row(i));
         }
         log::trace!("exiting embed_from_svd_result");
-        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance);
+        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance)
+            .with_dot_product(dot_product_dissimilarity::<F>);
         //
         Ok(embedded_a)
     } // end of embed_rpr_simple
@@ -470,7 +484,8 @@ where
         }
         self.sigma_q = Some(Array1::from_iter(sigma_q.iter().map(|x| x.1)));
         //
-        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance);
+        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance)
+            .with_dot_product(dot_product_dissimilarity::<F>);
         //
         Ok(embedded_a)
     } // end of embed_from_gsvd_result
@@ -530,7 +545,8 @@ where
             log::trace!("\n target {} {:?}", i, target.row(i));
         }
         log::trace!("exiting embed_from_svd_result");
-        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance);
+        let embedded_a = EmbeddedAsym::new(source, target, None, hope_distance)
+            .with_dot_product(dot_product_dissimilarity::<F>);
         //
         Ok(embedded_a)
     } // end of embed_from_svd_result
@@ -603,6 +619,8 @@ where
                 self.embed_ada_from_svd_result(&svd_res)
             }
         }; // znd of match
+        let mut embedding = embedding?;
+        embedding.set_asym_distance(self.params.get_asym_distance())?;
         let sys_t: f64 = sys_start.elapsed().unwrap().as_millis() as f64 / 1000.;
         log::info!(
             " compute_embedded sys time(s) {:.2e} cpu time(s) {:.2e}",
@@ -610,7 +628,7 @@ where
             cpu_start.elapsed().as_secs()
         );
         //
-        embedding
+        Ok(embedding)
     } // end of compute_embedded
 } // end of impl Hope
 
@@ -0,0 +1,293 @@
+//! Continuous node attributes.
+//!
+//! Sketching relies on discrete labels (see [LabelT]), so continuous node attributes (expression levels, features or embedding vectors ...)
+//! are discretized into tokens which are added to the discrete labels of nodes.
+//! Two discretizations are provided:
+//!
+//! - [HyperplaneDiscretizer] : locality sensitive hashing by random hyperplanes (SimHash, Charikar 2002).
+//!   Each of nb_tables tables gives a token made of nb_bits signs of projections on random hyperplanes,
+//!   so 2 nodes share a token with a probability increasing with the cosine similarity of their (centered) attributes.
+//!
+//! - [QuantileDiscretizer] : each dimension is binned by its quantiles, a node gets one token per dimension.
+//!
+//! Tokens are then mapped to labels after the labels already used in the graph (see [add_attribute_labels]),
+//! so that nodes sharing attribute tokens share labels and the sketching propagates them as other labels.
+
+use anyhow::anyhow;
+
+use ndarray::{Array2, ArrayView1, Axis};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rand_xoshiro::rand_core::SeedableRng;
+
+use petgraph::EdgeType;
+use petgraph::graph::NodeIndex;
+
+use super::loader::LabeledGraph;
+use super::pgraph::*;
+
+/// Converts an attribute vector into tokens in 0..get_nb_tokens()
+pub trait Discretizer {
+    /// number of distinct tokens the discretizer can produce
+    fn get_nb_tokens(&self) -> usize;
+    /// tokens of an attribute vector
+    fn get_tokens(&self, attributes: ArrayView1<f32>) -> Vec<usize>;
+}
+
+/// Discretization by random hyperplanes.
+pub struct HyperplaneDiscretizer {
+    /// mean of attributes, attributes are centered before projection
+    mean: Vec<f32>,
+    /// random gaussian hyperplanes, one row for each bit of each table
+    planes: Array2<f32>,
+    /// number of bits of a token
+    nb_bits: usize,
+    /// number of tokens by node
+    nb_tables: usize,
+} // end of struct HyperplaneDiscretizer
+
+impl HyperplaneDiscretizer {
+    /// attributes : array (nb_nodes, dim) used to compute the mean of attributes.
+    /// Each node will get nb_tables tokens, each token has nb_bits bits (nb_bits <= 16).
+    pub fn new(
+        attributes: &Array2<f32>,
+        nb_bits: usize,
+        nb_tables: usize,
+        seed: u64,
+    ) -> anyhow::Result<Self> {
+        if nb_bits == 0 || nb_bits > 16 || nb_tables == 0 {
+            return Err(anyhow!(
+                "HyperplaneDiscretizer needs 1 <= nb_bits <= 16 and nb_tables >= 1"
+            ));
+        }
+        let dim = attributes.ncols();
+        let mean = match attributes.mean_axis(Axis(0)) {
+            Some(mean) => mean.to_vec(),
+            None => vec![0.; dim],
+        };
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let planes = Array2::<f32>::from_shape_simple_fn((nb_tables * nb_bits, dim), || {
+            rng.sample(StandardNormal)
+        });
+        Ok(HyperplaneDiscretizer {
+            mean,
+            planes,
+            nb_bits,
+            nb_tables,
+        })
+    } // end of new
+} // end of impl HyperplaneDiscretizer
+
+impl Discretizer for HyperplaneDiscretizer {
+    fn get_nb_tokens(&self) -> usize {
+        self.nb_tables << self.nb_bits
+    }
+
+    fn get_tokens(&self, attributes: ArrayView1<f32>) -> Vec<usize> {
+        assert_eq!(attributes.len(), self.mean.len());
+        let centered: Vec<f32> = attributes
+            .iter()
+            .zip(self.mean.iter())
+            .map(|(x, m)| x - m)
+            .collect();
+        (0..self.nb_tables)
+            .map(|t| {
+                let mut token = 0usize;
+                for b in 0..self.nb_bits {
+                    let plane = self.planes.row(t * self.nb_bits + b);
+                    let proj: f32 = plane.iter().zip(centered.iter()).map(|(p, x)| p * x).sum();
+                    if proj >= 0. {
+                        token |= 1 << b;
+                    }
+                }
+                (t << self.nb_bits) + token
+            })
+            .collect()
+    }
+} // end of impl Discretizer for HyperplaneDiscretizer
+
+/// Discretization of each dimension by its quantiles.
+pub struct QuantileDiscretizer {
+    /// thresholds\[d\] are the nb_bins - 1 inner quantiles of dimension d
+    thresholds: Vec<Vec<f32>>,
+    nb_bins: usize,
+} // end of struct QuantileDiscretizer
+
+impl QuantileDiscretizer {
+    /// attributes : array (nb_nodes, dim) from which quantiles of each dimension are computed.
+    pub fn new(attributes: &Array2<f32>, nb_bins: usize) -> anyhow::Result<Self> {
+        if nb_bins < 2 || attributes.nrows() == 0 {
+            return Err(anyhow!(
+                "QuantileDiscretizer needs nb_bins >= 2 and some attributes"
+            ));
+        }
+        let nb_nodes = attributes.nrows();
+        let thresholds = attributes
+            .axis_iter(Axis(1))
+            .map(|column| {
+                let mut values = column.to_vec();
+                values.sort_unstable_by(|a, b| a.total_cmp(b));
+                (1..nb_bins)
+                    .map(|q| values[(q * nb_nodes / nb_bins).min(nb_nodes - 1)])
+                    .collect()
+            })
+            .collect();
+        Ok(QuantileDiscretizer {
+            thresholds,
+            nb_bins,
+        })
+    } // end of new
+} // end of impl QuantileDiscretizer
+
+impl Discretizer for QuantileDiscretizer {
+    fn get_nb_tokens(&self) -> usize {
+        self.thresholds.len() * self.nb_bins
+    }
+
+    fn get_tokens(&self, attributes: ArrayView1<f32>) -> Vec<usize> {
+        assert_eq!(attributes.len(), self.thresholds.len());
+        attributes
+            .iter()
+            .zip(self.thresholds.iter())
+            .enumerate()
+            .map(|(d, (x, thresholds))| {
+                let bin = thresholds.partition_point(|t| t <= x);
+                d * self.nb_bins + bin
+            })
+            .collect()
+    }
+} // end of impl Discretizer for QuantileDiscretizer
+
+/// adds to each node of the graph the labels corresponding to the tokens of its attributes.
+///
+/// - attributes : array (nb_nodes, dim), row i contains attributes of node of NodeIndex i.
+/// - first_label : token t is mapped to label first_label + t. It must be greater than labels already used in the graph
+///   (for a graph loaded by [load_labeled_graph](super::loader::load_labeled_graph) it is idmap.get_nb_labels() + 1).
+pub fn add_attribute_labels<Nlabel, Elabel, Ty>(
+    graph: &mut LabeledGraph<Nlabel, Elabel, Ty>,
+    attributes: &Array2<f32>,
+    discretizer: &dyn Discretizer,
+    first_label: usize,
+) -> anyhow::Result<()>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    Elabel: LabelT,
+    Ty: EdgeType,
+{
+    //
+    if attributes.nrows() != graph.node_count() {
+        return Err(anyhow!(
+            "add_attribute_labels got {} attribute vectors for {} nodes",
+            attributes.nrows(),
+            graph.node_count()
+        ));
+    }
+    let nb_tokens = discretizer.get_nb_tokens();
+    if first_label == 0 || nb_tokens == 0 {
+        return Err(anyhow!(
+            "add_attribute_labels needs first_label > 0 and a discretizer with tokens, got first_label {first_label} and {nb_tokens} tokens"
+        ));
+    }
+    let last_label = first_label.saturating_add(nb_tokens - 1);
+    if Nlabel::try_from(last_label).is_err() {
+        log::error!(
+            "add_attribute_labels labels {first_label}..={last_label} cannot be stored in label type"
+        );
+        return Err(anyhow!(
+            "add_attribute_labels labels {first_label}..={last_label} cannot be stored in label type"
+        ));
+    }
+    for (i, row) in attributes.axis_iter(Axis(0)).enumerate() {
+        let labels: Vec<Nlabel> = discretizer
+            .get_tokens(row)
+            .into_iter()
+            .filter_map(|t| Nlabel::try_from(first_label + t).ok())
+            .collect();
+        graph[NodeIndex::new(i)].add_labels(&labels);
+    }
+    log::info!(
+        "add_attribute_labels added labels {first_label}..={last_label} to {} nodes",
+        graph.node_count()
+    );
+    //
+    Ok(())
+} // end of add_attribute_labels
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::psketch::MgraphSketch;
+    use crate::embed::tools::edge;
+    use crate::embed::tools::jaccard::jaccard_distance;
+    use petgraph::Undirected;
+
+    use super::super::loader::{LabeledEdge, LabeledNode};
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 2 cliques of 6 nodes joined by an edge, nodes have the same discrete label but attributes depend on the clique
+    #[test]
+    fn test_attribute_labels_two_cliques() {
+        log_init_test();
+        //
+        let mut graph = LabeledGraph::<u16, u8, Undirected>::default();
+        let nodes: Vec<NodeIndex> = (0..12)
+            .map(|i| graph.add_node(LabeledNode::new(i, Nweight::new(vec![1]))))
+            .collect();
+        for c in 0..2 {
+            for i in 6 * c..6 * c + 6 {
+                for j in i + 1..6 * c + 6 {
+                    graph.add_edge(nodes[i], nodes[j], LabeledEdge::default());
+                }
+            }
+        }
+        graph.add_edge(nodes[5], nodes[6], LabeledEdge::default());
+        let attributes = Array2::<f32>::from_shape_fn((12, 4), |(i, d)| {
+            let center = if i < 6 {
+                [5., 0., 1., 2.]
+            } else {
+                [0., 5., 2., 1.]
+            };
+            center[d] + 0.01 * ((i * 4 + d) % 3) as f32
+        });
+        //
+        let hyperplanes = HyperplaneDiscretizer::new(&attributes, 4, 3, 1234).unwrap();
+        assert_eq!(hyperplanes.get_nb_tokens(), 48);
+        let t0 = hyperplanes.get_tokens(attributes.row(0));
+        assert_eq!(t0.len(), 3);
+        assert_eq!(t0, hyperplanes.get_tokens(attributes.row(1)));
+        assert_ne!(t0, hyperplanes.get_tokens(attributes.row(7)));
+        //
+        let quantiles = QuantileDiscretizer::new(&attributes, 2).unwrap();
+        assert_eq!(quantiles.get_tokens(attributes.row(0)), vec![1, 2, 4, 7]);
+        assert_eq!(quantiles.get_tokens(attributes.row(7)), vec![0, 3, 5, 6]);
+        // labels after discrete label 1
+        add_attribute_labels(&mut graph, &attributes, &hyperplanes, 2).unwrap();
+        assert_eq!(graph[nodes[0]].get_nweight().get_labels().len(), 4);
+        assert!(add_attribute_labels(&mut graph, &attributes, &hyperplanes, 0).is_err());
+        // attributes of dimension 0 give no token
+        let no_attributes = Array2::<f32>::zeros((12, 0));
+        let no_tokens = QuantileDiscretizer::new(&no_attributes, 2).unwrap();
+        assert_eq!(no_tokens.get_nb_tokens(), 0);
+        assert!(add_attribute_labels(&mut graph, &no_attributes, &no_tokens, 0).is_err());
+        assert!(add_attribute_labels(&mut graph, &no_attributes, &no_tokens, 2).is_err());
+        // nodes of a clique are now closer than nodes of different cliques
+        let skparams = SketchParams::new(50, 0.1, 2, true, false);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, false);
+        skgraph.compute_embedded().unwrap();
+        let embedded = skgraph.get_n_embedded_ref().unwrap();
+        let v = |i: usize| embedded.get_embedded_node(i, edge::INOUT).to_vec();
+        let d_in = jaccard_distance(&v(0), &v(1));
+        let d_out = jaccard_distance(&v(0), &v(8));
+        log::info!("distance in clique : {d_in}, between cliques : {d_out}");
+        assert!(d_in < d_out);
+    } // end of test_attribute_labels_two_cliques
+} // end of mod tests
@@ -48,6 +48,12 @@ impl HasNweight<u8> for PpiNode {
     } // end of get_nweight
 } // end of impl HasNweight
 
+impl HasNweightMut<u8> for PpiNode {
+    fn get_nweight_mut(&mut self) -> &mut Nweight<u8> {
+        &mut self.labels
+    }
+} // end of impl HasNweightMut
+
 /// Edge satisfy default
 #[derive(Default)]
 pub struct PpiEdge {
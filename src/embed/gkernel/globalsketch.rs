@@ -0,0 +1,169 @@
+//! Global summary of a directed labeled graph.
+//!
+//! In the directed case each node has 2 sketches: the OUT sketch of a node summarizes labels reached by following outgoing edges
+//! (the node as a source), the IN sketch summarizes labels reached by following incoming edges (the node as a target).
+//!
+//! The global summary of a graph keeps the 2 directions separated: the OUT signature is a weighted probminhash of
+//! the counts of labels found in all OUT sketches of nodes, the IN signature does the same with IN sketches.
+//! So a graph and its reverse graph get the OUT and IN signatures swapped, whereas merging both directions
+//! in one signature (as done by [get_global_embedded_n](super::psketch::MgraphSketchAsym::get_global_embedded_n)) cannot distinguish them.
+//!
+//! The distance between 2 summaries is the mean of the Jaccard distances between OUT signatures and between IN signatures.
+//! It is also the Jaccard distance between the concatenations OUT then IN of signatures (see [GlobalSketchAsym::get_concatenated])
+//! so concatenated summaries of a collection of directed graphs can be used directly in
+//! [knn_graph_classification](crate::validation::graphclass::knn_graph_classification).
+
+use ndarray::Array1;
+
+use crate::embed::tools::jaccard::jaccard_distance;
+
+/// The global summary of a directed graph, made of an OUT signature and an IN signature of same size.
+#[derive(Clone, Debug)]
+pub struct GlobalSketchAsym<T> {
+    /// signature of labels found following outgoing edges
+    out_sketch: Array1<T>,
+    /// signature of labels found following incoming edges
+    in_sketch: Array1<T>,
+} // end of struct GlobalSketchAsym
+
+impl<T> GlobalSketchAsym<T>
+where
+    T: Clone + Eq,
+{
+    /// out_sketch and in_sketch must have the same size
+    pub fn new(out_sketch: Array1<T>, in_sketch: Array1<T>) -> Self {
+        assert_eq!(out_sketch.len(), in_sketch.len());
+        GlobalSketchAsym {
+            out_sketch,
+            in_sketch,
+        }
+    }
+
+    /// returns the signature of labels found following outgoing edges
+    pub fn get_out_sketch(&self) -> &Array1<T> {
+        &self.out_sketch
+    }
+
+    /// returns the signature of labels found following incoming edges
+    pub fn get_in_sketch(&self) -> &Array1<T> {
+        &self.in_sketch
+    }
+
+    /// size of each signature
+    pub fn get_size(&self) -> usize {
+        self.out_sketch.len()
+    }
+
+    /// mean of Jaccard distances between OUT signatures and between IN signatures.
+    pub fn distance(&self, other: &Self) -> f64 {
+        assert_eq!(self.get_size(), other.get_size());
+        let d_out = jaccard_distance(
+            self.out_sketch.as_slice().unwrap(),
+            other.out_sketch.as_slice().unwrap(),
+        );
+        let d_in = jaccard_distance(
+            self.in_sketch.as_slice().unwrap(),
+            other.in_sketch.as_slice().unwrap(),
+        );
+        0.5 * (d_out + d_in)
+    } // end of distance
+
+    /// returns the concatenation of OUT and IN signatures.
+    /// The Jaccard distance between concatenations is equal to [distance](Self::distance)
+    pub fn get_concatenated(&self) -> Array1<T> {
+        let mut v = Vec::<T>::with_capacity(2 * self.get_size());
+        v.extend(self.out_sketch.iter().cloned());
+        v.extend(self.in_sketch.iter().cloned());
+        Array1::from_vec(v)
+    } // end of get_concatenated
+} // end of impl GlobalSketchAsym
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::loader::{LabeledEdge, LabeledGraph, LabeledNode};
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::pgraph::Nweight;
+    use crate::embed::gkernel::psketch::MgraphSketchAsym;
+    use petgraph::Directed;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // a star with center labelled 2 and leaves labelled 1, edges go from center to leaves if out is true
+    fn labeled_star(nb_leaves: u32, out: bool) -> LabeledGraph<u8, u8, Directed> {
+        let mut graph = LabeledGraph::<u8, u8, Directed>::default();
+        let center = graph.add_node(LabeledNode::new(0, Nweight::new(vec![2])));
+        for i in 1..=nb_leaves {
+            let leaf = graph.add_node(LabeledNode::new(i, Nweight::new(vec![1])));
+            if out {
+                graph.add_edge(center, leaf, LabeledEdge::default());
+            } else {
+                graph.add_edge(leaf, center, LabeledEdge::default());
+            }
+        }
+        graph
+    } // end of labeled_star
+
+    // returns global vector merging directions and global summary keeping directions
+    fn global_sketches(
+        nb_leaves: u32,
+        out: bool,
+        size: usize,
+    ) -> (Array1<u8>, GlobalSketchAsym<u8>) {
+        let skparams = SketchParams::new(20, 0.1, 2, false, false);
+        let graph = labeled_star(nb_leaves, out);
+        let mut skgraph = MgraphSketchAsym::new(&graph, skparams.clone(), false);
+        skgraph.compute_embedded().unwrap();
+        let global_asym = skgraph.get_global_embedded_asym_n(size).unwrap();
+        let mut skgraph = MgraphSketchAsym::new(&graph, skparams, false);
+        skgraph.compute_embedded().unwrap();
+        let global = skgraph.get_global_embedded_n(size).unwrap();
+        (global, global_asym)
+    } // end of global_sketches
+
+    #[test]
+    fn test_global_sketch_asym_stars() {
+        log_init_test();
+        //
+        let size = 200;
+        let (out_merged, out_star) = global_sketches(8, true, size);
+        let (out_merged_2, out_star_2) = global_sketches(9, true, size);
+        let (in_merged, in_star) = global_sketches(8, false, size);
+        assert_eq!(out_star.get_size(), size);
+        // merging directions cannot distinguish a star from its reverse
+        let d_merged = jaccard_distance(
+            out_merged.as_slice().unwrap(),
+            in_merged.as_slice().unwrap(),
+        );
+        let d_merged_2 = jaccard_distance(
+            out_merged.as_slice().unwrap(),
+            out_merged_2.as_slice().unwrap(),
+        );
+        log::info!("merged directions, reverse : {d_merged:.3e}, out stars : {d_merged_2:.3e}");
+        assert!(d_merged < 0.05);
+        // the directed summary does
+        let d_reverse = out_star.distance(&in_star);
+        let d_same = out_star.distance(&out_star_2);
+        log::info!("directed summary, reverse : {d_reverse:.3e}, out stars : {d_same:.3e}");
+        assert!(d_same < d_reverse);
+        assert!(d_reverse > 0.1);
+        // reversing a graph swaps OUT and IN signatures
+        let d_swap = jaccard_distance(
+            out_star.get_out_sketch().as_slice().unwrap(),
+            in_star.get_in_sketch().as_slice().unwrap(),
+        );
+        assert!(d_swap < 0.05);
+        // concatenation gives the same distance
+        let d_concat = jaccard_distance(
+            out_star.get_concatenated().as_slice().unwrap(),
+            in_star.get_concatenated().as_slice().unwrap(),
+        );
+        assert!((d_concat - d_reverse).abs() < 1.0e-10);
+    } // end of test_global_sketch_asym_stars
+} // end of mod tests
@@ -56,6 +56,21 @@ where
     pub fn new(ranktoidx: IndexMap<u32, NodeIndex>, relabel: IndexMap<ToLabel, Label>) -> Self {
         IdMap { ranktoidx, relabel }
     }
+
+    /// returns the number of relabelled labels
+    pub fn get_nb_labels(&self) -> usize {
+        self.relabel.len()
+    }
+
+    /// returns the map from node rank in datafile to NodeIndex
+    pub fn get_ranktoidx(&self) -> &IndexMap<u32, NodeIndex> {
+        &self.ranktoidx
+    }
+
+    /// returns the map from original labels to discrete labels
+    pub fn get_relabel(&self) -> &IndexMap<ToLabel, Label> {
+        &self.relabel
+    }
 } // end of impl IdMap
 
 impl<ToLabel, Label> IdMapper<ToLabel, Label> for IdMap<ToLabel, Label>
@@ -0,0 +1,521 @@
+//! A generic loader of labeled graphs from csv files.
+//!
+//! The graph is described by 2 files:
+//!
+//! - an edge file, each record is: source node, target node, and optionally an edge label and an edge weight
+//!   in columns given by [CsvFormat]. Edges with no weight column have weight 1.
+//!
+//! - a node label file, each record is: node, label1, label2 ... A node can also appear on many records, labels are accumulated.
+//!   Nodes appearing in the label file and not in the edge file are added to the graph as isolated nodes.
+//!   Nodes of the edge file not in the label file have no label.
+//!
+//! Nodes are identified in files by a u32. Labels (of nodes and edges) are arbitrary strings, they are mapped to a compact discrete type
+//! (u8, u16 ...) by order of first appearance. As the default value of labels is reserved (see [idmap](super::idmap)),
+//! the first label found is mapped to 1, the second to 2 ...
+//! The correspondance between node ids in files and NodeIndex in the graph, and between string node labels and discrete labels
+//! is returned in an [IdMap]. The correspondance for edge labels is returned in an IndexMap.
+
+use anyhow::anyhow;
+
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+
+use csv::ReaderBuilder;
+use indexmap::IndexMap;
+use ndarray::Array2;
+
+use petgraph::EdgeType;
+use petgraph::graph::{Graph, NodeIndex};
+
+use super::idmap::*;
+use super::pgraph::*;
+
+/// A node of a graph loaded by [load_labeled_graph]. It keeps its id in the data file and its labels.
+#[derive(Clone, Debug)]
+pub struct LabeledNode<Nlabel> {
+    /// num of node as given in data file.
+    num: u32,
+    /// associated labels
+    labels: Nweight<Nlabel>,
+}
+
+impl<Nlabel> LabeledNode<Nlabel>
+where
+    Nlabel: LabelT,
+{
+    pub fn new(num: u32, labels: Nweight<Nlabel>) -> Self {
+        LabeledNode { num, labels }
+    }
+
+    /// retrieve original node num (given in datafile)
+    pub fn get_num(&self) -> u32 {
+        self.num
+    }
+
+    /// adds labels not already present, see [add_attribute_labels](super::attributes::add_attribute_labels)
+    pub fn add_labels(&mut self, labels: &[Nlabel]) {
+        self.labels.add_labels(labels);
+    }
+} // end of impl LabeledNode
+
+impl<Nlabel> HasNweight<Nlabel> for LabeledNode<Nlabel>
+where
+    Nlabel: LabelT,
+{
+    fn get_nweight(&self) -> &Nweight<Nlabel> {
+        &self.labels
+    }
+} // end of impl HasNweight
+
+impl<Nlabel> HasNweightMut<Nlabel> for LabeledNode<Nlabel>
+where
+    Nlabel: LabelT,
+{
+    fn get_nweight_mut(&mut self) -> &mut Nweight<Nlabel> {
+        &mut self.labels
+    }
+} // end of impl HasNweightMut
+
+/// An edge of a graph loaded by [load_labeled_graph]. Edge satisfy default (no label, weight 1.)
+pub struct LabeledEdge<Elabel> {
+    labels: Eweight<Elabel>,
+}
+
+impl<Elabel> LabeledEdge<Elabel>
+where
+    Elabel: LabelT,
+{
+    pub fn new(label: Option<Elabel>, weight: f32) -> Self {
+        LabeledEdge {
+            labels: Eweight::new(label, weight),
+        }
+    }
+} // end of impl LabeledEdge
+
+impl<Elabel> Default for LabeledEdge<Elabel>
+where
+    Elabel: LabelT,
+{
+    fn default() -> Self {
+        LabeledEdge {
+            labels: Eweight::default(),
+        }
+    }
+}
+
+impl<Elabel> HasEweight<Elabel> for LabeledEdge<Elabel>
+where
+    Elabel: LabelT,
+{
+    fn get_eweight(&self) -> &Eweight<Elabel> {
+        &self.labels
+    }
+} // end of impl HasEweight
+
+/// The graph returned by [load_labeled_graph]
+pub type LabeledGraph<Nlabel, Elabel, Ty> = Graph<LabeledNode<Nlabel>, LabeledEdge<Elabel>, Ty>;
+
+/// Describes the format of csv files read by [load_labeled_graph]
+#[derive(Copy, Clone, Debug)]
+pub struct CsvFormat {
+    /// delimiter of fields, in both files
+    pub delimiter: u8,
+    /// set to true if both files begin with a header line
+    pub has_headers: bool,
+    /// column (beginning at 0) of edge label in edge file, if any
+    pub edge_label_column: Option<usize>,
+    /// column (beginning at 0) of edge weight in edge file, if any
+    pub weight_column: Option<usize>,
+} // end of struct CsvFormat
+
+impl CsvFormat {
+    /// a format with no header, no edge label nor weight
+    pub fn new(delimiter: u8) -> Self {
+        CsvFormat {
+            delimiter,
+            has_headers: false,
+            edge_label_column: None,
+            weight_column: None,
+        }
+    }
+} // end of impl CsvFormat
+
+// returns the compact label associated to a string label, allocating a new one if necessary
+pub(crate) fn get_or_insert_label<Label>(
+    relabel: &mut IndexMap<String, Label>,
+    label: &str,
+) -> anyhow::Result<Label>
+where
+    Label: LabelT + TryFrom<usize>,
+{
+    if let Some(l) = relabel.get(label) {
+        return Ok(l.clone());
+    }
+    // we skip default label
+    let rank = relabel.len() + 1;
+    match Label::try_from(rank) {
+        Ok(l) => {
+            relabel.insert(label.to_string(), l.clone());
+            Ok(l)
+        }
+        Err(_) => {
+            log::error!("label type too small, cannot store label of rank {rank}");
+            Err(anyhow!(
+                "label type too small, cannot store label of rank {rank}"
+            ))
+        }
+    }
+} // end of get_or_insert_label
+
+fn parse_node(field: &str, nb_record: usize) -> anyhow::Result<u32> {
+    field.trim().parse::<u32>().map_err(|_| {
+        anyhow!(
+            "error decoding node {:?} at record {}",
+            field,
+            nb_record + 1
+        )
+    })
+}
+
+fn open_csv(
+    filepath: &Path,
+    format: &CsvFormat,
+) -> anyhow::Result<csv::Reader<BufReader<std::fs::File>>> {
+    let fileres = OpenOptions::new().read(true).open(filepath);
+    if fileres.is_err() {
+        log::error!(
+            "load_labeled_graph could not open file {:?}",
+            filepath.as_os_str()
+        );
+        return Err(anyhow!(
+            "load_labeled_graph could not open file {}",
+            filepath.display()
+        ));
+    }
+    let rdr = ReaderBuilder::new()
+        .delimiter(format.delimiter)
+        .flexible(true)
+        .has_headers(format.has_headers)
+        .from_reader(BufReader::new(fileres?));
+    Ok(rdr)
+} // end of open_csv
+
+/// loads a labeled graph from an edge file and a node label file (see module documentation for the format of files).
+///
+/// Returns the graph, the [IdMap] giving NodeIndex from node id in files and discrete node labels from string labels,
+/// and the map from string edge labels to discrete edge labels (empty if there is no edge label column).
+/// Ty can be Directed or Undirected. In the Undirected case an edge given twice (in both directions) gives 2 edges in the graph.
+#[allow(clippy::type_complexity)]
+pub fn load_labeled_graph<Nlabel, Elabel, Ty>(
+    edge_file: &Path,
+    label_file: &Path,
+    format: CsvFormat,
+) -> anyhow::Result<(
+    LabeledGraph<Nlabel, Elabel, Ty>,
+    IdMap<String, Nlabel>,
+    IndexMap<String, Elabel>,
+)>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    Elabel: LabelT + TryFrom<usize>,
+    Ty: EdgeType,
+{
+    //
+    log::info!(
+        "load_labeled_graph edges : {:?}, labels : {:?}",
+        edge_file,
+        label_file
+    );
+    // first we read labels
+    let mut node_relabel = IndexMap::<String, Nlabel>::new();
+    let mut nodelabels = IndexMap::<u32, Vec<Nlabel>>::new();
+    let mut rdr = open_csv(label_file, &format)?;
+    for (nb_record, result) in rdr.records().enumerate() {
+        let record = result?;
+        if record.is_empty() {
+            continue;
+        }
+        let node = parse_node(record.get(0).unwrap(), nb_record)?;
+        let labels = nodelabels.entry(node).or_default();
+        for field in record.iter().skip(1) {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            let label = get_or_insert_label(&mut node_relabel, field)?;
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+    log::info!(
+        "nb labeled nodes : {}, nb node labels : {}",
+        nodelabels.len(),
+        node_relabel.len()
+    );
+    // now edges
+    let min_nb_fields = 2
+        .max(format.edge_label_column.map_or(0, |c| c + 1))
+        .max(format.weight_column.map_or(0, |c| c + 1));
+    let mut edge_relabel = IndexMap::<String, Elabel>::new();
+    let mut graph = LabeledGraph::<Nlabel, Elabel, Ty>::with_capacity(nodelabels.len(), 0);
+    // This is to retrieve NodeIndex given original num of node as given in data file
+    let mut nodeset = IndexMap::<u32, NodeIndex>::new();
+    let mut get_node_index = |graph: &mut LabeledGraph<Nlabel, Elabel, Ty>, node: u32| {
+        *nodeset.entry(node).or_insert_with(|| {
+            let labels = nodelabels.get(&node).cloned().unwrap_or_default();
+            graph.add_node(LabeledNode::new(node, Nweight::new(labels)))
+        })
+    };
+    let mut rdr = open_csv(edge_file, &format)?;
+    for (nb_record, result) in rdr.records().enumerate() {
+        let record = result?;
+        if record.len() < min_nb_fields {
+            log::error!(
+                "record {} has {} fields, expecting at least {}",
+                nb_record + 1,
+                record.len(),
+                min_nb_fields
+            );
+            return Err(anyhow!(
+                "record {} has {} fields, expecting at least {}",
+                nb_record + 1,
+                record.len(),
+                min_nb_fields
+            ));
+        }
+        let node1 = get_node_index(&mut graph, parse_node(record.get(0).unwrap(), nb_record)?);
+        let node2 = get_node_index(&mut graph, parse_node(record.get(1).unwrap(), nb_record)?);
+        let label = match format.edge_label_column {
+            Some(c) => Some(get_or_insert_label(
+                &mut edge_relabel,
+                record.get(c).unwrap().trim(),
+            )?),
+            None => None,
+        };
+        let weight = match format.weight_column {
+            Some(c) => {
+                let field = record.get(c).unwrap().trim();
+                field.parse::<f32>().map_err(|_| {
+                    anyhow!(
+                        "error decoding weight {:?} at record {}",
+                        field,
+                        nb_record + 1
+                    )
+                })?
+            }
+            None => 1.,
+        };
+        graph.add_edge(node1, node2, LabeledEdge::new(label, weight));
+    }
+    // isolated labeled nodes
+    let isolated: Vec<u32> = nodelabels.keys().copied().collect();
+    for node in isolated {
+        get_node_index(&mut graph, node);
+    }
+    //
+    log::info!(
+        "load_labeled_graph nb nodes : {}, nb edges : {}, nb edge labels : {}",
+        graph.node_count(),
+        graph.edge_count(),
+        edge_relabel.len()
+    );
+    //
+    let idmap = IdMap::<String, Nlabel>::new(nodeset, node_relabel);
+    Ok((graph, idmap, edge_relabel))
+} // end of load_labeled_graph
+
+/// loads continuous node attributes of a graph loaded by [load_labeled_graph].
+///
+/// Each record of the attribute file is: node, x1, x2 ... xd, all records having the same dimension d.
+/// Returns an array (nb_nodes, d) whose row i contains attributes of node of NodeIndex i, as expected by
+/// [add_attribute_labels](super::attributes::add_attribute_labels).
+/// Nodes not in the graph are ignored, nodes with no record get null attributes.
+pub fn load_node_attributes<Nlabel>(
+    attribute_file: &Path,
+    format: CsvFormat,
+    idmap: &IdMap<String, Nlabel>,
+    nb_nodes: usize,
+) -> anyhow::Result<Array2<f32>>
+where
+    Nlabel: LabelT,
+{
+    //
+    log::info!("load_node_attributes attributes : {:?}", attribute_file);
+    //
+    let mut rows = Vec::<(usize, Vec<f32>)>::new();
+    let mut dim: Option<usize> = None;
+    let mut rdr = open_csv(attribute_file, &format)?;
+    for (nb_record, result) in rdr.records().enumerate() {
+        let record = result?;
+        if record.is_empty() {
+            continue;
+        }
+        let node = parse_node(record.get(0).unwrap(), nb_record)?;
+        let values = record
+            .iter()
+            .skip(1)
+            .map(|field| {
+                field.trim().parse::<f32>().map_err(|_| {
+                    anyhow!(
+                        "error decoding attribute {:?} at record {}",
+                        field,
+                        nb_record + 1
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<f32>>>()?;
+        match dim {
+            None => dim = Some(values.len()),
+            Some(d) if d != values.len() => {
+                log::error!(
+                    "record {} has {} attributes, expecting {}",
+                    nb_record + 1,
+                    values.len(),
+                    d
+                );
+                return Err(anyhow!(
+                    "record {} has {} attributes, expecting {}",
+                    nb_record + 1,
+                    values.len(),
+                    d
+                ));
+            }
+            _ => {}
+        }
+        match idmap.get_nodeindex(node) {
+            Some(idx) => rows.push((idx.index(), values)),
+            None => log::warn!("load_node_attributes node {node} not in graph"),
+        }
+    }
+    //
+    let dim = dim.unwrap_or(0);
+    let mut attributes = Array2::<f32>::zeros((nb_nodes, dim));
+    let mut found = vec![false; nb_nodes];
+    for (idx, values) in rows {
+        if idx >= nb_nodes {
+            return Err(anyhow!(
+                "load_node_attributes node index {idx} not less than nb nodes {nb_nodes}"
+            ));
+        }
+        attributes
+            .row_mut(idx)
+            .assign(&ndarray::ArrayView1::from(&values));
+        found[idx] = true;
+    }
+    let nb_missing = found.iter().filter(|f| !**f).count();
+    if nb_missing > 0 {
+        log::warn!("load_node_attributes {nb_missing} nodes have no attributes");
+    }
+    log::info!(
+        "load_node_attributes nb nodes : {}, dim : {}",
+        nb_nodes,
+        dim
+    );
+    //
+    Ok(attributes)
+} // end of load_node_attributes
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::psketch::MgraphSketch;
+    use crate::embed::tools::edge;
+    use petgraph::Undirected;
+    use std::io::Write;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_load_labeled_graph() {
+        log_init_test();
+        //
+        let dir = std::env::temp_dir();
+        let edge_file = dir.join("test_load_labeled_graph_edges.csv");
+        let label_file = dir.join("test_load_labeled_graph_labels.csv");
+        let mut file = std::fs::File::create(&edge_file).unwrap();
+        writeln!(file, "source,target,relation,weight").unwrap();
+        for line in [
+            "10,20,friend,1.",
+            "20,30,colleague,2.5",
+            "30,10,friend,0.5",
+            "30,40,family,1.",
+        ] {
+            writeln!(file, "{line}").unwrap();
+        }
+        let mut file = std::fs::File::create(&label_file).unwrap();
+        writeln!(file, "node,labels").unwrap();
+        for line in ["10,red,blue", "20,blue", "30,green", "10,green", "50,red"] {
+            writeln!(file, "{line}").unwrap();
+        }
+        //
+        let mut format = CsvFormat::new(b',');
+        format.has_headers = true;
+        format.edge_label_column = Some(2);
+        format.weight_column = Some(3);
+        let (graph, idmap, edge_relabel) =
+            load_labeled_graph::<u8, u8, Undirected>(&edge_file, &label_file, format).unwrap();
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 4);
+        // labels are numbered from 1 by order of appearance
+        assert_eq!(idmap.get_label(String::from("red")), Some(&1));
+        assert_eq!(idmap.get_label(String::from("green")), Some(&3));
+        assert_eq!(edge_relabel.get("family"), Some(&3));
+        let node10 = *idmap.get_nodeindex(10).unwrap();
+        assert_eq!(graph[node10].get_num(), 10);
+        assert_eq!(graph[node10].get_nweight().get_labels(), &[1, 2, 3]);
+        // 40 has no label, 50 is isolated
+        let node40 = *idmap.get_nodeindex(40).unwrap();
+        assert!(graph[node40].get_nweight().get_labels().is_empty());
+        let node50 = *idmap.get_nodeindex(50).unwrap();
+        assert_eq!(graph.neighbors(node50).count(), 0);
+        let edge = graph
+            .find_edge(
+                *idmap.get_nodeindex(20).unwrap(),
+                *idmap.get_nodeindex(30).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(graph[edge].get_eweight().get_label(), Some(&2));
+        assert_eq!(graph[edge].get_eweight().get_weight(), 2.5);
+        // continuous attributes, 40 has none, 60 is not in graph
+        let attribute_file = dir.join("test_load_labeled_graph_attributes.csv");
+        let mut file = std::fs::File::create(&attribute_file).unwrap();
+        writeln!(file, "node,x,y").unwrap();
+        for line in ["10,1.,2.", "20,0.5,-1.", "30,3.,0.", "50,1.,1.", "60,2.,2."] {
+            writeln!(file, "{line}").unwrap();
+        }
+        drop(file);
+        let attributes =
+            load_node_attributes(&attribute_file, format, &idmap, graph.node_count()).unwrap();
+        assert_eq!(attributes.dim(), (5, 2));
+        assert_eq!(attributes[[node10.index(), 1]], 2.);
+        assert_eq!(attributes[[node40.index(), 0]], 0.);
+        // u8 cannot store 300 labels
+        let mut file = std::fs::File::create(&label_file).unwrap();
+        for i in 0..300 {
+            writeln!(file, "{i},label{i}").unwrap();
+        }
+        drop(file);
+        let res = load_labeled_graph::<u8, u8, Undirected>(&edge_file, &label_file, format);
+        assert!(res.is_err());
+        // the graph is ready for sketching
+        let skparams = SketchParams::new(10, 0.1, 2, true, false);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, true);
+        skgraph.compute_embedded().unwrap();
+        let n_embedded = skgraph.get_n_embedded_ref().unwrap();
+        assert_eq!(
+            n_embedded
+                .get_embedded_node(node10.index(), edge::INOUT)
+                .len(),
+            10
+        );
+    } // end of test_load_labeled_graph
+} // end of mod tests
@@ -60,8 +60,26 @@ pub mod psketch;
 /// Defines sketching parameters.
 pub mod params;
 
+/// Global summary of directed graphs keeping IN and OUT directions.
+pub mod globalsketch;
+
 /// Defines translations of labels and ranks between raw data from io and our structures in MgraphSketcher.
 pub mod idmap;
 
+/// A generic loader of labeled graphs from csv files.
+pub mod loader;
+
+/// Discretization of continuous node attributes into labels.
+pub mod attributes;
+
+/// Structural role labels from degrees, cores and triangles, for role embeddings of unlabeled graphs.
+pub mod roles;
+
+/// Reader of graph collections in TU benchmark format and global sketches of graphs.
+pub mod tudataset;
+
+/// Index of global sketches of graphs answering nearest neighbours queries for the Jaccard distance.
+pub mod sketchindex;
+
 /// some utilities to load data examples.  
-mod exio;
\ No newline at end of file
+pub(crate) mod exio;
\ No newline at end of file
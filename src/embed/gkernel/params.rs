@@ -1,9 +1,27 @@
 //!
 //! Sketching Parameters
 //!
+//! Besides the sketch size, decay and number of hops, parameters control how edges scale the propagation of labels.
+//! Each edge contributes to the probminhash weighting of a node with a weight which is the product of :
 //!
+//! - its numeric weight (given by [Eweight]), or 1 if numeric weights are disabled (see [SketchParams::set_edge_weighted]),
+//! - a multiplicative weight attached to its label, 1 by default. Label weights are given by an [EdgeLabelWeights] structure
+//!   stored in the parameters (see [SketchParams::set_edge_label_weights]).
+//!   For typed interaction networks it enables to make "activates" edges count more than "binds" edges.
 //!
-#[derive(Debug, Copy, Clone)]
+//! The weight of an edge scales both the labels of the neighbour and the labels brought by the neighbour sketch (decayed at each hop).
+//! Edges whose label has a null weight are ignored (they do not count in the degree of nodes). Edges are never dropped
+//! because of their numeric weight, so without label weights the adjacency of the graph is used as is.
+//!
+
+use anyhow::anyhow;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::pgraph::{Eweight, LabelT};
+
+#[derive(Debug, Clone)]
 pub struct SketchParams {
     /// size of the sketch or number of hashed values used for representing a node.   
     /// In fact it is the dimension of the embeding.
@@ -16,6 +34,10 @@ pub struct SketchParams {
     pub symetric: bool,
     /// parallel mode
     pub parallel: bool,
+    /// if true (the default) numeric edge weights are used in weighting, else each edge has weight 1.
+    edge_weighted: bool,
+    /// multiplicative weights of edge labels, None if not given. Shared between clones of parameters.
+    edge_label_weights: Option<Arc<EdgeLabelWeights>>,
 } // end of NodeSketchParams
 
 impl SketchParams {
@@ -32,10 +54,12 @@ impl SketchParams {
             nb_iter,
             symetric,
             parallel,
+            edge_weighted: true,
+            edge_label_weights: None,
         }
     }
     //
-    pub fn get_decay_weight(self) -> f64 {
+    pub fn get_decay_weight(&self) -> f64 {
         self.decay
     }
 
@@ -65,4 +89,76 @@ impl SketchParams {
     pub fn set_symetry(&mut self, symetry: bool) {
         self.symetric = symetry
     }
+
+    /// if edge_weighted is false numeric edge weights are ignored and each edge has weight 1 (times the weight of its label)
+    pub fn set_edge_weighted(&mut self, edge_weighted: bool) {
+        self.edge_weighted = edge_weighted
+    }
+
+    /// returns true if numeric edge weights are used
+    pub fn is_edge_weighted(&self) -> bool {
+        self.edge_weighted
+    }
+
+    /// sets multiplicative weights of edge labels (see [EdgeLabelWeights]).
+    /// Edges whose label has a null weight are ignored.
+    pub fn set_edge_label_weights(&mut self, edge_label_weights: EdgeLabelWeights) {
+        self.edge_label_weights = Some(Arc::new(edge_label_weights))
+    }
+
+    /// returns multiplicative weights of edge labels if given
+    pub fn get_edge_label_weights(&self) -> Option<&EdgeLabelWeights> {
+        self.edge_label_weights.as_deref()
+    }
+
+    /// returns the numeric part of the weight of an edge in sketching : its weight if edges are weighted, else 1.
+    pub fn get_edge_weight<Elabel: LabelT>(&self, eweight: &Eweight<Elabel>) -> f32 {
+        if self.edge_weighted {
+            eweight.get_weight()
+        } else {
+            1.
+        }
+    } // end of get_edge_weight
 } // end of SketchParams
+
+/// Multiplicative weights of edge labels used in sketching (see the module documentation).
+///
+/// Labels are keyed by their signature (see [LabelT]), labels without a weight have weight 1.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeLabelWeights {
+    weights: HashMap<Vec<u8>, f32>,
+} // end of EdgeLabelWeights
+
+impl EdgeLabelWeights {
+    pub fn new() -> Self {
+        EdgeLabelWeights {
+            weights: HashMap::new(),
+        }
+    }
+
+    /// sets the multiplicative weight of edges with label label.
+    /// For a graph loaded by [load_labeled_graph](super::loader::load_labeled_graph) the discrete label corresponding
+    /// to a label in the file is given by the edge relabelling map.
+    /// The weight must be non negative, edges with a null weight are ignored.
+    pub fn set_weight<Elabel: LabelT>(
+        &mut self,
+        label: &Elabel,
+        weight: f32,
+    ) -> anyhow::Result<()> {
+        if !weight.is_finite() || weight < 0. {
+            return Err(anyhow!(
+                "EdgeLabelWeights::set_weight weight must be non negative, got {weight} for label {label:?}"
+            ));
+        }
+        self.weights.insert(label.get_sig(), weight);
+        Ok(())
+    } // end of set_weight
+
+    /// returns the multiplicative weight of edges with label label, 1 if not set
+    pub fn get_weight<Elabel: LabelT>(&self, label: &Elabel) -> f32 {
+        if self.weights.is_empty() {
+            return 1.;
+        }
+        self.weights.get(&label.get_sig()).copied().unwrap_or(1.)
+    }
+} // end of impl EdgeLabelWeights
@@ -11,13 +11,22 @@ use std::hash::Hash;
 // use std::fmt::Display;
 
 use probminhash::probminhasher::*;
+use serde::{Deserialize, Serialize};
 /// Our labels must satisfy this trait.
 ///
 /// - For having String as possible labels we need Clone.
 ///
 /// - To hash strings or Vectors with sha2 crate we must be able to associate to labels something statisfying a Vec\<u8\>.  
 ///   This is provided by Sig (and is required by Probminhash3sha which do not need copy on items hashed)
-pub trait LabelT: Send + Sync + Eq + Hash + Clone + Default + std::fmt::Debug + sig::Sig {}
+///
+/// - Labels must be 'static. Embeddings of labeled graphs store their distance as a shared trait object
+///   ([DistanceRef](crate::embedding::DistanceRef)), and the Jaccard distance between label vectors is a 'static object
+///   only if the label type is. Requiring it here avoids repeating the bound on all sketching functions
+///   and does not exclude usual labels (integers, String, or couples of labels as in NElabel), which are owned types.
+pub trait LabelT:
+    Send + Sync + Eq + Hash + Clone + Default + std::fmt::Debug + sig::Sig + 'static
+{
+}
 
 impl LabelT for u8 {}
 impl LabelT for u16 {}
@@ -29,7 +38,7 @@ impl LabelT for String {}
 
 /// A label type encoding a couple of Node label and edge label representing a transiiton from/to a node via a labelled edge
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct NElabel<Nlabel, Elabel>(pub(crate) Nlabel, pub(crate) Elabel);
 
 impl<Nlabel, Elabel> sig::Sig for NElabel<Nlabel, Elabel>
@@ -68,6 +77,20 @@ where
     pub fn get_labels(&self) -> &[Nlabel] {
         &self.labels
     }
+
+    /// removes all labels and returns them
+    pub fn take_labels(&mut self) -> Vec<Nlabel> {
+        std::mem::take(&mut self.labels)
+    }
+
+    /// adds labels not already present
+    pub fn add_labels(&mut self, labels: &[Nlabel]) {
+        for label in labels {
+            if !self.has_label(label) {
+                self.labels.push(label.clone());
+            }
+        }
+    }
 } // end of Nweight
 
 //===================================================================================
@@ -121,6 +144,12 @@ pub trait HasNweight<Nlabel: LabelT> {
     fn get_nweight(&self) -> &Nweight<Nlabel>;
 }
 
+/// A node structure whose labels can be modified, for example to hide labels before a prediction
+/// (see [labelprediction](crate::validation::labelprediction)).
+pub trait HasNweightMut<Nlabel: LabelT>: HasNweight<Nlabel> {
+    fn get_nweight_mut(&mut self) -> &mut Nweight<Nlabel>;
+}
+
 /// A structure defining an edge must implement this trait. See examples in gkernel::exio
 pub trait HasEweight<Elabel: LabelT> {
     fn get_eweight(&self) -> &Eweight<Elabel>;
@@ -26,10 +26,8 @@ use ndarray::{Array1, Array2};
 use cpu_time::ProcessTime;
 use std::time::SystemTime;
 
-use petgraph::graph::{Graph, IndexType};
-use petgraph::stable_graph::{DefaultIx, NodeIndex};
+use petgraph::Direction;
 use petgraph::visit::*;
-use petgraph::{Directed, Direction, EdgeType};
 
 use probminhash::probminhasher::*;
 use std::collections::HashMap;
@@ -37,6 +35,7 @@ use std::collections::HashMap;
 use crate::embed::tools::edge::EdgeDir;
 use crate::embed::tools::{edge, jaccard};
 
+use super::globalsketch::GlobalSketchAsym;
 use super::params::*;
 use super::pgraph::*;
 use crate::embedding::*;
@@ -252,32 +251,31 @@ where
 
 //==========================================================================
 
+/// for each node rank, list of edges with rank of neighbour and weight of edge in sketching
+type Adjacency<EdgeRef> = Vec<Vec<(usize, EdgeRef, f32)>>;
+
 /// This structure provides sketching for symetric and asymetric labeled graph.  
-/// NodeData and EdgeData are Weights attached to Node and Edge in the petgraph terminology.  
+/// The graph is accessed through the petgraph visit traits, so G can be a reference on a petgraph Graph, StableGraph, Csr ...  
+/// NodeWeight and EdgeWeight of G are Weights attached to Node and Edge in the petgraph terminology.  
 /// For our sketching these attached data must satisfy traits (HasNweight)[HasNweight] and (HasEweight)[HasEweight].  
-/// Labels can be attributed to node and edges
-pub(crate) struct MgraphSketcher<
-    'a,
-    Nlabel,
-    Elabel,
-    NodeData,
-    EdgeData,
-    Ty = Directed,
-    Ix = DefaultIx,
-> where
+/// Labels can be attributed to node and edges.  
+/// Self loop augmentation is done virtually: each node is processed as its own neighbour, the graph is never modified.
+pub(crate) struct MgraphSketcher<Nlabel, Elabel, G>
+where
     Nlabel: LabelT,
     Elabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: HasEweight<Elabel> + Send + Sync,
+    G: IntoNodeReferences + IntoEdgeReferences,
 {
-    //
-    graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
+    /// node references indexed by rank (NodeIndexable::to_index), None for holes of the index space
+    nodes: Vec<Option<G::NodeRef>>,
+    /// for each node rank, outgoing edges (all incident edges in the undirected case) with rank of neighbour and weight
+    out_edges: Adjacency<G::EdgeRef>,
+    /// for each node rank, incoming edges with rank of neighbour and weight. None in the undirected case
+    in_edges: Option<Adjacency<G::EdgeRef>>,
     /// sketching parameters
     sk_params: SketchParams,
     /// true if graph has labelled edges
     has_edge_labels: bool,
-    /// has single loop augmentation been done ?
-    is_sla: bool,
     //
     symetric_transition: Option<SketchTransition<Nlabel, Elabel>>,
     //
@@ -286,26 +284,67 @@ pub(crate) struct MgraphSketcher<
     parallel: bool,
 } // end of struct MgraphSketcher
 
-impl<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
-    MgraphSketcher<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
+impl<Nlabel, Elabel, G> MgraphSketcher<Nlabel, Elabel, G>
 where
     Elabel: LabelT,
     Nlabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: Default + HasEweight<Elabel> + Send + Sync,
-    Ty: EdgeType + Send + Sync,
-    Ix: IndexType + Send + Sync,
+    G: IntoEdges + IntoNodeReferences + NodeIndexable + GraphProp,
+    G::NodeWeight: HasNweight<Nlabel> + Send + Sync,
+    G::EdgeWeight: HasEweight<Elabel> + Send + Sync,
+    G::NodeRef: Send + Sync,
+    G::EdgeRef: Send + Sync,
 {
     /// allocation
-    fn new(
-        graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
-        params: SketchParams,
-        has_edge_labels: bool,
-        parallel: bool,
-    ) -> Self {
-        // allocation of nodeindex
-        let nb_nodes = graph.node_count();
+    fn new(graph: G, params: SketchParams, has_edge_labels: bool, parallel: bool) -> Self {
+        // sketches are indexed by node rank, we need node_bound ranks
+        let nb_nodes = graph.node_bound();
         let nb_sketch = params.get_sketch_size();
+        // collect node references and adjacency lists once, with weights of edges given by params and label weights.
+        // Edges whose label has a null weight are dropped
+        let mut nodes: Vec<Option<G::NodeRef>> = vec![None; nb_nodes];
+        for node in graph.node_references() {
+            nodes[graph.to_index(node.id())] = Some(node);
+        }
+        let edge_weight = |eweight: &Eweight<Elabel>| -> Option<f32> {
+            let weight = params.get_edge_weight(eweight);
+            match (params.get_edge_label_weights(), eweight.get_label()) {
+                (Some(label_weights), Some(label)) => {
+                    let label_weight = label_weights.get_weight(label);
+                    (label_weight > 0.).then_some(weight * label_weight)
+                }
+                _ => Some(weight),
+            }
+        };
+        let mut out_edges: Adjacency<G::EdgeRef> = vec![Vec::new(); nb_nodes];
+        for node in graph.node_identifiers() {
+            let rank = graph.to_index(node);
+            // edges(node) gives outgoing edges in the directed case, and each incident edge once in the undirected case
+            for edge in graph.edges(node) {
+                let neighbour = if edge.source() == node {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                if let Some(weight) = edge_weight(edge.weight().get_eweight()) {
+                    out_edges[rank].push((graph.to_index(neighbour), edge, weight));
+                }
+            }
+        }
+        let in_edges = if graph.is_directed() {
+            let mut in_edges: Adjacency<G::EdgeRef> = vec![Vec::new(); nb_nodes];
+            for edge in graph.edge_references() {
+                if let Some(weight) = edge_weight(edge.weight().get_eweight()) {
+                    in_edges[graph.to_index(edge.target())].push((
+                        graph.to_index(edge.source()),
+                        edge,
+                        weight,
+                    ));
+                }
+            }
+            Some(in_edges)
+        } else {
+            None
+        };
         // first initialization of previous sketches
         let symetric_transition: Option<SketchTransition<Nlabel, Elabel>>;
         let asymetric_transition: Option<AsymetricTransition<Nlabel, Elabel>>;
@@ -328,16 +367,48 @@ where
         log::debug!("allocation a MgraphSketcher structure");
         //
         MgraphSketcher {
-            graph,
+            nodes,
+            out_edges,
+            in_edges,
             sk_params: params,
             has_edge_labels,
-            is_sla: false,
             symetric_transition,
             asymetric_transition,
             parallel,
         }
     } // end of new
 
+    /// returns true if graph is directed
+    fn is_directed(&self) -> bool {
+        self.in_edges.is_some()
+    }
+
+    /// returns edges of node of rank ndix in direction dir, with rank of neighbour and weight.
+    /// In the undirected case both directions give all incident edges as petgraph's edges_directed does.
+    fn get_edges(&self, ndix: usize, dir: Direction) -> &[(usize, G::EdgeRef, f32)] {
+        match (dir, self.in_edges.as_ref()) {
+            (Direction::Incoming, Some(in_edges)) => &in_edges[ndix],
+            _ => &self.out_edges[ndix],
+        }
+    } // end of get_edges
+
+    /// returns ranks of nodes of the graph
+    fn get_node_ranks(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].is_some())
+            .collect()
+    }
+
+    /// returns node labels of node of rank ndix
+    fn get_node_labels(&self, ndix: usize) -> &[Nlabel] {
+        self.nodes[ndix]
+            .as_ref()
+            .unwrap()
+            .weight()
+            .get_nweight()
+            .get_labels()
+    }
+
     /// check if graph has edge labels to avoid useless computations
     #[allow(unused)]
     pub(crate) fn graph_has_elabels(&self) -> bool {
@@ -461,7 +532,6 @@ where
         let cpu_start = ProcessTime::now();
         let sys_start = SystemTime::now();
         //
-        self.self_loop_augmentation();
         // symetric or asymetric embedding
         let nb_iter = self.sk_params.get_nb_iter();
         // iterations loop
@@ -492,41 +562,19 @@ where
         Ok(1)
     } // end of compute_embedded
 
-    // TODO This function is the only reason why we need &mut graph in struct!!
-    /// updte sketches from previous sketches
-    fn self_loop_augmentation(&mut self) {
-        // WE MUST NOT FORGET Self Loop Augmentation
-        log::debug!("in MgraphSketcher::self_loop_augmentation");
-        // loop on all nodeindex
-        if !self.is_sla {
-            let node_indices = &mut self.graph.node_indices();
-            while let Some(idx) = node_indices.next() {
-                // TODO recall that default label for edge is None!
-                self.graph.add_edge(idx, idx, EdgeData::default());
-            }
-        }
-        self.is_sla = true;
-    } // end of self_loop_augmentation
-
-    /// returns true if graph is self loop augmented.
-    #[allow(unused)]
-    pub(crate) fn is_sla(&self) -> bool {
-        self.is_sla
-    }
-
     /// serial/parallel symetric iteration on nodes to update sketches
     fn one_iteration_symetric(&self) {
         //
         log::debug!("in one_iteration_symetric");
+        let n_indices = self.get_node_ranks();
         if self.parallel {
-            let n_indices: Vec<NodeIndex<Ix>> = self.graph.node_indices().collect();
             n_indices
                 .into_par_iter()
-                .for_each(|ndix| self.treat_node_symetric(&ndix));
+                .for_each(|ndix| self.treat_node_symetric(ndix));
         } else {
-            self.graph
-                .node_indices()
-                .for_each(|ndix| self.treat_node_symetric(&ndix));
+            n_indices
+                .into_iter()
+                .for_each(|ndix| self.treat_node_symetric(ndix));
         }
         // one all nodes have been treated we must do the current to previous iteration
         self.symetric_transition
@@ -536,11 +584,13 @@ where
     } // end one_iteration_symetric
 
     // Building block for hash updating.
-    // This function takes a node given by its nodeindex and a direction (Incoming or Outgoing) and process edges in the given direction
+    // This function takes a node given by its rank and a direction (Incoming or Outgoing) and process edges in the given direction
     // h_label_n stores hashed values of nodes labels, h_label_e stores labels of edge labels
+    // WE MUST NOT FORGET Self Loop Augmentation : the node is processed first as its own neighbour
+    // through a virtual loop of weight 1 without label (as a default edge), so the graph needs not be modified.
     fn process_node_edges_labels(
         &self,
-        ndix: &NodeIndex<Ix>,
+        ndix: usize,
         dir: Direction,
         h_label_n: &mut HashMap<Nlabel, f64, ahash::RandomState>,
         h_label_ne: &mut HashMap<NElabel<Nlabel, Elabel>, f64, ahash::RandomState>,
@@ -548,171 +598,180 @@ where
         //
         log::trace!(
             "in process_node_edges_labels for node : {}, dir : {:?}",
-            ndix.index(),
+            ndix,
             dir
         );
         //
-        let mut degree = 0usize;
-        let edges = self.graph.edges_directed(*ndix, dir);
-        for edge in edges {
+        let mut degree = 1usize;
+        self.process_neighbour(ndix, ndix, dir, 1., None, h_label_n, h_label_ne);
+        for (neighbour_idx, edge, weight) in self.get_edges(ndix, dir) {
             degree += 1;
-            // get node and weight attribute, it is brought with the weight connection from row to neighbour
-            let e_weight = edge.weight(); // This is petgraph's weight
-            let edge_weight = e_weight.get_eweight(); // This is our Eweight gathering label and f32 weight
-            let edge_label = edge_weight.get_label();
-            let neighbour_idx = match dir {
-                Direction::Outgoing => edge.target(),
-                Direction::Incoming => edge.source(),
-            };
-
-            let n_labels = self.graph[neighbour_idx].get_nweight().get_labels();
-            // treatment of h_label_n
-            for label in n_labels {
-                match h_label_n.get_mut(label) {
+            // weight is the edge weight given by sketching parameters, labels are brought with it from neighbour to row
+            let edge_label = edge.weight().get_eweight().get_label();
+            self.process_neighbour(
+                ndix,
+                *neighbour_idx,
+                dir,
+                *weight,
+                edge_label,
+                h_label_n,
+                h_label_ne,
+            );
+        }
+        // degree counts the virtual self loop, if degree is one the node has no edge in this direction,
+        // it means in fact this node must have null embedding so we reset labels to Label::default
+        if degree == 1 {
+            h_label_n.clear();
+        }
+    } // end of process_node_edges_labels
+
+    // propagates labels and previous sketch of neighbour_idx to node ndix through an edge of weight edge_weight
+    #[allow(clippy::too_many_arguments)]
+    fn process_neighbour(
+        &self,
+        ndix: usize,
+        neighbour_idx: usize,
+        dir: Direction,
+        edge_weight: f32,
+        edge_label: Option<&Elabel>,
+        h_label_n: &mut HashMap<Nlabel, f64, ahash::RandomState>,
+        h_label_ne: &mut HashMap<NElabel<Nlabel, Elabel>, f64, ahash::RandomState>,
+    ) {
+        let n_labels = self.get_node_labels(neighbour_idx);
+        // treatment of h_label_n
+        for label in n_labels {
+            match h_label_n.get_mut(label) {
+                Some(val) => {
+                    *val += edge_weight as f64;
+                    log::trace!(
+                        "{:?} augmenting weight hashed node labels for neighbour {:?},  new weight {:.3e}",
+                        ndix,
+                        neighbour_idx,
+                        *val
+                    );
+                }
+                None => {
+                    // we add edge info in h_label_n
+                    log::trace!(
+                        "adding node in hashed node labels {:?}  label : {:?}, weight {:.3e}",
+                        neighbour_idx,
+                        label,
+                        edge_weight
+                    );
+                    h_label_n.insert(label.clone(), edge_weight as f64);
+                }
+            } // end match
+            // treat transition via of couples of labels (node_label , edge_label)
+            if let Some(edge_label) = edge_label {
+                let ne_label = NElabel(label.clone(), edge_label.clone());
+                match h_label_ne.get_mut(&ne_label) {
                     Some(val) => {
-                        *val += edge_weight.get_weight() as f64;
+                        *val += edge_weight as f64;
                         log::trace!(
-                            "{:?} augmenting weight hashed node labels for neighbour {:?},  new weight {:.3e}",
-                            *ndix,
+                            "{:?} augmenting weight hashed node labels for neighbour {:?}, via edge label {:?} ,  new weight {:.3e}",
+                            ndix,
                             neighbour_idx,
+                            *edge_label,
                             *val
                         );
                     }
                     None => {
                         // we add edge info in h_label_n
                         log::trace!(
-                            "adding node in hashed node labels {:?}  label : {:?}, weight {:.3e}",
+                            "adding node hashed (node,edge) labels {:?}  n_label : {:?}, e_label : {:?} weight {:.3e}",
                             neighbour_idx,
                             label,
-                            e_weight.get_eweight().get_weight()
+                            *edge_label,
+                            edge_weight
                         );
-                        h_label_n.insert(label.clone(), edge_weight.get_weight() as f64);
-                    }
-                } // end match
-                // treat transition via of couples of labels (node_label , edge_label)
-                if edge_label.is_some() {
-                    let ne_label = NElabel(label.clone(), edge_label.unwrap().clone());
-                    match h_label_ne.get_mut(&ne_label) {
-                        Some(val) => {
-                            *val += edge_weight.get_weight() as f64;
-                            log::trace!(
-                                "{:?} augmenting weight hashed node labels for neighbour {:?}, via edge label {:?} ,  new weight {:.3e}",
-                                *ndix,
-                                neighbour_idx,
-                                *edge_label.unwrap(),
-                                *val
-                            );
-                        }
-                        None => {
-                            // we add edge info in h_label_n
-                            log::trace!(
-                                "adding node hashed (node,edge) labels {:?}  n_label : {:?}, e_label : {:?} weight {:.3e}",
-                                neighbour_idx,
-                                label,
-                                *edge_label.unwrap(),
-                                edge_weight.get_weight()
-                            );
-                            h_label_ne.insert(ne_label, edge_weight.get_weight() as f64);
-                        }
+                        h_label_ne.insert(ne_label, edge_weight as f64);
                     }
                 }
-            } // end of for on nodes labels
-            //
-            // get component due to previous sketch of current neighbour
-            //
-            // we must get node label of neighbour and edge label, first we process nodes labels
-            let hop_weight = self.sk_params.get_decay_weight() / self.get_sketch_size() as f64;
-            // Problem weight of each label? do we renormalize by number of labels, or the weight of the node
-            // will be proportional to the number of its labels??
-            let dir = edgedir_from_petgraph_dir(dir);
-            let neighbour_sketch = &self.get_previous_sketch_node(neighbour_idx.index(), dir);
-            if neighbour_sketch.is_none() {
-                log::error!(
-                    "process_node_edges_labels cannot get previous sketch for neighbour index : {} , dir : {:?}",
-                    neighbour_idx.index(),
-                    dir
-                );
-                std::panic!();
             }
-            // we take previous sketches and we propagate them to our new Nlabel and Elabel hashmap applying hop_weight
-            let neighbour_sketch_n = &*neighbour_sketch.unwrap().get_n_sketch().read();
-            for sketch_n in neighbour_sketch_n {
-                // something (here sketch_n) in a neighbour sketch is brought with the weight connection from neighbour  ndix to ndix multiplied by the decay factor
-                match h_label_n.get_mut(sketch_n) {
+        } // end of for on nodes labels
+        //
+        // get component due to previous sketch of current neighbour
+        //
+        // we must get node label of neighbour and edge label, first we process nodes labels
+        let hop_weight = self.sk_params.get_decay_weight() / self.get_sketch_size() as f64;
+        // Problem weight of each label? do we renormalize by number of labels, or the weight of the node
+        // will be proportional to the number of its labels??
+        let dir = edgedir_from_petgraph_dir(dir);
+        let neighbour_sketch = &self.get_previous_sketch_node(neighbour_idx, dir);
+        if neighbour_sketch.is_none() {
+            log::error!(
+                "process_node_edges_labels cannot get previous sketch for neighbour index : {} , dir : {:?}",
+                neighbour_idx,
+                dir
+            );
+            std::panic!();
+        }
+        // we take previous sketches and we propagate them to our new Nlabel and Elabel hashmap applying hop_weight
+        let neighbour_sketch_n = &*neighbour_sketch.unwrap().get_n_sketch().read();
+        for sketch_n in neighbour_sketch_n {
+            // something (here sketch_n) in a neighbour sketch is brought with the weight connection from neighbour  ndix to ndix multiplied by the decay factor
+            match h_label_n.get_mut(sketch_n) {
+                Some(val) => {
+                    *val += hop_weight * edge_weight as f64;
+                    log::trace!(
+                        "{} sketch augmenting node {} weight in hashmap with decayed edge weight {:.3e} new weight {:.3e}",
+                        neighbour_idx,
+                        ndix,
+                        hop_weight * edge_weight as f64,
+                        *val
+                    );
+                }
+                _ => {
+                    log::trace!(
+                        "{} sketch adding n label {:?} with decayed weight {:.3e}",
+                        neighbour_idx,
+                        sketch_n,
+                        hop_weight * edge_weight as f64
+                    );
+                    h_label_n.insert(sketch_n.clone(), hop_weight * edge_weight as f64);
+                }
+            } // end match
+        }
+        // now we treat transition via couples (node label, edge label)
+        if edge_label.is_some() {
+            let neighbour_sketch_ne = &*neighbour_sketch.unwrap().get_ne_sketch().unwrap().read();
+            for sketch_ne in neighbour_sketch_ne {
+                match h_label_ne.get_mut(sketch_ne) {
                     Some(val) => {
-                        *val += hop_weight * edge_weight.get_weight() as f64;
+                        *val += hop_weight * edge_weight as f64;
                         log::trace!(
-                            "{} sketch augmenting node {} weight in hashmap with decayed edge weight {:.3e} new weight {:.3e}",
-                            neighbour_idx.index(),
-                            ndix.index(),
-                            hop_weight * edge_weight.get_weight() as f64,
+                            "{:?} augmenting weight in edge hash for neighbour {:?},  new weight {:.3e}",
+                            ndix,
+                            neighbour_idx,
                             *val
                         );
                     }
-                    _ => {
+                    None => {
+                        // we add edge info in h_label_e
                         log::trace!(
-                            "{} sketch adding n label {:?} with decayed weight {:.3e}",
-                            neighbour_idx.index(),
-                            sketch_n,
-                            hop_weight * edge_weight.get_weight() as f64
-                        );
-                        h_label_n.insert(
-                            sketch_n.clone(),
-                            hop_weight * edge_weight.get_weight() as f64,
+                            "adding node in hashed edge labels {:?}  label : {:?}, weight {:.3e}",
+                            neighbour_idx,
+                            edge_label,
+                            edge_weight
                         );
+                        h_label_ne.insert(sketch_ne.clone(), hop_weight * edge_weight as f64);
                     }
                 } // end match
-            }
-            // now we treat transition via couples (node label, edge label)
-            if edge_label.is_some() {
-                let neighbour_sketch_ne =
-                    &*neighbour_sketch.unwrap().get_ne_sketch().unwrap().read();
-                for sketch_ne in neighbour_sketch_ne {
-                    match h_label_ne.get_mut(sketch_ne) {
-                        Some(val) => {
-                            *val += hop_weight * edge_weight.get_weight() as f64;
-                            log::trace!(
-                                "{:?} augmenting weight in edge hash for neighbour {:?},  new weight {:.3e}",
-                                *ndix,
-                                neighbour_idx,
-                                *val
-                            );
-                        }
-                        None => {
-                            // we add edge info in h_label_e
-                            log::trace!(
-                                "adding node in hashed edge labels {:?}  label : {:?}, weight {:.3e}",
-                                neighbour_idx,
-                                edge_label,
-                                edge_weight.get_weight()
-                            );
-                            h_label_ne.insert(
-                                sketch_ne.clone(),
-                                hop_weight * edge_weight.get_weight() as f64,
-                            );
-                        }
-                    } // end match
-                } // end loop on sketch_ne
-            }
-        } // end of while
-        // We if we have sla and degree is one, it means in fact this node must have null embedding
-        // so we reset labels to Label::default
-        if degree == 1 && self.is_sla() {
-            h_label_n.clear();
+            } // end loop on sketch_ne
         }
-    } // end of process_node_edges_labels
+    } // end of process_neighbour
 
     // loop on neighbours and sketch
     // We will need two probminhasher : one for Nlabels and one for Elabels
     // In the symetric (undirected) case we must treat both edge target and edge source
-    fn treat_node_symetric(&self, ndix: &NodeIndex<Ix>) {
+    fn treat_node_symetric(&self, ndix: usize) {
         // ndix should correspond to rank in self.sketches (and so to rank in nodes array in petgraph:::grap
         // self.neighbors_undirected give an iterator on all neighbours
         // we must also get labels of edges
         // Graph:edge_endpoints(e) -> 2 NodeIndex from to
         // Graph.edges_directed(nidx) get an iterator over all edges connected to nidx
         //
-        assert!(!self.graph.is_directed());
+        assert!(!self.is_directed());
         // TODO we initialize by first label ??
         let mut h_label_n = HashMap::<Nlabel, f64, ahash::RandomState>::default();
         let mut h_label_ne = HashMap::<NElabel<Nlabel, Elabel>, f64, ahash::RandomState>::default();
@@ -739,7 +798,7 @@ where
         let sketch_n = Array1::from_vec(probminhash3asha_n.get_signature().clone());
         // we set new sketch
         let mut row_write = self
-            .get_current_sketch_node(ndix.index(), EdgeDir::INOUT)
+            .get_current_sketch_node(ndix, EdgeDir::INOUT)
             .unwrap()
             .n_sketch
             .write();
@@ -756,7 +815,7 @@ where
                     .clone(),
             );
             let mut row_write = self
-                .get_current_sketch_node(ndix.index(), EdgeDir::INOUT)
+                .get_current_sketch_node(ndix, EdgeDir::INOUT)
                 .unwrap()
                 .ne_sketch
                 .as_ref()
@@ -769,9 +828,9 @@ where
     } // end of treat_node_symetric
 
     // for asymetric embedding
-    fn treat_node_asymetric(&self, ndix: &NodeIndex<Ix>) {
+    fn treat_node_asymetric(&self, ndix: usize) {
         //
-        assert!(self.graph.is_directed());
+        assert!(self.is_directed());
         //
         // first we treat outgoing edges
         //
@@ -801,7 +860,7 @@ where
         let sketch_n = Array1::from_vec(probminhash3asha_n.get_signature().clone());
         // we set new sketch
         let mut row_write = self
-            .get_current_sketch_node(ndix.index(), EdgeDir::OUT)
+            .get_current_sketch_node(ndix, EdgeDir::OUT)
             .unwrap()
             .n_sketch
             .write();
@@ -819,7 +878,7 @@ where
             );
             // we set new sketch
             let mut row_write = self
-                .get_current_sketch_node(ndix.index(), EdgeDir::OUT)
+                .get_current_sketch_node(ndix, EdgeDir::OUT)
                 .unwrap()
                 .ne_sketch
                 .as_ref()
@@ -857,7 +916,7 @@ where
         let sketch_n = Array1::from_vec(probminhash3asha_n.get_signature().clone());
         // we set new sketch
         let mut row_write = self
-            .get_current_sketch_node(ndix.index(), EdgeDir::IN)
+            .get_current_sketch_node(ndix, EdgeDir::IN)
             .unwrap()
             .n_sketch
             .write();
@@ -875,7 +934,7 @@ where
             );
             // we set new sketch
             let mut row_write = self
-                .get_current_sketch_node(ndix.index(), EdgeDir::IN)
+                .get_current_sketch_node(ndix, EdgeDir::IN)
                 .unwrap()
                 .ne_sketch
                 .as_ref()
@@ -890,17 +949,17 @@ where
     /// serial/parallel symetric iteration on nodes to update sketches
     fn one_iteration_asymetric(&self) {
         //
+        let n_indices = self.get_node_ranks();
         if self.parallel {
             log::debug!("parallel asymetric iteration");
-            let n_indices: Vec<NodeIndex<Ix>> = self.graph.node_indices().collect();
             n_indices
                 .into_par_iter()
-                .for_each(|ndix| self.treat_node_asymetric(&ndix));
+                .for_each(|ndix| self.treat_node_asymetric(ndix));
         } else {
             log::debug!("serial asymetric iteration");
-            self.graph
-                .node_indices()
-                .for_each(|ndix| self.treat_node_asymetric(&ndix));
+            n_indices
+                .into_iter()
+                .for_each(|ndix| self.treat_node_asymetric(ndix));
         }
         // one all nodes have been treated we must do the current to previous iteration
         self.asymetric_transition
@@ -1049,15 +1108,13 @@ where
 //==============================================================================================
 
 /// This structure computes a an embedding from a symetric  graph
-pub struct MgraphSketch<'a, Nlabel, Elabel, NodeData, EdgeData, Ty = Directed, Ix = DefaultIx>
+pub struct MgraphSketch<Nlabel, Elabel, G>
 where
     Nlabel: LabelT,
     Elabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: HasEweight<Elabel> + Send + Sync,
 {
-    //
-    graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
+    /// a reference on the graph, accessed through petgraph visit traits
+    graph: G,
     /// sketching parameters
     sk_params: SketchParams,
     /// true if graph has labelled edges
@@ -1070,22 +1127,19 @@ where
     parallel: bool,
 } // end of struct MgraphSketcher
 
-impl<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
-    MgraphSketch<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
+impl<Nlabel, Elabel, G> MgraphSketch<Nlabel, Elabel, G>
 where
     Elabel: LabelT,
     Nlabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: Default + HasEweight<Elabel> + Send + Sync,
-    Ty: EdgeType + Send + Sync,
-    Ix: IndexType + Send + Sync,
+    G: IntoEdges + IntoNodeReferences + NodeIndexable + GraphProp,
+    G::NodeWeight: HasNweight<Nlabel> + Send + Sync,
+    G::EdgeWeight: HasEweight<Elabel> + Send + Sync,
+    G::NodeRef: Send + Sync,
+    G::EdgeRef: Send + Sync,
 {
-    /// allocation
-    pub fn new(
-        graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
-        params: SketchParams,
-        has_edge_labels: bool,
-    ) -> Self {
+    /// allocation.  
+    /// graph is a reference on a petgraph graph (Graph, StableGraph, Csr ...), it is not modified by the sketching.
+    pub fn new(graph: G, params: SketchParams, has_edge_labels: bool) -> Self {
         //
         let parallel = params.get_parallel();
         //
@@ -1099,7 +1153,6 @@ where
         }
     }
 
-    // TODO &mut is required only beccause of loop augmentation on graph!
     /// Must collect at the end of computation the embedding built on NLabel.  
     /// If Graph has Edge labels, an embedding is built on couples (NLabel, ELabel), We get 2 structures [`Embedded<F>`]
     pub fn compute_embedded(&mut self) -> Result<usize, anyhow::Error> {
@@ -1110,7 +1163,7 @@ where
         //
         let mut graphsketcher = MgraphSketcher::new(
             self.graph,
-            self.sk_params,
+            self.sk_params.clone(),
             self.has_edge_labels,
             self.parallel,
         );
@@ -1221,15 +1274,13 @@ where
 //==============================================================================================
 
 /// This structure computes a an embedding from an saymetric  graph
-pub struct MgraphSketchAsym<'a, Nlabel, Elabel, NodeData, EdgeData, Ty = Directed, Ix = DefaultIx>
+pub struct MgraphSketchAsym<Nlabel, Elabel, G>
 where
     Nlabel: LabelT,
     Elabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: HasEweight<Elabel> + Send + Sync,
 {
-    //
-    graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
+    /// a reference on the graph, accessed through petgraph visit traits
+    graph: G,
     /// sketching parameters
     sk_params: SketchParams,
     /// true if graph has labelled edges
@@ -1242,22 +1293,19 @@ where
     parallel: bool,
 } // end of struct MgraphSketcherAsym
 
-impl<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
-    MgraphSketchAsym<'a, Nlabel, Elabel, NodeData, EdgeData, Ty, Ix>
+impl<Nlabel, Elabel, G> MgraphSketchAsym<Nlabel, Elabel, G>
 where
     Elabel: LabelT,
     Nlabel: LabelT,
-    NodeData: HasNweight<Nlabel> + Send + Sync,
-    EdgeData: Default + HasEweight<Elabel> + Send + Sync,
-    Ty: EdgeType + Send + Sync,
-    Ix: IndexType + Send + Sync,
+    G: IntoEdges + IntoNodeReferences + NodeIndexable + GraphProp,
+    G::NodeWeight: HasNweight<Nlabel> + Send + Sync,
+    G::EdgeWeight: HasEweight<Elabel> + Send + Sync,
+    G::NodeRef: Send + Sync,
+    G::EdgeRef: Send + Sync,
 {
-    /// allocation
-    pub fn new(
-        graph: &'a mut Graph<NodeData, EdgeData, Ty, Ix>,
-        params: SketchParams,
-        has_edge_labels: bool,
-    ) -> Self {
+    /// allocation.  
+    /// graph is a reference on a petgraph graph (Graph, StableGraph, Csr ...), it is not modified by the sketching.
+    pub fn new(graph: G, params: SketchParams, has_edge_labels: bool) -> Self {
         //
         let parallel = params.get_parallel();
         //
@@ -1271,7 +1319,6 @@ where
         }
     }
 
-    // TODO &mut is required only beccause of loop augmentation on graph!
     /// Must collect at the end of computation the embedding built on NLabel.  
     /// If Graph has Edge labels, an embedding is built on couples (NLabel, ELabel), We get 2 structures [`Embedded<F>`]
     pub fn compute_embedded(&mut self) -> Result<usize, anyhow::Error> {
@@ -1282,7 +1329,7 @@ where
         //
         let mut graphsketcher = MgraphSketcher::new(
             self.graph,
-            self.sk_params,
+            self.sk_params.clone(),
             self.has_edge_labels,
             self.parallel,
         );
@@ -1328,38 +1375,21 @@ where
         }
     } // end of get_ne_embedded
 
-    // This function merges a nodes vector of Nlabel into a global graph vector of Nlabel
+    /// This function merges OUT and IN sketches of nodes into a global graph vector of Nlabel.
+    /// As both directions are counted together, a graph and its reverse get the same vector.
+    /// See [get_global_embedded_asym_n](Self::get_global_embedded_asym_n) for a summary keeping directions.
     pub fn get_global_embedded_n(self, size: usize) -> Option<Array1<Nlabel>> {
         // TODO must enforce minimal size
-        // must do OUT and IN
-        let mut hash_label = HashMap::<Nlabel, f64, ahash::RandomState>::default();
-        let n_embedded = self.get_n_embedded_ref().unwrap();
-        let nbnodes = n_embedded.get_nb_nodes();
-        let dim = n_embedded.get_dimension();
-        for dir in [edge::OUT, edge::IN] {
-            for i in 0..nbnodes {
-                let label_v = n_embedded.get_embedded_node(i, dir);
-                for j in 0..dim {
-                    match hash_label.get_mut(&label_v[j]) {
-                        Some(val) => {
-                            *val += 1.;
-                        }
-                        None => {
-                            // we add edge info in hash_label
-                            hash_label.insert(label_v[j].clone(), 1.);
-                        }
-                    } // end match
-                }
-            } // end of for i
-        } // end of for dir
-        // allocate a probminhash
-        let mut probminhash3asha_n = ProbMinHash3aSha::<Nlabel>::new(size, Nlabel::default());
-        probminhash3asha_n.hash_weigthed_hashmap(&hash_label);
-        let global_sketch_n = Array1::from_vec(probminhash3asha_n.get_signature().clone());
-        Some(global_sketch_n)
+        let n_embedded = self.get_n_embedded_ref()?;
+        Some(globalize_sketch_asym(
+            n_embedded,
+            &[edge::OUT, edge::IN],
+            size,
+        ))
     } // end of get_global_embedded_n
 
-    // This function merges a nodes vector of Nlabel into a global graph vector of Nlabel
+    /// This function merges OUT and IN sketches of nodes into a global graph vector of (Nlabel, Elabel).
+    /// As for [get_global_embedded_n](Self::get_global_embedded_n) directions are mixed.
     pub fn get_global_embedded_ne(self, size: usize) -> Option<Array1<NElabel<Nlabel, Elabel>>> {
         // TODO must enforce minimal size
         let ne_embedded = self.get_ne_embedded_ref();
@@ -1367,37 +1397,71 @@ where
             log::error!("MgraphSketchAsym::get_global_embedded_ne has no (node,edge) embedding");
             return None;
         }
-        let ne_embedded = ne_embedded.unwrap();
-        let mut hash_label = HashMap::<NElabel<Nlabel, Elabel>, f64, ahash::RandomState>::default();
-        let nbnodes = ne_embedded.get_nb_nodes();
-        let dim = ne_embedded.get_dimension();
-        for dir in [edge::OUT, edge::IN] {
-            for i in 0..nbnodes {
-                let label_v = ne_embedded.get_embedded_node(i, dir);
-                for j in 0..dim {
-                    match hash_label.get_mut(&label_v[j]) {
-                        Some(val) => {
-                            *val += 1.;
-                        }
-                        None => {
-                            // we add edge info in hash_label
-                            hash_label.insert(label_v[j].clone(), 1.);
-                        }
-                    } // end match
-                }
-            } // end of for i
-        } // end of for dir
-        // allocate a probminhash
-        let mut probminhash3asha_ne =
-            ProbMinHash3aSha::<NElabel<Nlabel, Elabel>>::new(size, NElabel::default());
-        probminhash3asha_ne.hash_weigthed_hashmap(&hash_label);
-        let global_sketch_ne = Array1::from_vec(probminhash3asha_ne.get_signature().clone());
-        Some(global_sketch_ne)
+        Some(globalize_sketch_asym(
+            ne_embedded.unwrap(),
+            &[edge::OUT, edge::IN],
+            size,
+        ))
     } // end of get_global_embedded_ne
+
+    /// Returns a global summary of the graph based on Node labels, keeping OUT and IN directions separated.
+    /// Each signature has size size. See [GlobalSketchAsym] for the semantics and the distance between summaries.
+    pub fn get_global_embedded_asym_n(self, size: usize) -> Option<GlobalSketchAsym<Nlabel>> {
+        let n_embedded = self.get_n_embedded_ref()?;
+        let out_sketch = globalize_sketch_asym(n_embedded, &[edge::OUT], size);
+        let in_sketch = globalize_sketch_asym(n_embedded, &[edge::IN], size);
+        Some(GlobalSketchAsym::new(out_sketch, in_sketch))
+    } // end of get_global_embedded_asym_n
+
+    /// Returns a global summary of the graph based on (Node label, Edge label), keeping OUT and IN directions separated.
+    pub fn get_global_embedded_asym_ne(
+        self,
+        size: usize,
+    ) -> Option<GlobalSketchAsym<NElabel<Nlabel, Elabel>>> {
+        let ne_embedded = self.get_ne_embedded_ref();
+        if ne_embedded.is_none() {
+            log::error!(
+                "MgraphSketchAsym::get_global_embedded_asym_ne has no (node,edge) embedding"
+            );
+            return None;
+        }
+        let ne_embedded = ne_embedded.unwrap();
+        let out_sketch = globalize_sketch_asym(ne_embedded, &[edge::OUT], size);
+        let in_sketch = globalize_sketch_asym(ne_embedded, &[edge::IN], size);
+        Some(GlobalSketchAsym::new(out_sketch, in_sketch))
+    } // end of get_global_embedded_asym_ne
 } // end of impl block
 
 //===============================================================================================
 
+// counts labels found in node sketches of the given directions and hashes counts into a global vector of size size
+fn globalize_sketch_asym<T>(embedded: &EmbeddedAsym<T>, dirs: &[u8], size: usize) -> Array1<T>
+where
+    T: Clone + Eq + std::hash::Hash + Default + std::fmt::Debug + sig::Sig,
+{
+    let mut hash_label = HashMap::<T, f64, ahash::RandomState>::default();
+    let nbnodes = embedded.get_nb_nodes();
+    for dir in dirs {
+        for i in 0..nbnodes {
+            let label_v = embedded.get_embedded_node(i, *dir);
+            for label in label_v.iter() {
+                match hash_label.get_mut(label) {
+                    Some(val) => {
+                        *val += 1.;
+                    }
+                    None => {
+                        hash_label.insert(label.clone(), 1.);
+                    }
+                } // end match
+            }
+        } // end of for i
+    } // end of for dir
+    // allocate a probminhash
+    let mut probminhash3asha = ProbMinHash3aSha::<T>::new(size, T::default());
+    probminhash3asha.hash_weigthed_hashmap(&hash_label);
+    Array1::from_vec(probminhash3asha.get_signature().clone())
+} // end of globalize_sketch_asym
+
 // convert from petgraph direction coding to our EdgeDir
 fn edgedir_from_petgraph_dir(pdir: petgraph::Direction) -> EdgeDir {
     match pdir {
@@ -1415,6 +1479,9 @@ mod tests {
     use crate::embed::gkernel::idmap::*;
 
     use crate::embed::gkernel::exio::{maileu::*, ppisapiens::*};
+    use crate::embed::gkernel::loader::{LabeledEdge, LabeledGraph, LabeledNode};
+    use petgraph::graph::NodeIndex;
+    use petgraph::stable_graph::StableGraph;
 
     const MAILEU_DIR: &str = "/home/jpboth/Data/Graphs/Email-Eu";
     const PPI_DIR: &str = "/home/jpboth/Data/Graphs/PPI";
@@ -1430,12 +1497,12 @@ mod tests {
         log_init_test();
         let res_graph = read_maileu_data(String::from(MAILEU_DIR));
         assert!(res_graph.is_ok());
-        let (mut graph, idmap) = res_graph.unwrap();
+        let (graph, idmap) = res_graph.unwrap();
         //
         const SKETCH_SIZE: usize = 100;
         let skparams = SketchParams::new(SKETCH_SIZE, 0.1, 10, false, false);
         let has_edge_labels = false;
-        let mut skgraph = MgraphSketchAsym::new(&mut graph, skparams, has_edge_labels);
+        let mut skgraph = MgraphSketchAsym::new(&graph, skparams, has_edge_labels);
         //
         let _embedded = skgraph.compute_embedded();
         let n_embedded = skgraph.get_n_embedded_ref();
@@ -1513,12 +1580,12 @@ mod tests {
         log_init_test();
         let res_graph = read_ppi_directed_data(String::from(PPI_DIR));
         assert!(res_graph.is_ok());
-        let (mut graph, idmap) = res_graph.unwrap();
+        let (graph, idmap) = res_graph.unwrap();
         //
         const SKETCH_SIZE: usize = 100;
         let skparams = SketchParams::new(SKETCH_SIZE, 0.1, 10, false, true);
         let has_edge_labels = false;
-        let mut skgraph = MgraphSketchAsym::new(&mut graph, skparams, has_edge_labels);
+        let mut skgraph = MgraphSketchAsym::new(&graph, skparams, has_edge_labels);
         //
         let _embedded = skgraph.compute_embedded();
         let n_embedded = skgraph.get_n_embedded_ref();
@@ -1599,12 +1666,12 @@ mod tests {
         log_init_test();
         let res_graph = read_ppi_undirected_data(String::from(PPI_DIR));
         assert!(res_graph.is_ok());
-        let (mut graph, idmap) = res_graph.unwrap();
+        let (graph, idmap) = res_graph.unwrap();
         //
         const SKETCH_SIZE: usize = 100;
         let skparams = SketchParams::new(SKETCH_SIZE, 0.1, 10, true, true);
         let has_edge_labels = false;
-        let mut skgraph = MgraphSketch::new(&mut graph, skparams, has_edge_labels);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, has_edge_labels);
         //
         let _embedded = skgraph.compute_embedded();
         let n_embedded = skgraph.get_n_embedded_ref();
@@ -1638,4 +1705,95 @@ mod tests {
             global_v
         );
     } // end of test_pgraph_ppi_undirected
+
+    // sketching must not modify the graph and must give the same result on a Graph and a StableGraph
+    #[test]
+    fn test_pgraph_stable_immutable() {
+        log_init_test();
+        // a labeled path 0 - 1 - 2 - 3 - 4 plus chord 1 - 3 and an isolated node 5
+        let mut graph = LabeledGraph::<u8, u8, petgraph::Undirected>::default();
+        let nodes: Vec<NodeIndex> = (0..6)
+            .map(|i| graph.add_node(LabeledNode::new(i, Nweight::new(vec![1 + (i % 3) as u8]))))
+            .collect();
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 4), (1, 3)] {
+            graph.add_edge(nodes[a], nodes[b], LabeledEdge::default());
+        }
+        let nb_edges = graph.edge_count();
+        let stable =
+            StableGraph::from(graph.map(|_, n| n.clone(), |_, _| LabeledEdge::<u8>::default()));
+        //
+        let skparams = SketchParams::new(50, 0.1, 3, true, false);
+        let mut skgraph = MgraphSketch::new(&graph, skparams.clone(), false);
+        skgraph.compute_embedded().unwrap();
+        let embedded = skgraph.get_n_embedded_ref().unwrap().get_embedded().clone();
+        // computing twice gives the same result as no self loop is accumulated
+        skgraph.compute_embedded().unwrap();
+        assert_eq!(
+            &embedded,
+            skgraph.get_n_embedded_ref().unwrap().get_embedded()
+        );
+        assert_eq!(graph.edge_count(), nb_edges);
+        //
+        let mut skstable = MgraphSketch::new(&stable, skparams.clone(), false);
+        skstable.compute_embedded().unwrap();
+        assert_eq!(
+            &embedded,
+            skstable.get_n_embedded_ref().unwrap().get_embedded()
+        );
+        assert_eq!(stable.edge_count(), nb_edges);
+        // isolated node has null embedding
+        assert!(embedded.row(5).iter().all(|l| *l == 0));
+        // a StableGraph with a hole in node indexation
+        let mut stable = stable;
+        stable.remove_node(nodes[0]);
+        let mut skstable = MgraphSketch::new(&stable, skparams, false);
+        skstable.compute_embedded().unwrap();
+        let embedded_hole = skstable.get_n_embedded_ref().unwrap();
+        assert_eq!(embedded_hole.get_nb_nodes(), 6);
+        assert!(embedded_hole.get_embedded().row(4).iter().any(|l| *l != 0));
+    } // end of test_pgraph_stable_immutable
+
+    // numeric edge weights given by sketching parameters and per edge label weights
+    #[test]
+    fn test_pgraph_edge_weights() {
+        log_init_test();
+        // a star, center labelled 1, 3 leaves labelled 2 by edges with label 1 and weight 1,
+        // 3 leaves labelled 3 by edges with label 2 and weight 20
+        let mut graph = LabeledGraph::<u8, u8, petgraph::Undirected>::default();
+        let center = graph.add_node(LabeledNode::new(0, Nweight::new(vec![1])));
+        for i in 1..=6u32 {
+            let (n_label, e_label, weight) = if i <= 3 { (2, 1, 1.) } else { (3, 2, 20.) };
+            let leaf = graph.add_node(LabeledNode::new(i, Nweight::new(vec![n_label])));
+            graph.add_edge(center, leaf, LabeledEdge::new(Some(e_label), weight));
+        }
+        let count = |params: &SketchParams, label: u8| -> usize {
+            let mut skgraph = MgraphSketch::new(&graph, params.clone(), true);
+            skgraph.compute_embedded().unwrap();
+            let embedded = skgraph.get_n_embedded_ref().unwrap();
+            embedded
+                .get_embedded_node(center.index(), edge::INOUT)
+                .iter()
+                .filter(|l| **l == label)
+                .count()
+        };
+        //
+        let mut skparams = SketchParams::new(100, 0.1, 2, true, false);
+        let eweight = Eweight::new(Some(2u8), 20.);
+        assert_eq!(skparams.get_edge_weight(&eweight), 20.);
+        let (weighted_2, weighted_3) = (count(&skparams, 2), count(&skparams, 3));
+        log::info!("weighted edges, label 2 : {weighted_2}, label 3 : {weighted_3}");
+        assert!(weighted_3 > weighted_2);
+        // numeric weights ignored
+        skparams.set_edge_weighted(false);
+        assert_eq!(skparams.get_edge_weight(&eweight), 1.);
+        // edges with label 2 ignored
+        let mut label_weights = EdgeLabelWeights::new();
+        assert!(label_weights.set_weight(&2u8, -1.).is_err());
+        label_weights.set_weight(&2u8, 0.).unwrap();
+        assert_eq!(label_weights.get_weight(&2u8), 0.);
+        assert_eq!(label_weights.get_weight(&1u8), 1.);
+        skparams.set_edge_label_weights(label_weights);
+        assert_eq!(count(&skparams, 3), 0);
+        assert!(count(&skparams, 2) > 0);
+    } // end of test_pgraph_edge_weights
 } // end of mod tests
@@ -0,0 +1,381 @@
+//! Structural role labels.
+//!
+//! Sketching labels propagated through edges gives an embedding of proximity: nodes in the same region of the graph share labels.
+//! To compare nodes by their role (hubs, bridges, periphery ...) even when they are far apart, we derive synthetic labels
+//! from the local structure of each node and run the usual propagation of [MgraphSketch] on them,
+//! in the spirit of struc2vec (Ribeiro-Saverese-Figueiredo 2017) and role2vec (Ahmed et al. 2018).
+//!
+//! Each [RoleFeature] (degree, core number, number of triangles) is bucketed on a logarithmic scale: a value x gets the bucket
+//! 0 if x is 0 and 1 + floor(log2(x)) otherwise (capped at [NB_ROLE_BUCKETS] - 1).
+//! A node gets one label per feature, feature of rank f in the feature list mapping bucket b to label 1 + f * NB_ROLE_BUCKETS + b,
+//! so the 3 features fit in a u8 label.
+//!
+//! The functions run on the compressed matrix given by [csv_to_trimat](crate::io::csv::csv_to_trimat), so unlabeled csv graphs
+//! can be embedded directly with [embed_roles]. Edge weights, edge directions and self loops are ignored.
+
+use anyhow::anyhow;
+
+use sprs::CsMatI;
+
+use petgraph::Undirected;
+use petgraph::graph::NodeIndex;
+
+use super::loader::{LabeledEdge, LabeledGraph, LabeledNode};
+use super::params::SketchParams;
+use super::pgraph::*;
+use super::psketch::MgraphSketch;
+use crate::embed::tools::jaccard;
+use crate::embedding::Embedded;
+use crate::io::csv::NodeIndexation;
+use crate::structure::cores::kcore::{get_adjacency, kcore_from_adjacency};
+
+/// number of logarithmic buckets of each feature
+pub const NB_ROLE_BUCKETS: usize = 32;
+
+/// Structural features from which role labels are derived
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RoleFeature {
+    /// number of neighbours
+    Degree,
+    /// core number in the k-core decomposition, see [kcore_decomposition](crate::structure::cores::kcore::kcore_decomposition)
+    Coreness,
+    /// number of triangles the node belongs to
+    Triangles,
+}
+
+// logarithmic bucket of a count
+fn log_bucket(x: u64) -> usize {
+    if x == 0 {
+        0
+    } else {
+        (1 + x.ilog2() as usize).min(NB_ROLE_BUCKETS - 1)
+    }
+} // end of log_bucket
+
+// counts triangles of each node. Edges are oriented along the degeneracy ordering so each node has at most degeneracy
+// successors and each triangle is found once by intersecting successor lists.
+fn count_triangles(adjacency: &(Vec<usize>, Vec<usize>), ordering: &[usize]) -> Vec<u64> {
+    let nb_nodes = adjacency.0.len() - 1;
+    let mut position = vec![0usize; nb_nodes];
+    for (p, n) in ordering.iter().enumerate() {
+        position[*n] = p;
+    }
+    let successors: Vec<Vec<usize>> = (0..nb_nodes)
+        .map(|i| {
+            let mut succ: Vec<usize> = adjacency.1[adjacency.0[i]..adjacency.0[i + 1]]
+                .iter()
+                .filter(|j| position[**j] > position[i])
+                .copied()
+                .collect();
+            succ.sort_unstable();
+            succ.dedup();
+            succ
+        })
+        .collect();
+    let mut triangles = vec![0u64; nb_nodes];
+    for u in 0..nb_nodes {
+        for v in &successors[u] {
+            // sorted lists, we merge
+            let (su, sv) = (&successors[u], &successors[*v]);
+            let (mut a, mut b) = (0, 0);
+            while a < su.len() && b < sv.len() {
+                if su[a] < sv[b] {
+                    a += 1;
+                } else if sv[b] < su[a] {
+                    b += 1;
+                } else {
+                    triangles[u] += 1;
+                    triangles[*v] += 1;
+                    triangles[su[a]] += 1;
+                    a += 1;
+                    b += 1;
+                }
+            }
+        }
+    }
+    triangles
+} // end of count_triangles
+
+/// computes role labels of each node of the graph given by a (square) csr matrix.
+///
+/// - symetric must be set to true if the matrix stores both i->j and j->i for each edge
+///   (see [kcore_decomposition](crate::structure::cores::kcore::kcore_decomposition)).
+/// - features : the features used, each node gets one label for each feature in this order.
+///
+/// Returns for each node (by rank in the matrix) its labels.
+pub fn get_role_labels<Nlabel, F>(
+    csmat: &CsMatI<F, usize>,
+    symetric: bool,
+    features: &[RoleFeature],
+) -> anyhow::Result<Vec<Vec<Nlabel>>>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    F: Clone + Default,
+{
+    //
+    assert!(csmat.is_csr());
+    assert_eq!(csmat.rows(), csmat.cols());
+    check_role_features::<Nlabel>(features)?;
+    //
+    let adjacency = get_adjacency(csmat, symetric);
+    role_labels_from_adjacency(&adjacency, features)
+} // end of get_role_labels
+
+// checks that features are not empty and that their labels fit in Nlabel
+fn check_role_features<Nlabel>(features: &[RoleFeature]) -> anyhow::Result<()>
+where
+    Nlabel: TryFrom<usize>,
+{
+    if features.is_empty() {
+        return Err(anyhow!("get_role_labels needs at least one feature"));
+    }
+    let last_label = features.len() * NB_ROLE_BUCKETS;
+    if Nlabel::try_from(last_label).is_err() {
+        log::error!("get_role_labels labels 1..={last_label} cannot be stored in label type");
+        return Err(anyhow!(
+            "get_role_labels labels 1..={last_label} cannot be stored in label type"
+        ));
+    }
+    Ok(())
+} // end of check_role_features
+
+// role labels from adjacency lists as returned by get_adjacency
+fn role_labels_from_adjacency<Nlabel>(
+    adjacency: &(Vec<usize>, Vec<usize>),
+    features: &[RoleFeature],
+) -> anyhow::Result<Vec<Vec<Nlabel>>>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+{
+    let nb_nodes = adjacency.0.len() - 1;
+    let need_cores = features
+        .iter()
+        .any(|f| *f == RoleFeature::Coreness || *f == RoleFeature::Triangles);
+    let decomposition = if need_cores {
+        Some(kcore_from_adjacency(adjacency))
+    } else {
+        None
+    };
+    //
+    let mut labels: Vec<Vec<Nlabel>> = vec![Vec::with_capacity(features.len()); nb_nodes];
+    for (f, feature) in features.iter().enumerate() {
+        let values: Vec<u64> = match feature {
+            RoleFeature::Degree => (0..nb_nodes)
+                .map(|i| (adjacency.0[i + 1] - adjacency.0[i]) as u64)
+                .collect(),
+            RoleFeature::Coreness => decomposition
+                .as_ref()
+                .unwrap()
+                .get_cores()
+                .iter()
+                .map(|c| *c as u64)
+                .collect(),
+            RoleFeature::Triangles => count_triangles(
+                adjacency,
+                decomposition.as_ref().unwrap().get_degeneracy_ordering(),
+            ),
+        };
+        for (i, v) in values.into_iter().enumerate() {
+            let label = 1 + f * NB_ROLE_BUCKETS + log_bucket(v);
+            let label = Nlabel::try_from(label)
+                .map_err(|_| anyhow!("role label {label} cannot be stored in label type"))?;
+            labels[i].push(label);
+        }
+    }
+    //
+    Ok(labels)
+} // end of role_labels_from_adjacency
+
+/// builds an undirected [LabeledGraph] with the edges of the matrix and the role labels of nodes (see [get_role_labels]).
+///
+/// nodeindex is the indexation returned by [csv_to_trimat](crate::io::csv::csv_to_trimat), node num of rank i
+/// in the graph is the num of node of rank i in the csv file. Edges have no label and weight 1.
+pub fn role_labeled_graph<Nlabel, Elabel, F>(
+    csmat: &CsMatI<F, usize>,
+    nodeindex: &NodeIndexation<usize>,
+    symetric: bool,
+    features: &[RoleFeature],
+) -> anyhow::Result<LabeledGraph<Nlabel, Elabel, Undirected>>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    Elabel: LabelT,
+    F: Clone + Default,
+{
+    //
+    if nodeindex.len() != csmat.rows() {
+        return Err(anyhow!(
+            "role_labeled_graph got an indexation of {} nodes for {} rows",
+            nodeindex.len(),
+            csmat.rows()
+        ));
+    }
+    assert!(csmat.is_csr());
+    check_role_features::<Nlabel>(features)?;
+    // adjacency is shared by labels computation and graph construction
+    let adjacency = get_adjacency(csmat, symetric);
+    let labels = role_labels_from_adjacency::<Nlabel>(&adjacency, features)?;
+    //
+    let mut graph = LabeledGraph::<Nlabel, Elabel, Undirected>::with_capacity(
+        labels.len(),
+        adjacency.1.len() / 2,
+    );
+    for (i, node_labels) in labels.into_iter().enumerate() {
+        let num = u32::try_from(*nodeindex.get_index(i).unwrap())?;
+        graph.add_node(LabeledNode::new(num, Nweight::new(node_labels)));
+    }
+    // each edge is stored in both adjacency lists, we keep it once
+    for i in 0..csmat.rows() {
+        for j in &adjacency.1[adjacency.0[i]..adjacency.0[i + 1]] {
+            if *j > i {
+                graph.add_edge(
+                    NodeIndex::new(i),
+                    NodeIndex::new(*j),
+                    LabeledEdge::default(),
+                );
+            }
+        }
+    }
+    log::info!(
+        "role_labeled_graph nb nodes : {}, nb edges : {}",
+        graph.node_count(),
+        graph.edge_count()
+    );
+    //
+    Ok(graph)
+} // end of role_labeled_graph
+
+/// computes a structural role embedding of the graph given by a (square) csr matrix.
+///
+/// Role labels are computed with features (see [get_role_labels]) and propagated by [MgraphSketch] with params (in symetric mode).
+/// Nodes are identified by their rank in the matrix. Labels of 3 features fit in u8, Nlabel must be larger if there are more than 7 features.
+pub fn embed_roles<Nlabel, F>(
+    csmat: &CsMatI<F, usize>,
+    symetric: bool,
+    features: &[RoleFeature],
+    params: SketchParams,
+) -> anyhow::Result<Embedded<Nlabel>>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    F: Clone + Default,
+{
+    //
+    let nodeindex: NodeIndexation<usize> = (0..csmat.rows()).collect();
+    let graph = role_labeled_graph::<Nlabel, u8, F>(csmat, &nodeindex, symetric, features)?;
+    let mut params = params;
+    params.set_symetry(true);
+    let mut skgraph = MgraphSketch::new(&graph, params, false);
+    skgraph.compute_embedded()?;
+    let embedded = skgraph
+        .get_n_embedded_ref()
+        .ok_or_else(|| anyhow!("embed_roles could not get node embedding"))?;
+    //
+    Ok(Embedded::new(
+        embedded.get_embedded().clone(),
+        jaccard::jaccard_distance,
+    ))
+} // end of embed_roles
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::csv_to_trimat;
+    use std::io::Write;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 2 stars of 8 leaves with a 4-clique hanging on one leaf, centers are joined by a long path
+    fn write_stars_csv(path: &std::path::Path) {
+        let mut edges = Vec::<(usize, usize)>::new();
+        for (c, first) in [(100, 101), (200, 201)] {
+            for l in first..first + 8 {
+                edges.push((c, l));
+            }
+        }
+        for p in 300..305 {
+            edges.push((p, p + 1));
+        }
+        edges.extend([(101, 300), (305, 201)]);
+        for i in 400..404 {
+            for j in i + 1..404 {
+                edges.push((i, j));
+            }
+        }
+        edges.push((400, 108));
+        let mut file = std::fs::File::create(path).unwrap();
+        for (i, j) in edges {
+            writeln!(file, "{i} {j}").unwrap();
+        }
+    } // end of write_stars_csv
+
+    #[test]
+    fn test_role_labels_stars() {
+        log_init_test();
+        //
+        assert_eq!(log_bucket(0), 0);
+        assert_eq!(log_bucket(1), 1);
+        assert_eq!(log_bucket(7), 3);
+        assert_eq!(log_bucket(8), 4);
+        assert_eq!(log_bucket(u64::MAX), NB_ROLE_BUCKETS - 1);
+        //
+        let path = std::env::temp_dir().join("test_roles_stars.csv");
+        write_stars_csv(&path);
+        let (trimat, nodeindex) = csv_to_trimat::<f64>(&path, false, b' ').unwrap();
+        let csmat: CsMatI<f64, usize> = trimat.to_csr();
+        let rank = |num: usize| nodeindex.get_index_of(&num).unwrap();
+        //
+        let features = [
+            RoleFeature::Degree,
+            RoleFeature::Coreness,
+            RoleFeature::Triangles,
+        ];
+        let labels = get_role_labels::<u8, f64>(&csmat, true, &features).unwrap();
+        let b = NB_ROLE_BUCKETS;
+        // a clique node : degree 3, core 3, 3 triangles
+        assert_eq!(
+            labels[rank(401)],
+            vec![1 + 2, (1 + b + 2) as u8, (1 + 2 * b + 2) as u8]
+        );
+        // a center : degree 8, core 1, no triangle
+        assert_eq!(
+            labels[rank(100)],
+            vec![1 + 4, (1 + b + 1) as u8, (1 + 2 * b) as u8]
+        );
+        // the symetrized matrix gives the same labels
+        let mut directed = sprs::TriMatI::<f64, usize>::new((csmat.rows(), csmat.cols()));
+        for (_, (i, j)) in csmat.iter() {
+            if i < j {
+                directed.add_triplet(i, j, 1.);
+            }
+        }
+        let labels_sym = get_role_labels::<u8, f64>(&directed.to_csr(), false, &features).unwrap();
+        assert_eq!(labels, labels_sym);
+        // too many labels for u8 is an error
+        let many = [RoleFeature::Degree; 8];
+        assert!(get_role_labels::<u8, f64>(&csmat, true, &many).is_err());
+        //
+        let graph = role_labeled_graph::<u8, u8, f64>(&csmat, &nodeindex, true, &features).unwrap();
+        assert_eq!(graph.edge_count(), csmat.nnz() / 2);
+        assert_eq!(graph[NodeIndex::new(rank(200))].get_num(), 200);
+        // centers are far apart but have the same role
+        let params = SketchParams::new(100, 0.1, 2, true, false);
+        let embedded = embed_roles::<u8, f64>(&csmat, true, &features, params.clone()).unwrap();
+        let v = |num: usize| embedded.get_embedded().row(rank(num)).to_vec();
+        let d_centers = jaccard::jaccard_distance(&v(100), &v(200));
+        let d_center_leaf = jaccard::jaccard_distance(&v(100), &v(102));
+        let d_center_path = jaccard::jaccard_distance(&v(100), &v(302));
+        log::info!(
+            "centers : {d_centers:.3e}, center leaf : {d_center_leaf:.3e}, center path : {d_center_path:.3e}"
+        );
+        assert!(d_centers < d_center_leaf);
+        assert!(d_centers < d_center_path);
+        // more features need a larger label type
+        let embedded_many = embed_roles::<u16, f64>(&csmat, true, &many, params).unwrap();
+        assert_eq!(embedded_many.get_embedded().nrows(), csmat.rows());
+    } // end of test_role_labels_stars
+} // end of mod tests
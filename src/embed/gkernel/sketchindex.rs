@@ -0,0 +1,376 @@
+//! Similarity search over a collection of global graph sketches.
+//!
+//! Global sketches of graphs (see [get_global_embedded_n](super::psketch::MgraphSketch::get_global_embedded_n)
+//! or [TuDataset](super::tudataset::TuDataset)) are stored with an identifier for each graph, and [GlobalSketchIndex::search]
+//! returns the graphs nearest to a query sketch for the Jaccard distance.
+//!
+//! Under a number of sketches given at allocation the search is exhaustive (and parallel), above it the index builds a Hnsw structure
+//! (crate hnsw_rs) so that queries stay fast on large collections. The distances returned are always the exact Jaccard distances
+//! of the candidates.
+//!
+//! The index is dumped and reloaded with the bson format of [labelbson](crate::io::labelbson), the Hnsw structure is rebuilt at reload.
+
+use anyhow::anyhow;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
+
+use indexmap::IndexSet;
+use ndarray::Array1;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use hnsw_rs::prelude::*;
+
+use super::pgraph::LabelT;
+use crate::embed::tools::jaccard::jaccard_distance;
+use crate::io::labelbson::{bson_dump_global_sketches_with_ids, bson_load_labels};
+use crate::validation::anndensity::HnswDistance;
+
+/// default number of sketches above which a Hnsw structure is used
+pub const HNSW_THRESHOLD: usize = 5000;
+
+// parameters of the Hnsw structure
+const HNSW_MAX_NB_CONNECTION: usize = 24;
+const HNSW_EF_CONSTRUCTION: usize = 400;
+const HNSW_NB_LAYER: usize = 16;
+
+/// An index of global sketches of graphs, answering k nearest neighbours queries for the Jaccard distance.
+pub struct GlobalSketchIndex<T>
+where
+    T: LabelT,
+{
+    /// identifiers of graphs, rank of identifier is rank of sketch
+    ids: IndexSet<String>,
+    /// sketches, all of same size
+    sketches: Vec<Array1<T>>,
+    /// number of sketches above which hnsw is used
+    hnsw_threshold: usize,
+    /// Hnsw structure, built when the number of sketches reaches hnsw_threshold. Data ids are ranks of sketches
+    hnsw: Option<Hnsw<'static, T, HnswDistance<T>>>,
+} // end of struct GlobalSketchIndex
+
+impl<T> GlobalSketchIndex<T>
+where
+    T: LabelT,
+{
+    /// an empty index. hnsw_threshold is the number of sketches above which a Hnsw structure is used, see [HNSW_THRESHOLD].
+    pub fn new(hnsw_threshold: usize) -> Self {
+        GlobalSketchIndex {
+            ids: IndexSet::new(),
+            sketches: Vec::new(),
+            hnsw_threshold,
+            hnsw: None,
+        }
+    }
+
+    /// an index of sketches with their identifiers (one for each sketch, all different).
+    pub fn from_sketches(
+        ids: Vec<String>,
+        sketches: Vec<Array1<T>>,
+        hnsw_threshold: usize,
+    ) -> anyhow::Result<Self> {
+        //
+        if ids.len() != sketches.len() {
+            return Err(anyhow!(
+                "GlobalSketchIndex got {} ids for {} sketches",
+                ids.len(),
+                sketches.len()
+            ));
+        }
+        let dim = sketches.first().map_or(0, |s| s.len());
+        if sketches.iter().any(|s| s.len() != dim) {
+            return Err(anyhow!(
+                "GlobalSketchIndex sketches must have the same size"
+            ));
+        }
+        let nb_ids = ids.len();
+        let ids: IndexSet<String> = ids.into_iter().collect();
+        if ids.len() != nb_ids {
+            return Err(anyhow!("GlobalSketchIndex graph ids must be all different"));
+        }
+        // sketches are stored in standard layout so that they can be seen as slices
+        let sketches = sketches.into_iter().map(standard_layout).collect();
+        let mut index = GlobalSketchIndex {
+            ids,
+            sketches,
+            hnsw_threshold,
+            hnsw: None,
+        };
+        if index.sketches.len() >= hnsw_threshold {
+            index.build_hnsw();
+        }
+        Ok(index)
+    } // end of from_sketches
+
+    /// returns the number of sketches
+    pub fn get_nb_sketches(&self) -> usize {
+        self.sketches.len()
+    }
+
+    /// returns size of sketches, 0 if index is empty
+    pub fn get_sketch_size(&self) -> usize {
+        self.sketches.first().map_or(0, |s| s.len())
+    }
+
+    /// returns true if queries are answered by the Hnsw structure
+    pub fn uses_hnsw(&self) -> bool {
+        self.hnsw.is_some()
+    }
+
+    /// returns identifier of sketch of rank rank
+    pub fn get_id(&self, rank: usize) -> Option<&str> {
+        self.ids.get_index(rank).map(|id| id.as_str())
+    }
+
+    /// returns rank of sketch of graph id
+    pub fn get_rank(&self, id: &str) -> Option<usize> {
+        self.ids.get_index_of(id)
+    }
+
+    /// returns sketch of rank rank
+    pub fn get_sketch(&self, rank: usize) -> Option<&Array1<T>> {
+        self.sketches.get(rank)
+    }
+
+    /// inserts the sketch of graph id and returns its rank.
+    /// The sketch must have the size of sketches already inserted and id must not be already present.
+    pub fn insert(&mut self, id: &str, sketch: Array1<T>) -> anyhow::Result<usize> {
+        if !self.sketches.is_empty() && sketch.len() != self.get_sketch_size() {
+            return Err(anyhow!(
+                "GlobalSketchIndex::insert sketch of size {}, expecting {}",
+                sketch.len(),
+                self.get_sketch_size()
+            ));
+        }
+        if self.ids.contains(id) {
+            return Err(anyhow!(
+                "GlobalSketchIndex::insert id {} already present",
+                id
+            ));
+        }
+        let rank = self.sketches.len();
+        self.ids.insert(id.to_string());
+        self.sketches.push(standard_layout(sketch));
+        match self.hnsw.as_mut() {
+            Some(hnsw) => {
+                hnsw.set_searching_mode(false);
+                hnsw.insert_slice((as_slice(&self.sketches[rank]), rank));
+                hnsw.set_searching_mode(true);
+            }
+            None => {
+                if self.sketches.len() >= self.hnsw_threshold {
+                    self.build_hnsw();
+                }
+            }
+        }
+        Ok(rank)
+    } // end of insert
+
+    // sketch of rank rank as a slice, sketches are stored in standard layout
+    fn get_slice(&self, rank: usize) -> &[T] {
+        as_slice(&self.sketches[rank])
+    }
+
+    // builds the hnsw structure from all sketches
+    fn build_hnsw(&mut self) {
+        //
+        let cpu_start = ProcessTime::now();
+        let sys_start = SystemTime::now();
+        //
+        let distance = HnswDistance::new(Arc::new(jaccard_distance::<T>));
+        let mut hnsw = Hnsw::<T, HnswDistance<T>>::new(
+            HNSW_MAX_NB_CONNECTION,
+            self.sketches.len(),
+            HNSW_NB_LAYER,
+            HNSW_EF_CONSTRUCTION,
+            distance,
+        );
+        let data_with_id: Vec<(&[T], usize)> = (0..self.sketches.len())
+            .map(|rank| (self.get_slice(rank), rank))
+            .collect();
+        hnsw.parallel_insert_slice(&data_with_id);
+        hnsw.set_searching_mode(true);
+        log::info!(
+            "GlobalSketchIndex built hnsw on {} sketches, sys time(s) {:.2e} cpu time(s) {:.2e}",
+            self.sketches.len(),
+            sys_start.elapsed().unwrap().as_millis() as f64 / 1000.,
+            cpu_start.elapsed().as_secs_f64()
+        );
+        self.hnsw = Some(hnsw);
+    } // end of build_hnsw
+
+    /// returns the (at most) knbn sketches nearest to query as couples (rank, Jaccard distance) sorted by increasing distance.
+    /// Identifiers of graphs are given by [get_id](Self::get_id).
+    pub fn search(&self, query: &Array1<T>, knbn: usize) -> anyhow::Result<Vec<(usize, f64)>> {
+        //
+        if !self.sketches.is_empty() && query.len() != self.get_sketch_size() {
+            return Err(anyhow!(
+                "GlobalSketchIndex::search query of size {}, expecting {}",
+                query.len(),
+                self.get_sketch_size()
+            ));
+        }
+        // query may have any layout
+        let query = query.as_standard_layout();
+        let query = query.as_slice().unwrap_or_default();
+        let mut neighbours: Vec<(usize, f64)> = match &self.hnsw {
+            Some(hnsw) => {
+                let ef_search = (2 * knbn).max(64);
+                hnsw.search(query, knbn, ef_search)
+                    .into_iter()
+                    .map(|n| (n.d_id, jaccard_distance(query, self.get_slice(n.d_id))))
+                    .collect()
+            }
+            None => (0..self.sketches.len())
+                .into_par_iter()
+                .map(|rank| (rank, jaccard_distance(query, self.get_slice(rank))))
+                .collect(),
+        };
+        neighbours.sort_unstable_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        neighbours.truncate(knbn);
+        Ok(neighbours)
+    } // end of search
+} // end of impl GlobalSketchIndex
+
+impl<T> GlobalSketchIndex<T>
+where
+    T: LabelT + Serialize + DeserializeOwned,
+{
+    /// dumps sketches and identifiers in bson format, see [bson_dump_global_sketches_with_ids]
+    pub fn dump(&self, fname: &Path) -> anyhow::Result<()> {
+        let ids: Vec<String> = self.ids.iter().cloned().collect();
+        bson_dump_global_sketches_with_ids(&self.sketches, &ids, fname)
+    }
+
+    /// reloads an index dumped by [dump](Self::dump), T must be the type of labels dumped.
+    pub fn reload(fname: &Path, hnsw_threshold: usize) -> anyhow::Result<Self> {
+        let reloaded = bson_load_labels::<T>(fname)?;
+        let ids = match reloaded.get_graph_ids() {
+            Some(ids) => ids.to_vec(),
+            None => return Err(anyhow!("no graph ids in file {}", fname.display())),
+        };
+        let sketches = reloaded.into_global_sketches()?;
+        Self::from_sketches(ids, sketches, hnsw_threshold)
+    } // end of reload
+} // end of impl GlobalSketchIndex
+
+// returns sketch in standard layout, copying it only if necessary
+fn standard_layout<T: Clone>(sketch: Array1<T>) -> Array1<T> {
+    if sketch.is_standard_layout() {
+        sketch
+    } else {
+        sketch.as_standard_layout().into_owned()
+    }
+}
+
+// slice of a sketch stored by GlobalSketchIndex
+fn as_slice<T>(sketch: &Array1<T>) -> &[T] {
+    sketch
+        .as_slice()
+        .expect("GlobalSketchIndex sketches must be in standard layout")
+}
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use rand::Rng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use rand_xoshiro::rand_core::SeedableRng;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 4 families of sketches, each family perturbs a random sketch at 20% of positions
+    fn family_sketches(nb_by_family: usize, size: usize) -> (Vec<String>, Vec<Array1<u16>>) {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(4567);
+        let mut ids = Vec::<String>::new();
+        let mut sketches = Vec::<Array1<u16>>::new();
+        for f in 0..4 {
+            let base: Vec<u16> = (0..size).map(|_| rng.random_range(1..1000)).collect();
+            for i in 0..nb_by_family {
+                let sketch = base
+                    .iter()
+                    .map(|l| {
+                        if rng.random::<f64>() < 0.2 {
+                            rng.random_range(1..1000)
+                        } else {
+                            *l
+                        }
+                    })
+                    .collect();
+                ids.push(format!("graph_{f}_{i}"));
+                sketches.push(Array1::from_vec(sketch));
+            }
+        }
+        (ids, sketches)
+    } // end of family_sketches
+
+    #[test]
+    fn test_global_sketch_index() {
+        log_init_test();
+        //
+        let (ids, sketches) = family_sketches(50, 100);
+        let query = sketches[10].clone();
+        // exhaustive search
+        let mut index =
+            GlobalSketchIndex::from_sketches(ids.clone(), sketches.clone(), HNSW_THRESHOLD)
+                .unwrap();
+        assert!(!index.uses_hnsw());
+        let exact = index.search(&query, 20).unwrap();
+        assert_eq!(exact.len(), 20);
+        assert_eq!(exact[0], (10, 0.));
+        assert!(exact.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(
+            exact
+                .iter()
+                .all(|(r, _)| index.get_id(*r).unwrap().starts_with("graph_0_"))
+        );
+        // insertion
+        assert!(index.insert("graph_0_0", query.clone()).is_err());
+        assert!(index.insert("new", Array1::from_elem(10, 1u16)).is_err());
+        let rank = index.insert("copy", query.clone()).unwrap();
+        assert_eq!(index.get_rank("copy"), Some(rank));
+        assert_eq!(index.search(&query, 2).unwrap()[1], (rank, 0.));
+        // sketches and queries not in standard layout
+        let mut reversed = query.clone();
+        reversed.invert_axis(ndarray::Axis(0));
+        assert!(!reversed.is_standard_layout());
+        let rev_rank = index.insert("reversed", reversed.clone()).unwrap();
+        assert_eq!(index.search(&reversed, 1).unwrap()[0], (rev_rank, 0.));
+        assert_eq!(index.get_sketch(rev_rank), Some(&reversed));
+        // hnsw search, built from the start or when reaching the threshold
+        let hnsw_index =
+            GlobalSketchIndex::from_sketches(ids.clone(), sketches.clone(), 100).unwrap();
+        assert!(hnsw_index.uses_hnsw());
+        let approx = hnsw_index.search(&query, 20).unwrap();
+        let recall = approx.iter().filter(|n| exact.contains(n)).count();
+        log::info!("hnsw recall : {recall}/20");
+        assert!(recall >= 18);
+        let mut grown = GlobalSketchIndex::new(100);
+        for (id, s) in ids.iter().zip(sketches.iter()) {
+            grown.insert(id, s.clone()).unwrap();
+        }
+        assert!(grown.uses_hnsw());
+        assert_eq!(grown.search(&query, 1).unwrap()[0], (10, 0.));
+        // dump and reload
+        let path = std::env::temp_dir().join("test_global_sketch_index.bson");
+        index.dump(&path).unwrap();
+        let reloaded = GlobalSketchIndex::<u16>::reload(&path, HNSW_THRESHOLD).unwrap();
+        assert_eq!(reloaded.get_nb_sketches(), index.get_nb_sketches());
+        assert_eq!(reloaded.get_id(rank), Some("copy"));
+        assert_eq!(
+            reloaded.search(&query, 20).unwrap(),
+            index.search(&query, 20).unwrap()
+        );
+    } // end of test_global_sketch_index
+} // end of mod tests
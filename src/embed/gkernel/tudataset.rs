@@ -0,0 +1,352 @@
+//! Reader of graph collections in the TU benchmark format <https://chrsmrrs.github.io/datasets/docs/format/>
+//! and computation of a global sketch for each graph of the collection.
+//!
+//! A dataset named DS consists of files in a directory:
+//!
+//! - DS_A.txt : edges of all graphs, one edge *i, j* per line where i and j are (global, starting at 1) node ids.
+//!   Undirected edges are given in both directions.
+//! - DS_graph_indicator.txt : line i gives the graph (starting at 1) node i belongs to.
+//! - DS_graph_labels.txt : line i gives the class of graph i.
+//! - DS_node_labels.txt (optional) : line i gives the label of node i. If the file is absent the degree of a node is used as its label
+//!   as usual in Weisfeiler-Lehman kernels.
+//! - DS_edge_labels.txt (optional) : line i gives the label of the edge of line i in DS_A.txt
+//!
+//! Graphs are loaded as undirected [LabeledGraph], labels being mapped to compact discrete labels as in [load_labeled_graph](super::loader::load_labeled_graph).
+//! The global sketches can then be used for graph classification, see [knn_graph_classification](crate::validation::graphclass::knn_graph_classification).
+
+use anyhow::anyhow;
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use indexmap::IndexMap;
+use ndarray::Array1;
+use rayon::prelude::*;
+
+use petgraph::Undirected;
+use petgraph::graph::NodeIndex;
+
+use super::loader::*;
+use super::params::SketchParams;
+use super::pgraph::*;
+use super::psketch::MgraphSketch;
+
+/// A collection of labeled graphs with a class for each graph.
+pub struct TuDataset<Nlabel, Elabel>
+where
+    Nlabel: LabelT,
+    Elabel: LabelT,
+{
+    /// name of dataset (prefix of files)
+    name: String,
+    /// graphs, nodes of graphs keep their global id in dataset files
+    graphs: Vec<LabeledGraph<Nlabel, Elabel, Undirected>>,
+    /// class of each graph
+    classes: Vec<i64>,
+    /// correspondance between node labels in files and discrete node labels
+    node_relabel: IndexMap<String, Nlabel>,
+    /// correspondance between edge labels in files and discrete edge labels, empty if dataset has no edge labels
+    edge_relabel: IndexMap<String, Elabel>,
+} // end of struct TuDataset
+
+// reads a file with one record per line, returns None if file does not exist
+fn read_lines(filepath: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    if !filepath.exists() {
+        return Ok(None);
+    }
+    let fileres = OpenOptions::new().read(true).open(filepath);
+    if fileres.is_err() {
+        log::error!("TuDataset could not open file {:?}", filepath.as_os_str());
+        return Err(anyhow!(
+            "TuDataset could not open file {}",
+            filepath.display()
+        ));
+    }
+    let mut lines = Vec::<String>::new();
+    for line in BufReader::new(fileres?).lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+    Ok(Some(lines))
+} // end of read_lines
+
+fn parse_id(field: &str, filepath: &Path, line: usize) -> anyhow::Result<usize> {
+    let id = field.trim().parse::<usize>().map_err(|_| {
+        anyhow!(
+            "error decoding {:?} at line {} of {}",
+            field,
+            line + 1,
+            filepath.display()
+        )
+    })?;
+    if id == 0 {
+        return Err(anyhow!(
+            "ids begin at 1, found 0 at line {} of {}",
+            line + 1,
+            filepath.display()
+        ));
+    }
+    Ok(id)
+} // end of parse_id
+
+impl<Nlabel, Elabel> TuDataset<Nlabel, Elabel>
+where
+    Nlabel: LabelT + TryFrom<usize>,
+    Elabel: LabelT + TryFrom<usize>,
+{
+    /// loads dataset of given name from directory dir
+    pub fn load(dir: &Path, name: &str) -> anyhow::Result<Self> {
+        //
+        log::info!("TuDataset::load {} from {:?}", name, dir);
+        let path = |suffix: &str| dir.join(format!("{name}_{suffix}.txt"));
+        let required = |suffix: &str| -> anyhow::Result<Vec<String>> {
+            read_lines(&path(suffix))?.ok_or_else(|| {
+                log::error!("TuDataset::load missing file {:?}", path(suffix));
+                anyhow!("TuDataset::load missing file {}", path(suffix).display())
+            })
+        };
+        // graph of each node
+        let indicator_path = path("graph_indicator");
+        let node_graph = required("graph_indicator")?
+            .iter()
+            .enumerate()
+            .map(|(i, l)| parse_id(l, &indicator_path, i).map(|g| g - 1))
+            .collect::<anyhow::Result<Vec<usize>>>()?;
+        let classes_path = path("graph_labels");
+        let classes = required("graph_labels")?
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                l.parse::<i64>().map_err(|_| {
+                    anyhow!(
+                        "error decoding class {:?} at line {} of {}",
+                        l,
+                        i + 1,
+                        classes_path.display()
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<i64>>>()?;
+        let nb_graphs = classes.len();
+        if let Some(g) = node_graph.iter().find(|g| **g >= nb_graphs) {
+            return Err(anyhow!(
+                "graph indicator refers to graph {} but there are {} graph labels",
+                g + 1,
+                nb_graphs
+            ));
+        }
+        // edges, as couples of node ranks
+        let edges_path = path("A");
+        let mut edges = Vec::<(usize, usize)>::new();
+        for (i, l) in required("A")?.iter().enumerate() {
+            let fields: Vec<&str> = l.split(',').collect();
+            if fields.len() != 2 {
+                return Err(anyhow!(
+                    "expecting 2 fields at line {} of {}",
+                    i + 1,
+                    edges_path.display()
+                ));
+            }
+            let n1 = parse_id(fields[0], &edges_path, i)? - 1;
+            let n2 = parse_id(fields[1], &edges_path, i)? - 1;
+            if n1 >= node_graph.len() || n2 >= node_graph.len() || node_graph[n1] != node_graph[n2]
+            {
+                return Err(anyhow!(
+                    "inconsistent edge at line {} of {}",
+                    i + 1,
+                    edges_path.display()
+                ));
+            }
+            edges.push((n1, n2));
+        }
+        let edge_labels = read_lines(&path("edge_labels"))?;
+        if let Some(labels) = &edge_labels
+            && labels.len() != edges.len()
+        {
+            return Err(anyhow!(
+                "number of edge labels {} different from number of edges {}",
+                labels.len(),
+                edges.len()
+            ));
+        }
+        // node labels, degrees if not given
+        let node_labels = match read_lines(&path("node_labels"))? {
+            Some(labels) => {
+                if labels.len() != node_graph.len() {
+                    return Err(anyhow!(
+                        "number of node labels {} different from number of nodes {}",
+                        labels.len(),
+                        node_graph.len()
+                    ));
+                }
+                labels
+            }
+            None => {
+                log::info!("no node labels, using degrees as labels");
+                let mut degrees = vec![0usize; node_graph.len()];
+                let mut seen = HashSet::<(usize, usize)>::new();
+                for (n1, n2) in &edges {
+                    if seen.insert(((*n1).min(*n2), (*n1).max(*n2))) {
+                        degrees[*n1] += 1;
+                        if n1 != n2 {
+                            degrees[*n2] += 1;
+                        }
+                    }
+                }
+                degrees.iter().map(|d| d.to_string()).collect()
+            }
+        };
+        // now we build graphs
+        let mut node_relabel = IndexMap::<String, Nlabel>::new();
+        let mut edge_relabel = IndexMap::<String, Elabel>::new();
+        let mut graphs: Vec<LabeledGraph<Nlabel, Elabel, Undirected>> =
+            (0..nb_graphs).map(|_| LabeledGraph::default()).collect();
+        let mut node_index = Vec::<NodeIndex>::with_capacity(node_graph.len());
+        for (node, g) in node_graph.iter().enumerate() {
+            let label = get_or_insert_label(&mut node_relabel, &node_labels[node])?;
+            let labels = Nweight::new(vec![label]);
+            node_index.push(graphs[*g].add_node(LabeledNode::new(node as u32 + 1, labels)));
+        }
+        // each undirected edge is kept once
+        let mut seen = HashSet::<(usize, usize)>::new();
+        for (i, (n1, n2)) in edges.iter().enumerate() {
+            if !seen.insert(((*n1).min(*n2), (*n1).max(*n2))) {
+                continue;
+            }
+            let label = match &edge_labels {
+                Some(labels) => Some(get_or_insert_label(&mut edge_relabel, &labels[i])?),
+                None => None,
+            };
+            graphs[node_graph[*n1]].add_edge(
+                node_index[*n1],
+                node_index[*n2],
+                LabeledEdge::new(label, 1.),
+            );
+        }
+        //
+        log::info!(
+            "TuDataset {} nb graphs : {}, nb nodes : {}, nb edges : {}, nb node labels : {}, nb edge labels : {}",
+            name,
+            nb_graphs,
+            node_graph.len(),
+            seen.len(),
+            node_relabel.len(),
+            edge_relabel.len()
+        );
+        //
+        Ok(TuDataset {
+            name: name.to_string(),
+            graphs,
+            classes,
+            node_relabel,
+            edge_relabel,
+        })
+    } // end of load
+} // end of impl TuDataset
+
+impl<Nlabel, Elabel> TuDataset<Nlabel, Elabel>
+where
+    Nlabel: LabelT,
+    Elabel: LabelT,
+{
+    /// returns name of dataset
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// returns number of graphs
+    pub fn get_nb_graphs(&self) -> usize {
+        self.graphs.len()
+    }
+
+    /// returns graph of rank i (graph i+1 in dataset files)
+    pub fn get_graph(&self, i: usize) -> Option<&LabeledGraph<Nlabel, Elabel, Undirected>> {
+        self.graphs.get(i)
+    }
+
+    /// returns class of each graph
+    pub fn get_classes(&self) -> &[i64] {
+        &self.classes
+    }
+
+    /// returns true if dataset has edge labels
+    pub fn has_edge_labels(&self) -> bool {
+        !self.edge_relabel.is_empty()
+    }
+
+    /// returns correspondance between node labels in files and discrete node labels
+    pub fn get_node_relabel(&self) -> &IndexMap<String, Nlabel> {
+        &self.node_relabel
+    }
+
+    /// returns correspondance between edge labels in files and discrete edge labels
+    pub fn get_edge_relabel(&self) -> &IndexMap<String, Elabel> {
+        &self.edge_relabel
+    }
+
+    // sketches each graph in parallel and globalize each graph sketch with function globalize
+    fn get_global_sketches<T, Fun>(
+        &self,
+        params: SketchParams,
+        has_edge_labels: bool,
+        globalize: Fun,
+    ) -> anyhow::Result<Vec<Array1<T>>>
+    where
+        T: Send,
+        Fun: Fn(
+                MgraphSketch<Nlabel, Elabel, &LabeledGraph<Nlabel, Elabel, Undirected>>,
+            ) -> Option<Array1<T>>
+            + Sync,
+    {
+        // graphs are symetric and parallelism is on graphs
+        let mut params = params;
+        params.set_symetry(true);
+        params.parallel = false;
+        self.graphs
+            .par_iter()
+            .enumerate()
+            .map(|(i, graph)| {
+                let mut sketcher = MgraphSketch::new(graph, params.clone(), has_edge_labels);
+                sketcher.compute_embedded()?;
+                globalize(sketcher)
+                    .ok_or_else(|| anyhow!("could not get global sketch of graph {i}"))
+            })
+            .collect()
+    } // end of get_global_sketches
+
+    /// computes for each graph (in parallel) a global sketch of size global_size from the node labels sketches.
+    ///
+    /// Sketching of each graph is done with params (in symetric and non parallel mode), see [MgraphSketch::get_global_embedded_n].
+    /// Edge label weights set in params (see [SketchParams::set_edge_label_weights]) apply to all graphs.
+    pub fn get_global_sketches_n(
+        &self,
+        params: SketchParams,
+        global_size: usize,
+    ) -> anyhow::Result<Vec<Array1<Nlabel>>> {
+        self.get_global_sketches(params, false, |sketcher| {
+            sketcher.get_global_embedded_n(global_size)
+        })
+    } // end of get_global_sketches_n
+
+    /// computes for each graph (in parallel) a global sketch of size global_size from the (node label, edge label) sketches.
+    /// The dataset must have edge labels.
+    ///
+    /// Sketching of each graph is done with params (in symetric and non parallel mode), see [MgraphSketch::get_global_embedded_ne].
+    pub fn get_global_sketches_ne(
+        &self,
+        params: SketchParams,
+        global_size: usize,
+    ) -> anyhow::Result<Vec<Array1<NElabel<Nlabel, Elabel>>>> {
+        if !self.has_edge_labels() {
+            return Err(anyhow!("dataset {} has no edge labels", self.name));
+        }
+        self.get_global_sketches(params, true, |sketcher| {
+            sketcher.get_global_embedded_ne(global_size)
+        })
+    } // end of get_global_sketches_ne
+} // end of impl TuDataset
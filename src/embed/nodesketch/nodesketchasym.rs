@@ -29,7 +29,7 @@ use std::time::SystemTime;
 
 use super::{params::NodeSketchParams, sla::*};
 use crate::embed::tools::degrees::*;
-use crate::embedding::{EmbeddedAsym, EmbedderT};
+use crate::embedding::{AsymDistance, EmbeddedAsym, EmbedderT};
 
 pub type RowSketch = Arc<RwLock<Array1<usize>>>;
 
@@ -52,6 +52,8 @@ pub struct NodeSketchAsym {
     sketches_out: Vec<RowSketch>,
     /// sketches_out state at previous iterations
     previous_sketches_out: Vec<RowSketch>,
+    /// strategy for node dissimilarity in the embedding
+    asym_distance: AsymDistance,
 } // end of struct NodeSketchAsym
 
 impl NodeSketchAsym {
@@ -89,9 +91,16 @@ impl NodeSketchAsym {
             previous_sketches_in,
             sketches_out,
             previous_sketches_out,
+            asym_distance: AsymDistance::default(),
         }
     } // end of for NodeSketchAsym::new
 
+    /// sets the strategy used by the embedding to compute node dissimilarities (default is [AsymDistance::Average]).
+    /// [AsymDistance::DotProduct] is not possible as sketches are not float vectors.
+    pub fn set_asym_distance(&mut self, asym_distance: AsymDistance) {
+        self.asym_distance = asym_distance;
+    }
+
     /// get sketch_size
     pub fn get_sketch_size(&self) -> usize {
         self.params.sketch_size
@@ -447,12 +456,13 @@ impl NodeSketchAsym {
                 embedded_source.row_mut(i)[j] = self.sketches_out[i].read()[j];
             }
         }
-        let embedded = EmbeddedAsym::<usize>::new(
+        let mut embedded = EmbeddedAsym::<usize>::new(
             embedded_source,
             embedded_target,
             Some(self.degrees.clone()),
             crate::embed::tools::jaccard::jaccard_distance,
         );
+        embedded.set_asym_distance(self.asym_distance)?;
         //
         Ok(embedded)
     } // end of compute_Embedded
@@ -4,12 +4,14 @@ use anyhow::anyhow;
 
 use hdrhistogram::Histogram;
 use indexmap::set::IndexSet;
-use rand::distr::{weighted::WeightedIndex, Uniform};
+use rand::distr::{Uniform, weighted::WeightedIndex};
 use sprs::CsMatI;
 use std::collections::HashMap;
 
 use rand::prelude::*;
 
+use crate::structure::cores::kcore::KCoreDecomposition;
+
 /// first component is in, second component is out!
 #[derive(Copy, Clone, Debug)]
 pub struct Degree {
@@ -143,9 +145,14 @@ where
 
 //
 /// samples (exactly) nb_nodes with weighs proportional to their degrees, return nodes rank
-pub fn sample_nodes_by_degrees<F>(csmat: &CsMatI<F, usize>, nb_nodes: usize) -> Vec<usize>
+pub fn sample_nodes_by_degrees<F, R>(
+    csmat: &CsMatI<F, usize>,
+    nb_nodes: usize,
+    rng: &mut R,
+) -> Vec<usize>
 where
     F: Copy + Default,
+    R: Rng,
 {
     if nb_nodes >= csmat.rows() {
         log::error!("graph has only {} nb_nodes ", csmat.rows());
@@ -158,13 +165,12 @@ where
         .map(|d| (d.degree_in() + d.degree_out()) as f32)
         .collect();
     //
-    let mut rng = rand::rng();
     let distribution = WeightedIndex::new(&weights).unwrap();
     let mut nb_try = 0;
     let mut nb_sampled = 0;
     //
     while nb_sampled < nb_nodes {
-        let node = distribution.sample(&mut rng);
+        let node = distribution.sample(rng);
         nb_try += 1;
         if sampled.insert(node) {
             nb_sampled += 1;
@@ -181,9 +187,14 @@ where
 
 //
 /// sample (approximately) nb_nodes uniformly
-pub fn sample_nodes_uniform<F>(csmat: &CsMatI<F, usize>, nb_sample: usize) -> Vec<usize>
+pub fn sample_nodes_uniform<F, R>(
+    csmat: &CsMatI<F, usize>,
+    nb_sample: usize,
+    rng: &mut R,
+) -> Vec<usize>
 where
     F: Copy + Default,
+    R: Rng,
 {
     //
     assert_eq!(csmat.rows(), csmat.cols());
@@ -192,12 +203,11 @@ where
     assert!(nb_nodes <= i32::MAX as usize);
     //
     let uniform = Uniform::<f64>::new(0., 1.).unwrap();
-    let mut rng = rand::rng();
     //
     let fraction = nb_sample as f64 / nb_nodes as f64;
     let sampled_nodes: Vec<usize> = (0..nb_nodes)
         .map(|i| {
-            if uniform.sample(&mut rng) <= fraction {
+            if uniform.sample(rng) <= fraction {
                 i as i32
             } else {
                 -1
@@ -209,3 +219,68 @@ where
     //
     sampled_nodes
 } // end of sample_nodes_uniform
+
+//
+/// samples (exactly, if possible) nb_nodes stratified by coreness, returns nodes rank.
+///
+/// Nodes are sorted by core number (see [KCoreDecomposition::get_degeneracy_ordering]) and cut in nb_strata strata of
+/// about the same size, a shell being never split between 2 strata. The same number of nodes is then sampled uniformly in each stratum,
+/// so that nodes of the dense inner cores are represented even if they are few.
+pub fn sample_nodes_by_coreness<R>(
+    kcore: &KCoreDecomposition,
+    nb_nodes: usize,
+    nb_strata: usize,
+    rng: &mut R,
+) -> Vec<usize>
+where
+    R: Rng,
+{
+    //
+    assert!(nb_strata > 0);
+    let ordering = kcore.get_degeneracy_ordering();
+    let nb_total = ordering.len();
+    if nb_nodes >= nb_total {
+        log::error!("graph has only {} nb_nodes ", nb_total);
+        return ordering.to_vec();
+    }
+    let core = |rank: usize| kcore.get_core(ordering[rank]).unwrap();
+    // strata boundaries in ordering, moved to the end of a shell
+    let mut bounds = vec![0usize];
+    for s in 1..nb_strata {
+        let mut end = (s * nb_total / nb_strata).max(*bounds.last().unwrap());
+        while end > 0 && end < nb_total && core(end) == core(end - 1) {
+            end += 1;
+        }
+        if end > *bounds.last().unwrap() && end < nb_total {
+            bounds.push(end);
+        }
+    }
+    bounds.push(nb_total);
+    let strata: Vec<&[usize]> = bounds.windows(2).map(|w| &ordering[w[0]..w[1]]).collect();
+    log::info!(
+        "sample_nodes_by_coreness, nb strata : {}, sizes : {:?}",
+        strata.len(),
+        strata.iter().map(|s| s.len()).collect::<Vec<usize>>()
+    );
+    // small strata are exhausted, what remains is dispatched on others
+    let mut quotas = vec![0usize; strata.len()];
+    let mut to_dispatch = nb_nodes;
+    while to_dispatch > 0 {
+        let open: Vec<usize> = (0..strata.len())
+            .filter(|s| quotas[*s] < strata[*s].len())
+            .collect();
+        let share = (to_dispatch / open.len()).max(1);
+        for s in open {
+            let added = share.min(strata[s].len() - quotas[s]).min(to_dispatch);
+            quotas[s] += added;
+            to_dispatch -= added;
+        }
+    }
+    //
+    let mut sampled = Vec::<usize>::with_capacity(nb_nodes);
+    for (stratum, quota) in strata.iter().zip(quotas) {
+        let chosen = rand::seq::index::sample(rng, stratum.len(), quota);
+        sampled.extend(chosen.iter().map(|i| stratum[i]));
+    }
+    sampled
+} // end of sample_nodes_by_coreness
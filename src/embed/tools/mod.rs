@@ -9,3 +9,5 @@ pub mod edge;
 pub mod jaccard;
 
 pub mod correlation;
+
+pub mod pairedtest;
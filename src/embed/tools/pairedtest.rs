@@ -0,0 +1,243 @@
+//! Paired statistical tests: Student paired t-test and Wilcoxon signed-rank test.
+//!
+//! They are used to compare two methods evaluated on the same samples (for example two embedders evaluated
+//! on identical edge splits). Both return a two-sided p-value.
+
+/// Student paired t-test on differences x\[i\] - y\[i\].
+///
+/// Returns the t statistic and the two sided p-value (with x.len() - 1 degrees of freedom).
+/// Both are NaN if there are less than 2 pairs.
+pub fn paired_t_test(x: &[f64], y: &[f64]) -> (f64, f64) {
+    assert_eq!(x.len(), y.len());
+    let nb_pairs = x.len();
+    if nb_pairs < 2 {
+        return (f64::NAN, f64::NAN);
+    }
+    let diffs: Vec<f64> = x.iter().zip(y.iter()).map(|(a, b)| a - b).collect();
+    let mean = diffs.iter().sum::<f64>() / nb_pairs as f64;
+    let var = diffs
+        .iter()
+        .fold(0., |acc, d| acc + (d - mean) * (d - mean))
+        / (nb_pairs - 1) as f64;
+    if var <= 0. {
+        // all differences are equal
+        return if mean == 0. {
+            (0., 1.)
+        } else {
+            (mean.signum() * f64::INFINITY, 0.)
+        };
+    }
+    let t = mean / (var / nb_pairs as f64).sqrt();
+    let df = (nb_pairs - 1) as f64;
+    let pvalue = incomplete_beta(df / (df + t * t), 0.5 * df, 0.5);
+    (t, pvalue.clamp(0., 1.))
+} // end of paired_t_test
+
+/// Wilcoxon signed-rank test on differences x\[i\] - y\[i\].
+///
+/// Null differences are discarded, tied absolute differences get their average rank.
+/// Returns the sum of ranks of positive differences and the two sided p-value.
+/// The p-value is exact for less than 26 non null differences without ties, otherwise it uses the normal approximation
+/// with tie and continuity corrections.
+pub fn wilcoxon_signed_rank(x: &[f64], y: &[f64]) -> (f64, f64) {
+    assert_eq!(x.len(), y.len());
+    let mut diffs: Vec<f64> = x
+        .iter()
+        .zip(y.iter())
+        .map(|(a, b)| a - b)
+        .filter(|d| *d != 0.)
+        .collect();
+    let n = diffs.len();
+    if n == 0 {
+        return (0., 1.);
+    }
+    diffs.sort_unstable_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap());
+    // average ranks of ties
+    let mut w_plus = 0.;
+    let mut ties_correction = 0.;
+    let mut i = 0;
+    while i < n {
+        let mut j = i + 1;
+        while j < n && diffs[j].abs() == diffs[i].abs() {
+            j += 1;
+        }
+        let nb_tied = (j - i) as f64;
+        let rank = (i + 1 + j) as f64 / 2.;
+        for d in &diffs[i..j] {
+            if *d > 0. {
+                w_plus += rank;
+            }
+        }
+        ties_correction += nb_tied * nb_tied * nb_tied - nb_tied;
+        i = j;
+    }
+    //
+    let pvalue = if n <= 25 && ties_correction == 0. {
+        // exact distribution : counts[s] is the number of subsets of {1..n} with rank sum s
+        let max_sum = n * (n + 1) / 2;
+        let mut counts = vec![0u64; max_sum + 1];
+        counts[0] = 1;
+        for r in 1..=n {
+            for s in (r..=max_sum).rev() {
+                counts[s] += counts[s - r];
+            }
+        }
+        let total = (1u64 << n) as f64;
+        let w = w_plus as usize;
+        let lower = counts[..=w].iter().sum::<u64>() as f64 / total;
+        let upper = counts[w..].iter().sum::<u64>() as f64 / total;
+        (2. * lower.min(upper)).min(1.)
+    } else {
+        let nf = n as f64;
+        let mean = nf * (nf + 1.) / 4.;
+        let var = nf * (nf + 1.) * (2. * nf + 1.) / 24. - ties_correction / 48.;
+        let delta = w_plus - mean;
+        if var <= 0. {
+            1.
+        } else {
+            let z = (delta.abs() - 0.5).max(0.) / var.sqrt();
+            (2. * (1. - normal_cdf(z))).clamp(0., 1.)
+        }
+    };
+    (w_plus, pvalue)
+} // end of wilcoxon_signed_rank
+
+/// cumulative distribution function of standard normal law
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * erfc(-z / std::f64::consts::SQRT_2)
+}
+
+// complementary error function, Chebyshev approximation with fractional error less than 1.2e-7 (Numerical Recipes)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let ans = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0. { ans } else { 2. - ans }
+} // end of erfc
+
+// log of gamma function, Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        (pi / (pi * x).sin()).ln() - ln_gamma(1. - x)
+    } else {
+        let x = x - 1.;
+        let t = x + 7.5;
+        let mut a = COEFFS[0];
+        for (i, c) in COEFFS.iter().enumerate().skip(1) {
+            a += c / (x + i as f64);
+        }
+        0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+    }
+} // end of ln_gamma
+
+// regularized incomplete beta function I_x(a,b)
+fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+    let bt = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln()).exp();
+    if x < (a + 1.) / (a + b + 2.) {
+        bt * beta_continued_fraction(x, a, b) / a
+    } else {
+        1. - bt * beta_continued_fraction(1. - x, b, a) / b
+    }
+} // end of incomplete_beta
+
+// continued fraction for incomplete beta function by modified Lentz method (Numerical Recipes)
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITER: usize = 300;
+    const EPS: f64 = 3.0e-16;
+    const FPMIN: f64 = 1.0e-300;
+    //
+    let fix = |v: f64| if v.abs() < FPMIN { FPMIN } else { v };
+    let qab = a + b;
+    let qap = a + 1.;
+    let qam = a - 1.;
+    let mut c = 1.;
+    let mut d = 1. / fix(1. - qab * x / qap);
+    let mut h = d;
+    for m in 1..=MAX_ITER {
+        let m = m as f64;
+        let m2 = 2. * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1. / fix(1. + aa * d);
+        c = fix(1. + aa / c);
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1. / fix(1. + aa * d);
+        c = fix(1. + aa / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.).abs() < EPS {
+            break;
+        }
+    }
+    h
+} // end of beta_continued_fraction
+
+//========================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_paired_t_test() {
+        // differences 1,2,3,4,5 : mean 3, sd sqrt(2.5), t = 3 / sqrt(0.5) = 4.2426, p = 0.01324 (4 df)
+        let x = [2., 4., 6., 8., 10.];
+        let y = [1., 2., 3., 4., 5.];
+        let (t, p) = paired_t_test(&x, &y);
+        assert!((t - 4.242641).abs() < 1.0e-5);
+        assert!((p - 0.013245).abs() < 1.0e-5);
+        // symetry
+        let (t2, p2) = paired_t_test(&y, &x);
+        assert!((t + t2).abs() < 1.0e-10);
+        assert!((p - p2).abs() < 1.0e-10);
+    }
+
+    #[test]
+    fn test_wilcoxon_exact() {
+        // all 6 differences positive : W+ = 21, exact two sided p = 2/64
+        let x = [1.1, 2.2, 3.3, 4.4, 5.5, 6.6];
+        let y = [1., 2., 3., 4., 5., 6.];
+        let (w, p) = wilcoxon_signed_rank(&x, &y);
+        assert_eq!(w, 21.);
+        assert!((p - 2. / 64.).abs() < 1.0e-12);
+        // no difference
+        let (w, p) = wilcoxon_signed_rank(&y, &y);
+        assert_eq!(w, 0.);
+        assert_eq!(p, 1.);
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.) - 0.5).abs() < 1.0e-7);
+        assert!((normal_cdf(1.959964) - 0.975).abs() < 1.0e-6);
+        assert!((normal_cdf(-1.959964) - 0.025).abs() < 1.0e-6);
+    }
+} // end of mod tests
@@ -20,16 +20,46 @@
 //! - Nodesketch is described in the paper <https://dl.acm.org/doi/10.1145/3292500.3330951>
 //!
 
+use anyhow::anyhow;
+
+use std::sync::Arc;
+
 use indexmap::IndexSet;
 use ndarray::{Array2, ArrayView1};
+use num_traits::Float;
+
+use rand::distr::{Distribution, Uniform};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rand_xoshiro::rand_core::SeedableRng;
+
+use serde::{Deserialize, Serialize};
+use sprs::CsMatI;
 
 use crate::embed::tools::degrees::*;
 use crate::embed::tools::edge::{IN, OUT};
 
 use crate::io::embeddedbson::EmbeddedBsonReload;
 
-/// to represent the distance in embedded space between 2 vectors
-type Distance<F> = fn(&[F], &[F]) -> f64;
+/// A distance (or dissimilarity) between 2 vectors in embedded space.
+///
+/// Contrary to a function pointer an implementation can carry state (per coordinate weights, a scale ...).
+/// The trait is implemented for functions and closures `Fn(&[F], &[F]) -> f64` so the Jaccard and Hope distances are used as is.
+pub trait EmbeddedDistance<F>: Send + Sync {
+    /// distance from v1 to v2
+    fn eval(&self, v1: &[F], v2: &[F]) -> f64;
+}
+
+impl<F, D> EmbeddedDistance<F> for D
+where
+    D: Fn(&[F], &[F]) -> f64 + Send + Sync,
+{
+    fn eval(&self, v1: &[F], v2: &[F]) -> f64 {
+        self(v1, v2)
+    }
+}
+
+/// A shared distance, as stored in embedded data and returned by [EmbeddedT::get_distance]
+pub type DistanceRef<F> = Arc<dyn EmbeddedDistance<F>>;
 
 #[derive(Debug)]
 pub enum EmbeddingMode {
@@ -46,6 +76,37 @@ pub const TAG_IN: u8 = 1;
 /// tag to specify we ask information on a node in symetric embedding
 pub const TAG_IN_OUT: u8 = 1;
 
+/// Strategy used by [EmbeddedAsym] to compute the dissimilarity **from** a node **to** another node.
+///
+/// The strategy is set when the embedding is constructed (see for example [HopeParams::set_asym_distance](crate::prelude::HopeParams::set_asym_distance))
+/// or with [EmbeddedAsym::set_asym_distance], and it is stored in bson dumps.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AsymDistance {
+    /// distance from the source vector of node1 to the target vector of node2
+    SourceTarget,
+    /// average of source-source, target-target and source-target distances
+    #[default]
+    Average,
+    /// weighted sum of source-source, target-target and source-target distances, in this order.
+    /// Weights can be learned from a graph with [EmbeddedAsym::learn_asym_weights]
+    Weighted([f64; 3]),
+    /// dot product similarity between source vector of node1 and target vector of node2, transformed into 1/(1+exp(dot)).
+    /// Only for embeddings with float vectors (Hope)
+    DotProduct,
+}
+
+/// dissimilarity associated to dot product similarity : 1/(1+exp(v1.v2)) which is in \]0,1\[ and decreasing with dot product.
+pub fn dot_product_dissimilarity<F: Float>(v1: &[F], v2: &[F]) -> f64 {
+    assert_eq!(v1.len(), v2.len());
+    let dot = v1
+        .iter()
+        .zip(v2.iter())
+        .fold(F::zero(), |acc, v| acc + (*v.0 * *v.1))
+        .to_f64()
+        .unwrap();
+    1. / (1. + dot.exp())
+} // end of dot_product_dissimilarity
+
 /// The Embedded trait. It defines the interface satisfied by embedded data.  
 /// In our implementations the embedded data are stored in Array2 and embedded node
 /// are identified by their rank.  
@@ -67,11 +128,15 @@ pub trait EmbeddedT<F> {
     /// For a basic symetric embedding , tag is not taken into account.
     /// For embedding that has multiple embedding by node (example asysmetric embedding , the tag is used)    
     fn get_embedded_node(&self, node_rank: usize, _tag: u8) -> ArrayView1<F>;
-    /// Returns the distance function f (a pointer to) used for computing distances the embedding.   
+    /// Returns the distance used for computing distances in the embedding.   
     /// Note that for asymetric embedding the value of the distance returned by get_noderank_distance
     /// is not directly the result of applying f to 2 slices representing 2 nodes as a node may have more than one
     /// representation.
-    fn get_distance(&self) -> fn(&[F], &[F]) -> f64;
+    fn get_distance(&self) -> DistanceRef<F>;
+    /// returns the strategy used to compute node dissimilarities of an asymetric embedding, None for a symetric embedding
+    fn get_asym_distance(&self) -> Option<AsymDistance> {
+        None
+    }
 } // end of trait
 
 /// represent symetric Embedded data without information on the node indexation  
@@ -80,26 +145,31 @@ pub struct Embedded<F> {
     /// array (n,d) with n number of data, d dimension of Embedded
     data: Array2<F>,
     /// distance between vectors in embedded space. helps to implement trait [EmbeddedT\<F\>]
-    distance: fn(&[F], &[F]) -> f64,
+    distance: DistanceRef<F>,
 } // end of Embedded
 
 impl<F> Embedded<F> {
     // fills embedded vectors with the appropriate distance function
-    pub(crate) fn new(arr: Array2<F>, distance: Distance<F>) -> Self {
+    pub(crate) fn new(arr: Array2<F>, distance: impl EmbeddedDistance<F> + 'static) -> Self {
         Embedded {
             data: arr,
-            distance: distance,
+            distance: Arc::new(distance),
         }
     }
 
+    /// replaces the distance used by the embedding, for example by a distance with learned parameters.
+    pub fn set_distance(&mut self, distance: DistanceRef<F>) {
+        self.distance = distance;
+    }
+
     /// get representation of nodes as sources
     pub fn get_embedded(&self) -> &Array2<F> {
         &self.data
     }
 
-    /// get reference to distance function
-    pub fn get_distance_ref(&self) -> &fn(&[F], &[F]) -> f64 {
-        &self.distance
+    /// get reference to distance
+    pub fn get_distance_ref(&self) -> &dyn EmbeddedDistance<F> {
+        self.distance.as_ref()
     }
 
     /// get embedding of node of rank rank, and with tag.
@@ -124,13 +194,13 @@ impl<F> EmbeddedT<F> for Embedded<F> {
     /// dimensions must be equal to Embedded dimension
     fn get_vec_distance(&self, data1: &[F], data2: &[F]) -> f64 {
         assert_eq!(data1.len(), self.get_dimension());
-        (self.distance)(data1, data2)
+        self.distance.eval(data1, data2)
     }
 
     /// get distance between nodes identified by their rank!
     /// get distance from node1 to node2 (different from distance between node2 to node1 if Graph is asymetric)
     fn get_noderank_distance(&self, node1: usize, node2: usize) -> f64 {
-        (self.distance)(
+        self.distance.eval(
             self.data.row(node1).as_slice().unwrap(),
             self.data.row(node2).as_slice().unwrap(),
         )
@@ -149,8 +219,8 @@ impl<F> EmbeddedT<F> for Embedded<F> {
     }
 
     /// get distance function
-    fn get_distance(&self) -> fn(&[F], &[F]) -> f64 {
-        self.distance
+    fn get_distance(&self) -> DistanceRef<F> {
+        self.distance.clone()
     }
 } // end impl EmbeddedT<F>
 
@@ -165,7 +235,11 @@ pub struct EmbeddedAsym<F> {
     //
     degrees: Option<Vec<Degree>>,
     /// distance
-    distance: Distance<F>,
+    distance: DistanceRef<F>,
+    /// strategy to combine source and target representations in node dissimilarity
+    asym_distance: AsymDistance,
+    /// dissimilarity from dot product, only for float vectors
+    dot_product: Option<DistanceRef<F>>,
 } // end of struct EmbeddedAsym
 
 impl<F> EmbeddedAsym<F> {
@@ -173,7 +247,7 @@ impl<F> EmbeddedAsym<F> {
         source: Array2<F>,
         target: Array2<F>,
         degrees: Option<Vec<Degree>>,
-        distance: Distance<F>,
+        distance: impl EmbeddedDistance<F> + 'static,
     ) -> Self {
         assert_eq!(source.dim().0, target.dim().0);
         assert_eq!(source.dim().1, target.dim().1);
@@ -181,10 +255,26 @@ impl<F> EmbeddedAsym<F> {
             source,
             target,
             degrees,
-            distance,
+            distance: Arc::new(distance),
+            asym_distance: AsymDistance::default(),
+            dot_product: None,
         }
     }
 
+    // enables the dot product strategy
+    pub(crate) fn with_dot_product(
+        mut self,
+        dot_product: impl EmbeddedDistance<F> + 'static,
+    ) -> Self {
+        self.dot_product = Some(Arc::new(dot_product));
+        self
+    }
+
+    /// replaces the distance between vectors used by the embedding, the [AsymDistance] strategy is unchanged.
+    pub fn set_distance(&mut self, distance: DistanceRef<F>) {
+        self.distance = distance;
+    }
+
     /// get representation of nodes as sources
     pub fn get_embedded_source(&self) -> &Array2<F> {
         &self.source
@@ -194,8 +284,227 @@ impl<F> EmbeddedAsym<F> {
     pub fn get_embedded_target(&self) -> &Array2<F> {
         &self.target
     }
+
+    /// returns the strategy used in node dissimilarity
+    pub fn get_asym_distance(&self) -> AsymDistance {
+        self.asym_distance
+    }
+
+    /// sets the strategy used in node dissimilarity.
+    /// Returns an error for the dot product strategy if vectors are not float vectors, or if weights are negative or all null.
+    pub fn set_asym_distance(&mut self, asym_distance: AsymDistance) -> Result<(), anyhow::Error> {
+        match asym_distance {
+            AsymDistance::DotProduct if self.dot_product.is_none() => {
+                log::error!("EmbeddedAsym::set_asym_distance : dot product needs float vectors");
+                return Err(anyhow!("dot product strategy needs float vectors"));
+            }
+            AsymDistance::Weighted(weights)
+                if weights.iter().any(|w| *w < 0.) || weights.iter().sum::<f64>() <= 0. =>
+            {
+                log::error!(
+                    "EmbeddedAsym::set_asym_distance : bad weights {:?}",
+                    weights
+                );
+                return Err(anyhow!("weights must be positive and not all null"));
+            }
+            _ => {}
+        }
+        self.asym_distance = asym_distance;
+        Ok(())
+    } // end of set_asym_distance
+
+    /// dissimilarity from a node with representations (source1, target1) to a node with representations (source2, target2)
+    /// according to the strategy of the embedding.
+    pub fn get_asym_vec_distance(
+        &self,
+        source1: &[F],
+        target1: &[F],
+        source2: &[F],
+        target2: &[F],
+    ) -> f64 {
+        asym_vec_distance(
+            self.asym_distance,
+            self.distance.as_ref(),
+            self.dot_product.as_deref(),
+            (source1, target1),
+            (source2, target2),
+        )
+    }
+
+    /// returns the dissimilarity of the strategy as a function of 2 vectors, each one being the concatenation
+    /// of source and target representations of a node.
+    /// This is useful to build an Hnsw structure on asymetric embedded data.
+    pub fn get_concatenated_distance(&self) -> DistanceRef<F>
+    where
+        F: 'static,
+    {
+        Arc::new(ConcatenatedDistance {
+            asym_distance: self.asym_distance,
+            distance: self.distance.clone(),
+            dot_product: self.dot_product.clone(),
+            dim: self.get_dimension(),
+        })
+    } // end of get_concatenated_distance
+
+    /// learns the weights of the [AsymDistance::Weighted] strategy and sets it as the strategy of the embedding.
+    ///
+    /// Edges of csmat (the graph that was embedded) and the same number of random couples of nodes that are not edges
+    /// are sampled (at most nb_sample of each). A logistic regression predicting edges from the source-source, target-target
+    /// and source-target distances is fitted with positive weights. Weights are normalized to sum to 1 and returned.
+    ///
+    /// An error is returned, and the strategy is left unchanged, if the graph has no edge or less than 2 nodes,
+    /// or if its size does not match the embedding.
+    pub fn learn_asym_weights<G>(
+        &mut self,
+        csmat: &CsMatI<G, usize>,
+        nb_sample: usize,
+        seed: u64,
+    ) -> Result<[f64; 3], anyhow::Error> {
+        const NB_EPOCH: usize = 500;
+        const LEARNING_RATE: f64 = 0.5;
+        //
+        let nb_nodes = self.get_nb_nodes();
+        let nb_edges = csmat.nnz();
+        if nb_edges == 0 || nb_nodes < 2 {
+            return Err(anyhow!(
+                "learn_asym_weights needs at least one edge and 2 nodes, got {} edges and {} nodes",
+                nb_edges,
+                nb_nodes
+            ));
+        }
+        if csmat.rows() != nb_nodes || csmat.cols() != nb_nodes {
+            return Err(anyhow!(
+                "learn_asym_weights graph of shape {:?} does not match embedding of {} nodes",
+                csmat.shape(),
+                nb_nodes
+            ));
+        }
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let node_random = Uniform::<usize>::new(0, nb_nodes).unwrap();
+        let edges: Vec<(usize, usize)> = csmat.iter().map(|(_, (i, j))| (i, j)).collect();
+        let nb_pos = nb_sample.min(nb_edges);
+        // samples (features, label)
+        let mut samples = Vec::<([f64; 3], f64)>::with_capacity(2 * nb_pos);
+        let features = |i: usize, j: usize| -> [f64; 3] {
+            let s = |r: usize| self.source.row(r).to_slice().unwrap();
+            let t = |r: usize| self.target.row(r).to_slice().unwrap();
+            [
+                self.distance.eval(s(i), s(j)),
+                self.distance.eval(t(i), t(j)),
+                self.distance.eval(s(i), t(j)),
+            ]
+        };
+        let edge_random = Uniform::<usize>::new(0, nb_edges).unwrap();
+        for k in 0..nb_pos {
+            let (i, j) = if nb_pos < nb_edges {
+                edges[edge_random.sample(&mut rng)]
+            } else {
+                edges[k]
+            };
+            samples.push((features(i, j), 1.));
+        }
+        let mut nb_neg = 0;
+        let mut nb_tries = 0;
+        while nb_neg < nb_pos && nb_tries < 100 * nb_pos {
+            nb_tries += 1;
+            let i = node_random.sample(&mut rng);
+            let j = node_random.sample(&mut rng);
+            if i != j && csmat.get(i, j).is_none() {
+                samples.push((features(i, j), 0.));
+                nb_neg += 1;
+            }
+        }
+        // logistic regression P(edge) = sigmoid(b - w.x) with w >= 0, by projected gradient descent
+        let mut weights = [1. / 3.; 3];
+        let mut bias = 0.;
+        let nb_samples = samples.len() as f64;
+        for _ in 0..NB_EPOCH {
+            let mut grad_w = [0.; 3];
+            let mut grad_b = 0.;
+            for (x, y) in &samples {
+                let z = bias
+                    - weights
+                        .iter()
+                        .zip(x.iter())
+                        .map(|(w, x)| w * x)
+                        .sum::<f64>();
+                let err = 1. / (1. + (-z).exp()) - y;
+                grad_b += err;
+                for l in 0..3 {
+                    grad_w[l] -= err * x[l];
+                }
+            }
+            bias -= LEARNING_RATE * grad_b / nb_samples;
+            for l in 0..3 {
+                weights[l] = (weights[l] - LEARNING_RATE * grad_w[l] / nb_samples).max(0.);
+            }
+        }
+        let sum = weights.iter().sum::<f64>();
+        if sum > 0. {
+            weights.iter_mut().for_each(|w| *w /= sum);
+        } else {
+            log::warn!("learn_asym_weights : all weights null, using average");
+            weights = [1. / 3.; 3];
+        }
+        log::info!("learn_asym_weights : weights {:?}", weights);
+        self.asym_distance = AsymDistance::Weighted(weights);
+        Ok(weights)
+    } // end of learn_asym_weights
 } // end of impl block for EmbeddedAsym
 
+// the dissimilarity of an asymetric embedding between concatenations of source and target vectors
+struct ConcatenatedDistance<F> {
+    asym_distance: AsymDistance,
+    distance: DistanceRef<F>,
+    dot_product: Option<DistanceRef<F>>,
+    dim: usize,
+}
+
+impl<F> EmbeddedDistance<F> for ConcatenatedDistance<F> {
+    fn eval(&self, v1: &[F], v2: &[F]) -> f64 {
+        asym_vec_distance(
+            self.asym_distance,
+            self.distance.as_ref(),
+            self.dot_product.as_deref(),
+            v1.split_at(self.dim),
+            v2.split_at(self.dim),
+        )
+    }
+} // end of impl EmbeddedDistance for ConcatenatedDistance
+
+// dissimilarity from node1 to node2 given their (source, target) representations
+fn asym_vec_distance<F>(
+    asym_distance: AsymDistance,
+    distance: &dyn EmbeddedDistance<F>,
+    dot_product: Option<&dyn EmbeddedDistance<F>>,
+    node1: (&[F], &[F]),
+    node2: (&[F], &[F]),
+) -> f64 {
+    match asym_distance {
+        AsymDistance::SourceTarget => distance.eval(node1.0, node2.1),
+        AsymDistance::Average => {
+            (distance.eval(node1.0, node2.0)
+                + distance.eval(node1.1, node2.1)
+                + distance.eval(node1.0, node2.1))
+                / 3.
+        }
+        AsymDistance::Weighted(w) => {
+            let mut dist = 0.;
+            if w[0] > 0. {
+                dist += w[0] * distance.eval(node1.0, node2.0);
+            }
+            if w[1] > 0. {
+                dist += w[1] * distance.eval(node1.1, node2.1);
+            }
+            if w[2] > 0. {
+                dist += w[2] * distance.eval(node1.0, node2.1);
+            }
+            dist / w.iter().sum::<f64>()
+        }
+        AsymDistance::DotProduct => dot_product.unwrap().eval(node1.0, node2.1),
+    }
+} // end of asym_vec_distance
+
 impl<F> EmbeddedT<F> for EmbeddedAsym<F> {
     fn is_symetric(&self) -> bool {
         false
@@ -208,36 +517,22 @@ impl<F> EmbeddedT<F> for EmbeddedAsym<F> {
 
     /// get distance from data1 to data2
     fn get_vec_distance(&self, data1: &[F], data2: &[F]) -> f64 {
-        (self.distance)(data1, data2)
+        self.distance.eval(data1, data2)
     }
 
     /// In this interface nodes are identified by their rank in the embedding, not their original identity
     /// get distance FROM source node_rank1 TO target node_rank2 if Embedded is asymetric, in symetric case there is no order).  
+    /// The source and target representations are combined according to the [AsymDistance] strategy of the embedding.
     ///  
     /// To get an interface with original nodes id, use the Embedding::get_node_distance function which has a mapping from node_id to node_rank
     fn get_noderank_distance(&self, node_rank1: usize, node_rank2: usize) -> f64 {
-        let mut distances = Vec::<f64>::with_capacity(3);
-        //
-        let dist_s = (self.distance)(
+        let dist = self.get_asym_vec_distance(
             self.source.row(node_rank1).as_slice().unwrap(),
-            self.source.row(node_rank2).as_slice().unwrap(),
-        );
-        distances.push(dist_s);
-
-        let dist_t = (self.distance)(
             self.target.row(node_rank1).as_slice().unwrap(),
+            self.source.row(node_rank2).as_slice().unwrap(),
             self.target.row(node_rank2).as_slice().unwrap(),
         );
-        distances.push(dist_t);
-        //
-        let dist_t = (self.distance)(
-            self.source.row(node_rank1).as_slice().unwrap(),
-            self.target.row(node_rank2).as_slice().unwrap(),
-        );
-        distances.push(dist_t);
-        if !distances.is_empty() {
-            distances.iter().sum::<f64>() / distances.len() as f64
-        } else {
+        if dist.is_nan() {
             match &self.degrees {
                 // if we have degrees we dump info
                 Some(degrees) => {
@@ -258,8 +553,9 @@ impl<F> EmbeddedT<F> for EmbeddedAsym<F> {
                 }
             }
             log::error!("get_noderank_distance asymetric no distance computed");
-            1.
+            return 1.;
         }
+        dist
     } // end of get_noderank_distance
 
     /// get number of nodes embedded.
@@ -288,8 +584,12 @@ impl<F> EmbeddedT<F> for EmbeddedAsym<F> {
     }
 
     /// get distance function
-    fn get_distance(&self) -> fn(&[F], &[F]) -> f64 {
-        self.distance
+    fn get_distance(&self) -> DistanceRef<F> {
+        self.distance.clone()
+    }
+
+    fn get_asym_distance(&self) -> Option<AsymDistance> {
+        Some(self.asym_distance)
     }
 } // end impl EmbeddedT<F>
 
@@ -386,7 +686,7 @@ pub fn from_bson_with_jaccard<F, NodeId>(
     bson_reload: EmbeddedBsonReload<F, NodeId>,
 ) -> Result<Embedding<F, NodeId, Embedded<F>>, anyhow::Error>
 where
-    F: Eq,
+    F: Eq + 'static,
     NodeId: std::hash::Hash + std::cmp::Eq,
 {
     // from_bson_with_jaccard
@@ -404,3 +704,39 @@ where
     };
     Ok(embedding)
 } // end of from_bson_with_jaccard
+
+/// make an EmbeddedAsym\<F\> structure from data of an asymetric embedding reloaded from bson data.
+/// The strategy of node dissimilarity stored in the dump (see [AsymDistance]) is restored, dumps without it get the default strategy.
+/// As in [from_bson_with_jaccard] the Eq constraint avoids float vectors, so the dot product strategy cannot be restored.
+pub fn from_bson_asym_with_jaccard<F, NodeId>(
+    bson_reload: EmbeddedBsonReload<F, NodeId>,
+) -> Result<Embedding<F, NodeId, EmbeddedAsym<F>>, anyhow::Error>
+where
+    F: Eq + 'static,
+    NodeId: std::hash::Hash + std::cmp::Eq,
+{
+    let asym_distance = bson_reload.get_asym_distance();
+    if bson_reload.in_embedded.is_none() {
+        return Err(anyhow!(
+            "no target embedding in bson dump, dump is symetric"
+        ));
+    }
+    if bson_reload.node_indexation.is_none() {
+        return Err(anyhow!("no node indexation in bson dump"));
+    }
+    let mut embedded_data = EmbeddedAsym::new(
+        bson_reload.out_embedded,
+        bson_reload.in_embedded.unwrap(),
+        None,
+        crate::embed::tools::jaccard::jaccard_distance::<F>,
+    );
+    if let Some(asym_distance) = asym_distance {
+        embedded_data.set_asym_distance(asym_distance)?;
+    }
+    let embedding = Embedding::<F, NodeId, EmbeddedAsym<F>> {
+        nodeindexation: bson_reload.node_indexation.unwrap(),
+        embedded: embedded_data,
+        mark: std::marker::PhantomData,
+    };
+    Ok(embedding)
+} // end of from_bson_asym_with_jaccard
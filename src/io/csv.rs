@@ -838,6 +838,80 @@ where
     res
 } // end of csv_to_trimat_delimiters
 
+/// Loads labels of nodes of a graph read by [csv_to_trimat_delimiters] or [csv_to_trimat].
+///
+/// Each line of the file contains a node id as in the graph csv file and a label, separated by one of the delimiters [b'\t', b',', b' ', b';'].
+/// A node with many labels appears on many lines. Lines beginning with # or % are skipped.
+/// Returns a vector, indexed by the rank of nodes in node_index, of the labels of each node (possibly empty).
+/// Nodes not in node_index are ignored.
+pub fn csv_to_node_labels(
+    filepath: &Path,
+    node_index: &NodeIndexation<usize>,
+) -> anyhow::Result<Vec<Vec<String>>> {
+    //
+    log::debug!("in csv_to_node_labels");
+    //
+    let fileres = OpenOptions::new().read(true).open(filepath);
+    if fileres.is_err() {
+        log::error!(
+            "csv_to_node_labels could not open file {:?}",
+            filepath.as_os_str()
+        );
+        return Err(anyhow!(
+            "csv_to_node_labels could not open file {}",
+            filepath.display()
+        ));
+    }
+    let bufreader = BufReader::new(fileres?);
+    let delimiters = ['\t', ',', ' ', ';'];
+    let mut labels: Vec<Vec<String>> = vec![Vec::new(); node_index.len()];
+    let mut nb_ignored = 0usize;
+    for (num, line) in bufreader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+            continue;
+        }
+        let mut fields = line.split(&delimiters[..]).filter(|f| !f.is_empty());
+        let (node, label) = match (fields.next(), fields.next()) {
+            (Some(node), Some(label)) => (node, label),
+            _ => {
+                return Err(anyhow!(
+                    "csv_to_node_labels : line {} of {} has not 2 fields",
+                    num + 1,
+                    filepath.display()
+                ));
+            }
+        };
+        let node: usize = node.parse().map_err(|_| {
+            anyhow!(
+                "csv_to_node_labels : cannot parse node id at line {}",
+                num + 1
+            )
+        })?;
+        match node_index.get_index_of(&node) {
+            Some(rank) => {
+                if !labels[rank].iter().any(|l| l == label) {
+                    labels[rank].push(label.to_string());
+                }
+            }
+            None => nb_ignored += 1,
+        }
+    }
+    if nb_ignored > 0 {
+        log::warn!(
+            "csv_to_node_labels : {} lines with nodes not in graph ignored",
+            nb_ignored
+        );
+    }
+    log::info!(
+        "csv_to_node_labels : nb labeled nodes : {}",
+        labels.iter().filter(|l| !l.is_empty()).count()
+    );
+    //
+    Ok(labels)
+} // end of csv_to_node_labels
+
 //========================================================================================
 
 #[cfg(test)]
@@ -904,4 +978,24 @@ mod tests {
             log::debug!("node1 {}, node2 {},  value {} ", node1, node2, value);
         }
     } // end test test_weighted_csv_to_trimat
+
+    #[test]
+    fn test_csv_to_node_labels() {
+        log_init_test();
+        //
+        let mut node_index = NodeIndexation::<usize>::new();
+        for node in [10, 20, 30] {
+            node_index.insert(node);
+        }
+        let path = std::env::temp_dir().join("test_csv_to_node_labels.txt");
+        std::fs::write(&path, "# node label\n10 a\n20\tb\n10,c\n10 a\n40 d\n").unwrap();
+        let labels = csv_to_node_labels(&path, &node_index).unwrap();
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels[0], vec!["a".to_string(), "c".to_string()]);
+        assert_eq!(labels[1], vec!["b".to_string()]);
+        assert!(labels[2].is_empty());
+        //
+        std::fs::write(&path, "10\n").unwrap();
+        assert!(csv_to_node_labels(&path, &node_index).is_err());
+    } // end of test_csv_to_node_labels
 } // edn of mod tests
@@ -11,6 +11,9 @@
 //! - symetric or asymetric flag
 //! - dimension of vectors
 //! - number of vectors
+//! - for an asymetric embedding, the strategy used to compute node dissimilarities (see [AsymDistance]) with key asym_distance.
+//!   It is encoded as a string ("SourceTarget", "Average", "DotProduct") or as a document {"Weighted" : \[w0, w1, w2\]}.
+//!   The key is absent in dumps of symetric embeddings and in dumps done before its introduction.
 //!
 //! 2. The embedded arrays, one or two depending on asymetry
 //!     - loop on number of vectors
@@ -65,6 +68,9 @@ pub struct EmbeddedBsonHeader {
     pub dimension: i64,
     /// number of vectors.
     pub nbdata: i64,
+    /// strategy of node dissimilarity for asymetric embedding
+    #[serde(default)]
+    pub asym_distance: Option<AsymDistance>,
 } // end of EmbeddedBsonHeader
 
 impl EmbeddedBsonHeader {
@@ -75,6 +81,7 @@ impl EmbeddedBsonHeader {
             type_name,
             dimension,
             nbdata,
+            asym_distance: None,
         }
     }
 } // end of impl EmbeddedBsonHeader
@@ -116,7 +123,7 @@ where
     let dim: i64 = FromPrimitive::from_usize(embedded.get_dimension()).unwrap();
     let nbdata: i64 = FromPrimitive::from_usize(embedded.get_nb_nodes()).unwrap();
     // we could allocate a EmbeddedBsonHeader and call bson::to_bson(&bson_header).unwrap() but for C decoder ...
-    let bson_header = bson!({
+    let mut bson_header = bson!({
         "version": 1_i64,
         "symetric":embedded.is_symetric(),
         "type_name": std::any::type_name::<F>(),  // TODO must be simplified
@@ -124,6 +131,12 @@ where
         "nbdata": nbdata
         }
     );
+    if let Some(asym_distance) = embedded.get_asym_distance() {
+        bson_header
+            .as_document_mut()
+            .unwrap()
+            .insert("asym_distance", bson::to_bson(&asym_distance)?);
+    }
     doc.insert("header", bson_header);
     let res = doc.to_writer(&mut bufwriter);
     if res.is_err() {
@@ -243,6 +256,8 @@ pub struct EmbeddedBsonReload<F, NodeId> {
     pub(crate) in_embedded: Option<Array2<F>>,
     /// If nodeindexation was dumped in bson
     pub(crate) node_indexation: Option<IndexSet<NodeId>>,
+    /// strategy of node dissimilarity if embedding is asymetric
+    pub(crate) asym_distance: Option<AsymDistance>,
 } // end of EmbeddedBsonReload
 
 impl<F, NodeId> EmbeddedBsonReload<F, NodeId> {
@@ -255,6 +270,7 @@ impl<F, NodeId> EmbeddedBsonReload<F, NodeId> {
             out_embedded,
             in_embedded,
             node_indexation,
+            asym_distance: None,
         }
     }
     /// returns embedded data. If asymetric embedding it is embedded as a source.
@@ -266,6 +282,11 @@ impl<F, NodeId> EmbeddedBsonReload<F, NodeId> {
     pub fn get_node_indexation(&self) -> Option<&IndexSet<NodeId>> {
         self.node_indexation.as_ref()
     }
+    /// returns the strategy of node dissimilarity if the embedding dumped is asymetric.
+    /// (None if the dump was done before the strategy was stored)
+    pub fn get_asym_distance(&self) -> Option<AsymDistance> {
+        self.asym_distance
+    }
     /// If the embedding dumped is asymetric this returns an array giving the embedding as a target (or destination) node.
     pub fn get_in_embedded(&self) -> Option<&Array2<F>> {
         self.in_embedded.as_ref()
@@ -416,7 +437,9 @@ where
             path.display(),
             res.err()
         );
-        Ok(EmbeddedBsonReload::new(out_array, in_array_opt, None))
+        let mut reload = EmbeddedBsonReload::new(out_array, in_array_opt, None);
+        reload.asym_distance = header.asym_distance;
+        Ok(reload)
     } else {
         log::info!("\t found document , node indexation");
         let bson_indexation = res.unwrap();
@@ -439,11 +462,9 @@ where
             node_indexation.insert(node_id);
         }
         assert_eq!(node_indexation.len(), nb_data);
-        Ok(EmbeddedBsonReload::new(
-            out_array,
-            in_array_opt,
-            Some(node_indexation),
-        ))
+        let mut reload = EmbeddedBsonReload::new(out_array, in_array_opt, Some(node_indexation));
+        reload.asym_distance = header.asym_distance;
+        Ok(reload)
     } // end case we have indexation in bson file
 } // end of bson_load
 
@@ -481,6 +502,14 @@ where
             }
         }
     }
+    if embedded_data.get_asym_distance() != reloaded.get_asym_distance() {
+        log::error!(
+            "reloaded asym distance differ, embedded : {:?}, reloaded : {:?}",
+            embedded_data.get_asym_distance(),
+            reloaded.get_asym_distance()
+        );
+        return Ok(false);
+    }
     if !embedded_data.is_symetric() {
         log::info!("test_bson_moreno checking equality of reload : IN embedding");
         // same thing with tag = IN
@@ -616,6 +645,53 @@ mod tests {
         }
     } // end of test_bson_moreno
 
+    // asymetric usize embedding, the strategy of node dissimilarity must survive the reload
+    #[test]
+    fn test_bson_moreno_asym_distance() {
+        log_init_test();
+        //
+        let path = Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        let (trimat, node_indexation) = csv_to_trimat::<f64>(&path, true, b' ').unwrap();
+        let sketching_params = NodeSketchParams {
+            sketch_size: 15,
+            decay: 0.1,
+            nb_iter: 2,
+            symetric: false,
+            parallel: false,
+        };
+        let mut nodesketch = NodeSketchAsym::new(sketching_params, trimat);
+        nodesketch.set_asym_distance(AsymDistance::SourceTarget);
+        let embedding = Embedding::new(node_indexation, &mut nodesketch).unwrap();
+        let output = io::output::Output::new(
+            io::output::Format::BSON,
+            true,
+            &Some(String::from("moreno_asym_usize.bson")),
+        );
+        bson_dump(&embedding, &output).unwrap();
+        //
+        let reloaded =
+            bson_load::<usize, usize, EmbeddedAsym<usize>>(output.get_output_name()).unwrap();
+        assert_eq!(
+            reloaded.get_asym_distance(),
+            Some(AsymDistance::SourceTarget)
+        );
+        let reloaded = from_bson_asym_with_jaccard(reloaded).unwrap();
+        let embedded = embedding.get_embedded_data();
+        let embedded_reloaded = reloaded.get_embedded_data();
+        assert_eq!(
+            embedded_reloaded.get_asym_distance(),
+            AsymDistance::SourceTarget
+        );
+        for (i, j) in [(0, 1), (3, 10), (20, 5)] {
+            assert_eq!(
+                embedded.get_noderank_distance(i, j),
+                embedded_reloaded.get_noderank_distance(i, j)
+            );
+        }
+    } // end of test_bson_moreno_asym_distance
+
     #[test]
     fn test_bson_moreno_f32() {
         log_init_test();
@@ -639,7 +715,8 @@ mod tests {
         let decay_f = 0.05;
         //    let range_m = RangeApproxMode::RANK(RangeRank::new(500, 2));
         let range_m = RangeApproxMode::EPSIL(RangePrecision::new(0.1, 10, 300));
-        let params = HopeParams::new(hope_m, range_m, decay_f);
+        let mut params = HopeParams::new(hope_m, range_m, decay_f);
+        params.set_asym_distance(AsymDistance::DotProduct);
         // now we embed
         let mut hope = Hope::new(params, trimat);
         let hope_embedding = Embedding::new(node_indexation, &mut hope).unwrap();
@@ -0,0 +1,130 @@
+//! Json dump and reload of result structures (reports, decompositions, partitions).
+//!
+//! The structures provide their own dump_json and reload_json methods, which call the functions of this module.
+//!
+//! Json has no representation of NaN and infinite floats (serde_json writes them as null), so fields that
+//! can be non finite must be declared with `#[serde(with = "crate::io::jsonio::nonfinite")]`
+//! (or [nonfinite_vec] for a `Vec<f64>`), which writes them as the strings "NaN", "inf" and "-inf".
+
+use anyhow::anyhow;
+
+use std::fs::OpenOptions;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// dumps value in json format in file filepath. The file is created or truncated.
+pub fn dump_json<T>(value: &T, filepath: &Path) -> anyhow::Result<()>
+where
+    T: Serialize + ?Sized,
+{
+    //
+    let name = std::any::type_name::<T>();
+    log::info!("dumping {} in json file : {:?}", name, filepath);
+    //
+    let fileres = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(filepath);
+    if fileres.is_err() {
+        log::error!(
+            "dump_json of {} could not open file {:?}",
+            name,
+            filepath.as_os_str()
+        );
+        return Err(anyhow!("dump_json of {} failed", name));
+    }
+    //
+    let mut writer = BufWriter::new(fileres.unwrap());
+    serde_json::to_writer(&mut writer, value)?;
+    writer.flush()?;
+    //
+    Ok(())
+} // end of dump_json
+
+/// reloads a value dumped by [dump_json]
+pub fn reload_json<T>(filepath: &Path) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+{
+    //
+    let name = std::any::type_name::<T>();
+    log::info!("reloading {} from json file : {:?}", name, filepath);
+    //
+    let fileres = OpenOptions::new().read(true).open(filepath);
+    if fileres.is_err() {
+        log::error!(
+            "reload_json of {} could not open file {:?}",
+            name,
+            filepath.as_os_str()
+        );
+        return Err(anyhow!("reload_json of {} could not open file", name));
+    }
+    let reader = BufReader::new(fileres.unwrap());
+    let value: T = serde_json::from_reader(reader)?;
+    //
+    Ok(value)
+} // end of reload_json
+
+// a float as found in json : a number, a non finite float written as a string, or null as written by serde_json
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFloat {
+    Number(f64),
+    Text(String),
+}
+
+fn json_float_to_f64<E: serde::de::Error>(value: Option<JsonFloat>) -> Result<f64, E> {
+    match value {
+        Some(JsonFloat::Number(x)) => Ok(x),
+        Some(JsonFloat::Text(s)) => s
+            .parse::<f64>()
+            .map_err(|_| E::custom(format!("invalid float string : {}", s))),
+        None => Ok(f64::NAN),
+    }
+} // end of json_float_to_f64
+
+/// serde adapter for a f64 field that can be NaN or infinite
+pub mod nonfinite {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else {
+            serializer.serialize_str(&value.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        super::json_float_to_f64(Option::<super::JsonFloat>::deserialize(deserializer)?)
+    }
+} // end of mod nonfinite
+
+/// serde adapter for a `Vec<f64>` field whose values can be NaN or infinite
+pub mod nonfinite_vec {
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for v in values {
+            if v.is_finite() {
+                seq.serialize_element(v)?;
+            } else {
+                seq.serialize_element(&v.to_string())?;
+            }
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        Vec::<Option<super::JsonFloat>>::deserialize(deserializer)?
+            .into_iter()
+            .map(super::json_float_to_f64)
+            .collect()
+    }
+} // end of mod nonfinite_vec
@@ -0,0 +1,767 @@
+//! module to do bson io for label valued embeddings computed by the gkernel module.
+//!
+//! Node embeddings of [MgraphSketch](crate::embed::gkernel::psketch::MgraphSketch) (or [MgraphSketchAsym](crate::embed::gkernel::psketch::MgraphSketchAsym))
+//! are arrays of labels (Nlabel or couples [NElabel](crate::embed::gkernel::pgraph::NElabel)) and global sketches of a collection of graphs
+//! are vectors of labels. They are dumped with the same layout as in [embeddedbson](super::embeddedbson):
+//!
+//! 1. A header document with key "header", see [EmbeddedBsonHeader]. The type_name is the type of labels
+//!    (for example "u16" or "graphembed::embed::gkernel::pgraph::NElabel<u16, u8>").
+//!    For a collection of global sketches nbdata is the number of graphs.
+//!
+//! 2. One document per vector with key "rank,tag" as in embeddedbson. Labels are encoded by serde in Bson
+//!    (integers as i32 or i64, String as string, a NElabel as an array of its 2 labels).
+//!    For asymetric node embeddings and directed global summaries ([GlobalSketchAsym]) OUT vectors are followed by IN vectors.
+//!
+//! 3. Optionally a last document with key *"label_indexation"* giving the original names of nodes and labels, see [LabelIndexation].
+//!    The sub-document with key "node_ids" associates to each node id in data file (as string) its rank in embedding as i64,
+//!    sub-documents with keys "node_labels" and "edge_labels" associate to each original label (as string) the discrete label used in sketching (as string).
+//!
+//! 4. For global sketches dumped with graph identifiers (see [bson_dump_global_sketches_with_ids]) a last document with key *"graph_ids"*
+//!    giving the array of identifiers (as strings) in the order of sketches.
+
+use anyhow::anyhow;
+
+use std::fmt::Display;
+use std::fs::OpenOptions;
+use std::hash::Hash;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+
+use bson::{Bson, Document, bson};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use indexmap::IndexMap;
+use ndarray::{Array1, Array2, ArrayView1};
+use petgraph::graph::NodeIndex;
+
+use super::embeddedbson::EmbeddedBsonHeader;
+use crate::embed::gkernel::globalsketch::GlobalSketchAsym;
+use crate::embed::gkernel::idmap::IdMap;
+use crate::embed::gkernel::pgraph::LabelT;
+use crate::embed::tools::edge::{IN, OUT};
+use crate::embed::tools::jaccard::jaccard_distance;
+use crate::embedding::*;
+
+/// Original names of nodes and labels dumped with a label embedding.
+///
+/// Discrete labels are stored through their string representation so that any label type (u8, u16, ..., String) can be reloaded.
+#[derive(Clone, Debug, Default)]
+pub struct LabelIndexation {
+    /// node id in data file -> rank of node in embedding (its NodeIndex)
+    node_ids: IndexMap<u32, usize>,
+    /// original node label -> discrete node label
+    node_labels: IndexMap<String, String>,
+    /// original edge label -> discrete edge label
+    edge_labels: IndexMap<String, String>,
+} // end of struct LabelIndexation
+
+fn relabel_to_strings<ToLabel, Label>(
+    relabel: &IndexMap<ToLabel, Label>,
+) -> IndexMap<String, String>
+where
+    ToLabel: Display,
+    Label: Display,
+{
+    relabel
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn relabel_from_strings<ToLabel, Label>(
+    relabel: &IndexMap<String, String>,
+) -> anyhow::Result<IndexMap<ToLabel, Label>>
+where
+    ToLabel: FromStr + Eq + Hash,
+    Label: FromStr,
+{
+    let mut map = IndexMap::<ToLabel, Label>::with_capacity(relabel.len());
+    for (k, v) in relabel {
+        let key = ToLabel::from_str(k).map_err(|_| anyhow!("could not decode label {}", k))?;
+        let value = Label::from_str(v).map_err(|_| anyhow!("could not decode label {}", v))?;
+        map.insert(key, value);
+    }
+    Ok(map)
+} // end of relabel_from_strings
+
+impl LabelIndexation {
+    /// an empty indexation
+    pub fn new() -> Self {
+        LabelIndexation::default()
+    }
+
+    /// gets node ids and node labels from the [IdMap] returned by graph loading
+    pub fn from_idmap<ToLabel, Nlabel>(idmap: &IdMap<ToLabel, Nlabel>) -> Self
+    where
+        ToLabel: Eq + Hash + Display,
+        Nlabel: LabelT + Display,
+    {
+        LabelIndexation {
+            node_ids: idmap
+                .get_ranktoidx()
+                .iter()
+                .map(|(id, idx)| (*id, idx.index()))
+                .collect(),
+            node_labels: relabel_to_strings(idmap.get_relabel()),
+            edge_labels: IndexMap::new(),
+        }
+    } // end of from_idmap
+
+    /// sets the correspondance between original node labels and discrete labels (useful when there is no IdMap as in a TuDataset)
+    pub fn set_node_relabel<ToLabel, Nlabel>(&mut self, relabel: &IndexMap<ToLabel, Nlabel>)
+    where
+        ToLabel: Display,
+        Nlabel: Display,
+    {
+        self.node_labels = relabel_to_strings(relabel);
+    }
+
+    /// sets the correspondance between original edge labels and discrete labels
+    pub fn set_edge_relabel<ToLabel, Elabel>(&mut self, relabel: &IndexMap<ToLabel, Elabel>)
+    where
+        ToLabel: Display,
+        Elabel: Display,
+    {
+        self.edge_labels = relabel_to_strings(relabel);
+    }
+
+    /// returns node id in data file -> rank of node in embedding
+    pub fn get_node_ids(&self) -> &IndexMap<u32, usize> {
+        &self.node_ids
+    }
+
+    /// reconstructs the IdMap dumped
+    pub fn get_idmap<ToLabel, Nlabel>(&self) -> anyhow::Result<IdMap<ToLabel, Nlabel>>
+    where
+        ToLabel: FromStr + Eq + Hash,
+        Nlabel: LabelT + FromStr,
+    {
+        let ranktoidx = self
+            .node_ids
+            .iter()
+            .map(|(id, rank)| (*id, NodeIndex::new(*rank)))
+            .collect();
+        Ok(IdMap::new(ranktoidx, self.get_node_relabel()?))
+    } // end of get_idmap
+
+    /// returns the correspondance between original node labels and discrete labels
+    pub fn get_node_relabel<ToLabel, Nlabel>(&self) -> anyhow::Result<IndexMap<ToLabel, Nlabel>>
+    where
+        ToLabel: FromStr + Eq + Hash,
+        Nlabel: FromStr,
+    {
+        relabel_from_strings(&self.node_labels)
+    }
+
+    /// returns the correspondance between original edge labels and discrete labels
+    pub fn get_edge_relabel<ToLabel, Elabel>(&self) -> anyhow::Result<IndexMap<ToLabel, Elabel>>
+    where
+        ToLabel: FromStr + Eq + Hash,
+        Elabel: FromStr,
+    {
+        relabel_from_strings(&self.edge_labels)
+    }
+
+    fn to_document(&self) -> Document {
+        let mut node_ids = Document::new();
+        for (id, rank) in &self.node_ids {
+            node_ids.insert(id.to_string(), *rank as i64);
+        }
+        let to_doc = |relabel: &IndexMap<String, String>| {
+            let mut doc = Document::new();
+            for (k, v) in relabel {
+                doc.insert(k.clone(), v.clone());
+            }
+            doc
+        };
+        let mut doc = Document::new();
+        doc.insert("node_ids", node_ids);
+        doc.insert("node_labels", to_doc(&self.node_labels));
+        doc.insert("edge_labels", to_doc(&self.edge_labels));
+        doc
+    } // end of to_document
+
+    fn from_document(doc: &Document) -> anyhow::Result<Self> {
+        let mut indexation = LabelIndexation::new();
+        for (id, rank) in doc.get_document("node_ids")? {
+            let id = u32::from_str(id).map_err(|_| anyhow!("could not decode node id {}", id))?;
+            let rank = rank
+                .as_i64()
+                .ok_or_else(|| anyhow!("could not get node rank for node_id {}", id))?;
+            indexation.node_ids.insert(id, rank as usize);
+        }
+        let from_doc = |key: &str| -> anyhow::Result<IndexMap<String, String>> {
+            let mut relabel = IndexMap::new();
+            for (k, v) in doc.get_document(key)? {
+                let v = v
+                    .as_str()
+                    .ok_or_else(|| anyhow!("could not decode label for {}", k))?;
+                relabel.insert(k.clone(), v.to_string());
+            }
+            Ok(relabel)
+        };
+        indexation.node_labels = from_doc("node_labels")?;
+        indexation.edge_labels = from_doc("edge_labels")?;
+        Ok(indexation)
+    } // end of from_document
+} // end of impl LabelIndexation
+
+// dumps vectors given by row(rank, tag) and the indexation if any
+fn dump_label_vectors<'a, T, R>(
+    fname: &Path,
+    symetric: bool,
+    nbdata: usize,
+    dim: usize,
+    row: R,
+    indexation: Option<&LabelIndexation>,
+    graph_ids: Option<&[String]>,
+) -> anyhow::Result<()>
+where
+    T: Serialize + 'a,
+    R: Fn(usize, u8) -> ArrayView1<'a, T>,
+{
+    //
+    let fileres = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(fname);
+    if fileres.is_err() {
+        log::error!("could not open file : {}", fname.display());
+        return Err(anyhow!("could not open file : {}", fname.display()));
+    }
+    let mut bufwriter = BufWriter::new(fileres.unwrap());
+    //
+    let mut doc = Document::new();
+    let bson_header = bson!({
+        "version": 1_i64,
+        "symetric": symetric,
+        "type_name": std::any::type_name::<T>(),
+        "dimension": dim as i64,
+        "nbdata": nbdata as i64
+    });
+    doc.insert("header", bson_header);
+    doc.to_writer(&mut bufwriter)?;
+    //
+    let tags = if symetric { vec![OUT] } else { vec![OUT, IN] };
+    for tag in tags {
+        for i in 0..nbdata {
+            let data = row(i, tag)
+                .iter()
+                .map(bson::to_bson)
+                .collect::<Result<Vec<Bson>, _>>()?;
+            let mut doc = Document::new();
+            doc.insert(format!("{},{}", i, tag), data);
+            let res = doc.to_writer(&mut bufwriter);
+            if res.is_err() {
+                log::error!("bson dump error for node {i}, tag {tag}");
+                return Err(anyhow!(
+                    "bson dump error for node {i}, tag {tag} {}",
+                    res.err().unwrap()
+                ));
+            }
+        }
+    }
+    //
+    if let Some(indexation) = indexation {
+        log::info!("\t dumping label indexation");
+        let mut doc = Document::new();
+        doc.insert("label_indexation", indexation.to_document());
+        doc.to_writer(&mut bufwriter)?;
+    }
+    if let Some(graph_ids) = graph_ids {
+        log::info!("\t dumping graph ids");
+        let mut doc = Document::new();
+        doc.insert("graph_ids", graph_ids.to_vec());
+        doc.to_writer(&mut bufwriter)?;
+    }
+    //
+    log::info!("label bson dump in file {} finished", fname.display());
+    //
+    Ok(())
+} // end of dump_label_vectors
+
+/// dumps a label node embedding (symetric or not) as returned by MgraphSketch or MgraphSketchAsym, with an optional indexation.
+pub fn bson_dump_labels<T, EmbeddedData>(
+    embedded: &EmbeddedData,
+    indexation: Option<&LabelIndexation>,
+    fname: &Path,
+) -> anyhow::Result<()>
+where
+    T: Serialize,
+    EmbeddedData: EmbeddedT<T>,
+{
+    log::info!("entering bson_dump_labels, file : {:?}", fname);
+    dump_label_vectors(
+        fname,
+        embedded.is_symetric(),
+        embedded.get_nb_nodes(),
+        embedded.get_dimension(),
+        |i, tag| embedded.get_embedded_node(i, tag),
+        indexation,
+        None,
+    )
+} // end of bson_dump_labels
+
+/// dumps the global sketches of a collection of graphs, all of same size, with an optional indexation
+pub fn bson_dump_global_sketches<T>(
+    sketches: &[Array1<T>],
+    indexation: Option<&LabelIndexation>,
+    fname: &Path,
+) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    log::info!("entering bson_dump_global_sketches, file : {:?}", fname);
+    let dim = sketches.first().map_or(0, |s| s.len());
+    if sketches.iter().any(|s| s.len() != dim) {
+        return Err(anyhow!(
+            "bson_dump_global_sketches sketches must have the same size"
+        ));
+    }
+    dump_label_vectors(
+        fname,
+        true,
+        sketches.len(),
+        dim,
+        |i, _| sketches[i].view(),
+        indexation,
+        None,
+    )
+} // end of bson_dump_global_sketches
+
+/// dumps the global sketches of a collection of graphs, all of same size, with the identifiers of graphs (one for each sketch).
+/// Identifiers are reloaded by [LabelBsonReload::get_graph_ids].
+pub fn bson_dump_global_sketches_with_ids<T>(
+    sketches: &[Array1<T>],
+    graph_ids: &[String],
+    fname: &Path,
+) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    log::info!(
+        "entering bson_dump_global_sketches_with_ids, file : {:?}",
+        fname
+    );
+    let dim = sketches.first().map_or(0, |s| s.len());
+    if sketches.iter().any(|s| s.len() != dim) {
+        return Err(anyhow!(
+            "bson_dump_global_sketches_with_ids sketches must have the same size"
+        ));
+    }
+    if graph_ids.len() != sketches.len() {
+        return Err(anyhow!(
+            "bson_dump_global_sketches_with_ids got {} ids for {} sketches",
+            graph_ids.len(),
+            sketches.len()
+        ));
+    }
+    dump_label_vectors(
+        fname,
+        true,
+        sketches.len(),
+        dim,
+        |i, _| sketches[i].view(),
+        None,
+        Some(graph_ids),
+    )
+} // end of bson_dump_global_sketches_with_ids
+
+/// dumps the directed global summaries of a collection of graphs, all of same size, with an optional indexation
+pub fn bson_dump_global_sketches_asym<T>(
+    sketches: &[GlobalSketchAsym<T>],
+    indexation: Option<&LabelIndexation>,
+    fname: &Path,
+) -> anyhow::Result<()>
+where
+    T: Serialize + Clone + Eq,
+{
+    log::info!(
+        "entering bson_dump_global_sketches_asym, file : {:?}",
+        fname
+    );
+    let dim = sketches.first().map_or(0, |s| s.get_size());
+    if sketches.iter().any(|s| s.get_size() != dim) {
+        return Err(anyhow!(
+            "bson_dump_global_sketches_asym sketches must have the same size"
+        ));
+    }
+    dump_label_vectors(
+        fname,
+        false,
+        sketches.len(),
+        dim,
+        |i, tag| {
+            if tag == OUT {
+                sketches[i].get_out_sketch().view()
+            } else {
+                sketches[i].get_in_sketch().view()
+            }
+        },
+        indexation,
+        None,
+    )
+} // end of bson_dump_global_sketches_asym
+
+/// The structure returned by [bson_load_labels]
+pub struct LabelBsonReload<T> {
+    /// vectors of a symetric dump, or OUT vectors
+    out_embedded: Array2<T>,
+    /// IN vectors of an asymetric dump
+    in_embedded: Option<Array2<T>>,
+    /// indexation if dumped
+    indexation: Option<LabelIndexation>,
+    /// graph identifiers if dumped
+    graph_ids: Option<Vec<String>>,
+} // end of struct LabelBsonReload
+
+impl<T> LabelBsonReload<T>
+where
+    T: Clone + Eq + Send + Sync + 'static,
+{
+    /// returns vectors of a symetric dump, or OUT vectors of an asymetric dump
+    pub fn get_out_embedded(&self) -> &Array2<T> {
+        &self.out_embedded
+    }
+
+    /// returns IN vectors of an asymetric dump
+    pub fn get_in_embedded(&self) -> Option<&Array2<T>> {
+        self.in_embedded.as_ref()
+    }
+
+    /// returns indexation if it was dumped
+    pub fn get_indexation(&self) -> Option<&LabelIndexation> {
+        self.indexation.as_ref()
+    }
+
+    /// returns graph identifiers if they were dumped (see [bson_dump_global_sketches_with_ids])
+    pub fn get_graph_ids(&self) -> Option<&[String]> {
+        self.graph_ids.as_deref()
+    }
+
+    /// reconstructs a symetric node embedding (with Jaccard distance)
+    pub fn into_embedded(self) -> anyhow::Result<Embedded<T>> {
+        if self.in_embedded.is_some() {
+            return Err(anyhow!("into_embedded called on an asymetric dump"));
+        }
+        Ok(Embedded::new(self.out_embedded, jaccard_distance))
+    }
+
+    /// reconstructs an asymetric node embedding (with Jaccard distance)
+    pub fn into_embedded_asym(self) -> anyhow::Result<EmbeddedAsym<T>> {
+        match self.in_embedded {
+            Some(in_embedded) => Ok(EmbeddedAsym::new(
+                self.out_embedded,
+                in_embedded,
+                None,
+                jaccard_distance,
+            )),
+            None => Err(anyhow!("into_embedded_asym called on a symetric dump")),
+        }
+    }
+
+    /// returns the global sketches of a dump done by [bson_dump_global_sketches]
+    pub fn into_global_sketches(self) -> anyhow::Result<Vec<Array1<T>>> {
+        if self.in_embedded.is_some() {
+            return Err(anyhow!("into_global_sketches called on an asymetric dump"));
+        }
+        Ok(self
+            .out_embedded
+            .rows()
+            .into_iter()
+            .map(|r| r.to_owned())
+            .collect())
+    }
+
+    /// returns the directed global summaries of a dump done by [bson_dump_global_sketches_asym]
+    pub fn into_global_sketches_asym(self) -> anyhow::Result<Vec<GlobalSketchAsym<T>>> {
+        match self.in_embedded {
+            Some(in_embedded) => Ok(self
+                .out_embedded
+                .rows()
+                .into_iter()
+                .zip(in_embedded.rows())
+                .map(|(o, i)| GlobalSketchAsym::new(o.to_owned(), i.to_owned()))
+                .collect()),
+            None => Err(anyhow!(
+                "into_global_sketches_asym called on a symetric dump"
+            )),
+        }
+    }
+} // end of impl LabelBsonReload
+
+// reads nbdata vectors with given tag
+fn load_label_vectors<T>(
+    bufreader: &mut BufReader<std::fs::File>,
+    nbdata: usize,
+    dim: usize,
+    tag: u8,
+) -> anyhow::Result<Array2<T>>
+where
+    T: DeserializeOwned + Clone,
+{
+    let mut data = Vec::<T>::with_capacity(nbdata * dim);
+    for i in 0..nbdata {
+        let doc = Document::from_reader(&mut *bufreader)?;
+        let key = format!("{},{}", i, tag);
+        let res = doc.get(&key);
+        if res.is_none() {
+            log::error!("could not get record for key {:?}", key);
+            return Err(anyhow!("could not get record for key {:?}", key));
+        }
+        let data_1d: Vec<T> = bson::from_bson(res.unwrap().clone())?;
+        if data_1d.len() != dim {
+            return Err(anyhow!(
+                "record {} has dimension {}, expecting {}",
+                key,
+                data_1d.len(),
+                dim
+            ));
+        }
+        data.extend(data_1d);
+    }
+    Ok(Array2::from_shape_vec((nbdata, dim), data)?)
+} // end of load_label_vectors
+
+/// reloads a dump done by [bson_dump_labels], [bson_dump_global_sketches], [bson_dump_global_sketches_with_ids] or [bson_dump_global_sketches_asym].
+/// T must be the type of labels dumped.
+pub fn bson_load_labels<T>(fname: &Path) -> anyhow::Result<LabelBsonReload<T>>
+where
+    T: DeserializeOwned + Clone,
+{
+    //
+    log::info!("entering bson_load_labels, file name : {:?}", fname);
+    //
+    let fileres = OpenOptions::new().read(true).open(fname);
+    if fileres.is_err() {
+        log::error!("reload of bson dump failed");
+        return Err(anyhow!("reload failed: {}", fileres.err().unwrap()));
+    }
+    let mut bufreader = BufReader::new(fileres.unwrap());
+    let doc = Document::from_reader(&mut bufreader)?;
+    let bson_header = doc
+        .get("header")
+        .ok_or_else(|| anyhow!("could not find header in document"))?;
+    let header: EmbeddedBsonHeader = bson::from_bson(bson_header.clone())?;
+    log::info!("header : {:?}", header);
+    if header.version != 1 {
+        log::error!("header format version : {}", header.version);
+        return Err(anyhow!("format version error, inconsistent with header"));
+    }
+    let type_name = std::any::type_name::<T>();
+    if header.type_name != type_name {
+        log::error!(
+            "header as type name : {}, reloading with : {}",
+            header.type_name,
+            type_name
+        );
+        return Err(anyhow!("type error, inconsistent with header"));
+    }
+    let nbdata = header.nbdata as usize;
+    let dim = header.dimension as usize;
+    //
+    let out_embedded = load_label_vectors(&mut bufreader, nbdata, dim, OUT)?;
+    let in_embedded = if header.symetric {
+        None
+    } else {
+        Some(load_label_vectors(&mut bufreader, nbdata, dim, IN)?)
+    };
+    // trying indexation and graph ids. We stop at a clean end of file, a truncated or corrupted document is an error
+    let mut indexation = None;
+    let mut graph_ids = None;
+    while !bufreader.fill_buf()?.is_empty() {
+        let doc = Document::from_reader(&mut bufreader)?;
+        if let Ok(doc) = doc.get_document("label_indexation") {
+            indexation = Some(LabelIndexation::from_document(doc)?);
+        } else if let Ok(ids) = doc.get_array("graph_ids") {
+            let ids = ids
+                .iter()
+                .map(|id| {
+                    id.as_str()
+                        .map(|id| id.to_string())
+                        .ok_or_else(|| anyhow!("could not decode graph id {}", id))
+                })
+                .collect::<anyhow::Result<Vec<String>>>()?;
+            graph_ids = Some(ids);
+        } else {
+            return Err(anyhow!(
+                "unexpected document after vectors in file {}",
+                fname.display()
+            ));
+        }
+    }
+    if indexation.is_none() {
+        log::info!("no label indexation in file {}", fname.display());
+    }
+    //
+    Ok(LabelBsonReload {
+        out_embedded,
+        in_embedded,
+        indexation,
+        graph_ids,
+    })
+} // end of bson_load_labels
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::idmap::IdMapper;
+    use crate::embed::gkernel::loader::{LabeledEdge, LabeledGraph, LabeledNode};
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::pgraph::{NElabel, Nweight};
+    use crate::embed::gkernel::psketch::{MgraphSketch, MgraphSketchAsym};
+    use petgraph::{Directed, EdgeType, Undirected};
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // a cycle of 10 nodes with labels alternating 1, 2 and edges labels alternating 1, 2
+    fn labeled_cycle<Ty: EdgeType>() -> LabeledGraph<u16, u8, Ty> {
+        let mut graph = LabeledGraph::<u16, u8, Ty>::default();
+        let nodes: Vec<_> = (0..10)
+            .map(|i| {
+                graph.add_node(LabeledNode::new(
+                    100 + i,
+                    Nweight::new(vec![1 + (i % 2) as u16]),
+                ))
+            })
+            .collect();
+        for i in 0..10 {
+            graph.add_edge(
+                nodes[i],
+                nodes[(i + 1) % 10],
+                LabeledEdge::new(Some(1 + (i % 3 == 0) as u8), 1.),
+            );
+        }
+        graph
+    } // end of labeled_cycle
+
+    #[test]
+    fn test_bson_labels_symetric() {
+        log_init_test();
+        //
+        let dir = std::env::temp_dir();
+        let graph = labeled_cycle::<Undirected>();
+        let ranktoidx = (0..10u32)
+            .map(|i| (100 + i, NodeIndex::new(i as usize)))
+            .collect();
+        let relabel: IndexMap<String, u16> = [(String::from("red"), 1), (String::from("blue"), 2)]
+            .into_iter()
+            .collect();
+        let idmap = IdMap::new(ranktoidx, relabel);
+        let mut indexation = LabelIndexation::from_idmap(&idmap);
+        let edge_relabel: IndexMap<String, u8> =
+            [(String::from("weak"), 1), (String::from("strong"), 2)]
+                .into_iter()
+                .collect();
+        indexation.set_edge_relabel(&edge_relabel);
+        //
+        let skparams = SketchParams::new(20, 0.1, 2, true, false);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, true);
+        skgraph.compute_embedded().unwrap();
+        // node labels
+        let n_embedded = skgraph.get_n_embedded_ref().unwrap();
+        let fname = dir.join("test_bson_labels_n.bson");
+        bson_dump_labels(n_embedded, Some(&indexation), &fname).unwrap();
+        let reloaded = bson_load_labels::<u16>(&fname).unwrap();
+        assert_eq!(reloaded.get_out_embedded(), n_embedded.get_embedded());
+        let reloaded_idmap = reloaded
+            .get_indexation()
+            .unwrap()
+            .get_idmap::<String, u16>()
+            .unwrap();
+        assert_eq!(reloaded_idmap.get_label(String::from("blue")), Some(&2));
+        assert_eq!(reloaded_idmap.get_nodeindex(105), Some(&NodeIndex::new(5)));
+        let edge_relabel = reloaded
+            .get_indexation()
+            .unwrap()
+            .get_edge_relabel::<String, u8>()
+            .unwrap();
+        assert_eq!(edge_relabel.get("strong"), Some(&2));
+        let reloaded_embedded = reloaded.into_embedded().unwrap();
+        assert_eq!(
+            reloaded_embedded.get_noderank_distance(0, 2),
+            n_embedded.get_noderank_distance(0, 2)
+        );
+        // reloading with another label type fails
+        assert!(bson_load_labels::<u32>(&fname).is_err());
+        // (node label, edge label) couples
+        let ne_embedded = skgraph.get_ne_embedded_ref().unwrap();
+        let fname = dir.join("test_bson_labels_ne.bson");
+        bson_dump_labels(ne_embedded, None, &fname).unwrap();
+        let reloaded = bson_load_labels::<NElabel<u16, u8>>(&fname).unwrap();
+        assert!(reloaded.get_indexation().is_none());
+        assert_eq!(reloaded.get_out_embedded(), ne_embedded.get_embedded());
+        // global sketches
+        let global = skgraph.get_global_embedded_n(50).unwrap();
+        let sketches = vec![global.clone(), global];
+        let fname = dir.join("test_bson_labels_global.bson");
+        bson_dump_global_sketches(&sketches, Some(&indexation), &fname).unwrap();
+        let reloaded = bson_load_labels::<u16>(&fname)
+            .unwrap()
+            .into_global_sketches()
+            .unwrap();
+        assert_eq!(reloaded, sketches);
+    } // end of test_bson_labels_symetric
+
+    #[test]
+    fn test_bson_labels_asymetric() {
+        log_init_test();
+        //
+        let dir = std::env::temp_dir();
+        let skparams = SketchParams::new(20, 0.1, 2, false, false);
+        let graph = labeled_cycle::<Directed>();
+        let mut skgraph = MgraphSketchAsym::new(&graph, skparams, false);
+        skgraph.compute_embedded().unwrap();
+        let n_embedded = skgraph.get_n_embedded_ref().unwrap();
+        let fname = dir.join("test_bson_labels_asym.bson");
+        bson_dump_labels(n_embedded, None, &fname).unwrap();
+        let reloaded = bson_load_labels::<u16>(&fname).unwrap();
+        assert_eq!(
+            reloaded.get_out_embedded(),
+            n_embedded.get_embedded_source()
+        );
+        assert_eq!(
+            reloaded.get_in_embedded().unwrap(),
+            n_embedded.get_embedded_target()
+        );
+        assert!(reloaded.into_embedded_asym().is_ok());
+        // directed global summaries
+        let global = skgraph.get_global_embedded_asym_n(50).unwrap();
+        let fname = dir.join("test_bson_labels_global_asym.bson");
+        bson_dump_global_sketches_asym(std::slice::from_ref(&global), None, &fname).unwrap();
+        let reloaded = bson_load_labels::<u16>(&fname)
+            .unwrap()
+            .into_global_sketches_asym()
+            .unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].get_out_sketch(), global.get_out_sketch());
+        assert_eq!(reloaded[0].get_in_sketch(), global.get_in_sketch());
+    } // end of test_bson_labels_asymetric
+
+    // a truncated trailing document must give an error, not a reload without graph ids
+    #[test]
+    fn test_bson_truncated_graph_ids() {
+        log_init_test();
+        //
+        let fname = std::env::temp_dir().join("test_bson_truncated_graph_ids.bson");
+        let sketches: Vec<Array1<u16>> = (0..4u16).map(|i| Array1::from_elem(10, i)).collect();
+        let ids: Vec<String> = (0..4).map(|i| format!("graph_{i}")).collect();
+        bson_dump_global_sketches_with_ids(&sketches, &ids, &fname).unwrap();
+        let reloaded = bson_load_labels::<u16>(&fname).unwrap();
+        assert_eq!(reloaded.get_graph_ids(), Some(ids.as_slice()));
+        // cut the end of graph ids document
+        let len = std::fs::metadata(&fname).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&fname).unwrap();
+        file.set_len(len - 5).unwrap();
+        assert!(bson_load_labels::<u16>(&fname).is_err());
+    } // end of test_bson_truncated_graph_ids
+} // end of mod tests
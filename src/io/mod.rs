@@ -5,4 +5,10 @@ pub mod csv;
 
 pub mod embeddedbson;
 
+/// bson io for label embeddings of the gkernel module
+pub mod labelbson;
+
+/// json dump and reload of result structures
+pub mod jsonio;
+
 pub mod output;
\ No newline at end of file
@@ -10,8 +10,14 @@ pub use crate::embedding::*;
 
 pub use crate::validation::link;
 pub use crate::validation::linkparams::*;
+pub use crate::validation::linkcompare::{NamedEmbedder, compare_embedders};
+pub use crate::validation::linkreport::*;
+pub use crate::validation::nodelabel::{
+    LabelHomophilyReport, estimate_label_homophily, estimate_label_homophily_graph,
+};
 pub use annembed::tools::svdapprox::*;
 //pub use crate::validation::anndensity::*;
 
 pub use crate::structure::density::pava::{IsotonicRegression, PointIterator};
 pub use crate::structure::density::*;
+pub use crate::structure::dcores::dcore_decomposition;
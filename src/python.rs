@@ -152,7 +152,7 @@ fn embed_sketching(
 /* ----------------------------------------------------------------------- */
 
 #[pyfunction]
-#[pyo3(signature = (csv, target_rank, nbiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false))]
+#[pyo3(signature = (csv, target_rank, nbiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false, seed=456231))]
 fn validate_hope_rank(
     csv: &str,
     target_rank: usize,
@@ -161,6 +161,7 @@ fn validate_hope_rank(
     skip_frac: f64,
     symetric: bool,
     centric: bool,
+    seed: u64,
 ) -> PyResult<Vec<f64>> {
     let (trimat, _) = load_csv(csv, symetric).map_err(to_py_err)?;
     let csr = trimat.to_csr();
@@ -173,16 +174,16 @@ fn validate_hope_rank(
         let mut h = Hope::new(params, t);
         h.embed().unwrap()
     };
-    let auc = link::estimate_auc(&csr, nbpass, skip_frac, symetric, &f);
+    let report = link::estimate_auc(&csr, nbpass, skip_frac, symetric, seed, &f);
     if centric {
-        let c_auc = link::estimate_centric_auc(&csr, nbpass, skip_frac, symetric, &f);
-        info!("centric AUC = {:?}", c_auc);
+        let c_report = link::estimate_centric_auc(&csr, nbpass, skip_frac, symetric, seed, &f);
+        info!("centric AUC = {:?}", c_report.get_auc().map(|s| s.get_mean()));
     }
-    Ok(auc)
+    Ok(report.get_auc().unwrap().get_values().to_vec())
 }
 
 #[pyfunction]
-#[pyo3(signature = (csv, epsil, maxrank, blockiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false))]
+#[pyo3(signature = (csv, epsil, maxrank, blockiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false, seed=456231))]
 fn validate_hope_precision(
     csv: &str,
     epsil: f64,
@@ -192,6 +193,7 @@ fn validate_hope_precision(
     skip_frac: f64,
     symetric: bool,
     centric: bool,
+    seed: u64,
 ) -> PyResult<Vec<f64>> {
     let (trimat, _) = load_csv(csv, symetric).map_err(to_py_err)?;
     let csr = trimat.to_csr();
@@ -204,16 +206,16 @@ fn validate_hope_precision(
         let mut h = Hope::new(params, t);
         h.embed().unwrap()
     };
-    let auc = link::estimate_auc(&csr, nbpass, skip_frac, symetric, &f);
+    let report = link::estimate_auc(&csr, nbpass, skip_frac, symetric, seed, &f);
     if centric {
-        let c_auc = link::estimate_centric_auc(&csr, nbpass, skip_frac, symetric, &f);
-        info!("centric AUC = {:?}", c_auc);
+        let c_report = link::estimate_centric_auc(&csr, nbpass, skip_frac, symetric, seed, &f);
+        info!("centric AUC = {:?}", c_report.get_auc().map(|s| s.get_mean()));
     }
-    Ok(auc)
+    Ok(report.get_auc().unwrap().get_values().to_vec())
 }
 
 #[pyfunction]
-#[pyo3(signature = (csv, decay, dim, nbiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false))]
+#[pyo3(signature = (csv, decay, dim, nbiter, nbpass=1, skip_frac=0.2, symetric=true, centric=false, seed=456231))]
 fn validate_sketching(
     csv: &str,
     decay: f64,
@@ -223,6 +225,7 @@ fn validate_sketching(
     skip_frac: f64,
     symetric: bool,
     centric: bool,
+    seed: u64,
 ) -> PyResult<Vec<f64>> {
     let (trimat, _) = load_csv(csv, symetric).map_err(to_py_err)?;
     let csr = trimat.to_csr();
@@ -234,30 +237,30 @@ fn validate_sketching(
         parallel: true,
     };
 
-    let auc = if params.symetric {
+    let report = if params.symetric {
         let f = move |t: TriMatI<f64, usize>| {
             let mut ns = NodeSketch::new(params, t);
             ns.embed().unwrap()
         };
-        link::estimate_auc(&csr, nbpass, skip_frac, symetric, &f)
+        link::estimate_auc(&csr, nbpass, skip_frac, symetric, seed, &f)
     } else {
         let f = move |t: TriMatI<f64, usize>| {
             let mut ns = NodeSketchAsym::new(params, t);
             ns.embed().unwrap()
         };
-        link::estimate_auc(&csr, nbpass, skip_frac, symetric, &f)
+        link::estimate_auc(&csr, nbpass, skip_frac, symetric, seed, &f)
     };
 
     if centric {
         info!("(centric validation not implemented for sketching)");
     }
-    Ok(auc)
+    Ok(report.get_auc().unwrap().get_values().to_vec())
 }
 
 /* ----------------------------  VCMPR  ----------------------------------- */
 
 #[pyfunction]
-#[pyo3(signature = (csv, target_rank, nbiter, nbpass=1, nb_edges=10, skip_frac=0.2, symetric=true))]
+#[pyo3(signature = (csv, target_rank, nbiter, nbpass=1, nb_edges=10, skip_frac=0.2, symetric=true, seed=456231))]
 fn estimate_vcmpr_hope_rank(
     csv: &str,
     target_rank: usize,
@@ -266,6 +269,7 @@ fn estimate_vcmpr_hope_rank(
     nb_edges: usize,
     skip_frac: f64,
     symetric: bool,
+    seed: u64,
 ) -> PyResult<()> {
     let (trimat, _) = load_csv(csv, symetric).map_err(to_py_err)?;
     let csr = trimat.to_csr();
@@ -278,12 +282,12 @@ fn estimate_vcmpr_hope_rank(
         let mut h = Hope::new(params, t);
         h.embed().unwrap()
     };
-    link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, &f);
+    link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, seed, &f);
     Ok(())
 }
 
 #[pyfunction]
-#[pyo3(signature = (csv, decay, dim, nbiter, nbpass=1, nb_edges=10, skip_frac=0.1, symetric=true))]
+#[pyo3(signature = (csv, decay, dim, nbiter, nbpass=1, nb_edges=10, skip_frac=0.1, symetric=true, seed=456231))]
 fn estimate_vcmpr_sketching(
     csv: &str,
     decay: f64,
@@ -293,6 +297,7 @@ fn estimate_vcmpr_sketching(
     nb_edges: usize,
     skip_frac: f64,
     symetric: bool,
+    seed: u64,
 ) -> PyResult<()> {
     let (trimat, _) = load_csv(csv, symetric).map_err(to_py_err)?;
     let csr = trimat.to_csr();
@@ -308,13 +313,13 @@ fn estimate_vcmpr_sketching(
             let mut ns = NodeSketch::new(params, t);
             ns.embed().unwrap()
         };
-        link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, &f);
+        link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, seed, &f);
     } else {
         let f = move |t: TriMatI<f64, usize>| {
             let mut ns = NodeSketchAsym::new(params, t);
             ns.embed().unwrap()
         };
-        link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, &f);
+        link::estimate_vcmpr(&csr, nbpass, nb_edges, skip_frac, symetric, seed, &f);
     }
     Ok(())
 }
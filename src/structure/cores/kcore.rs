@@ -0,0 +1,313 @@
+//! classical k-core decomposition by the bucket algorithm of Batagelj-Zaversnik
+//! _An O(m) Algorithm for Cores Decomposition of Networks_ [2003](https://arxiv.org/abs/cs/0310049)
+//!
+//! The decomposition runs directly on the compressed matrix used by embedders, so there is no need to build a petgraph Graph.
+//! Edge weights and self loops are ignored, and the graph is considered undirected.
+//! For the generalized (weighted, directed) cores see [pcore4](super::pcore::pcore4).
+
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use sprs::CsMatI;
+
+/// The result of a k-core decomposition. Nodes are identified by their rank in the matrix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KCoreDecomposition {
+    /// core number of each node
+    cores: Vec<u32>,
+    /// nodes in the order of removal by the peeling, core numbers are non decreasing along it.
+    ordering: Vec<usize>,
+    /// shell_sizes\[k\] is the number of nodes with core number exactly k
+    shell_sizes: Vec<usize>,
+} // end of struct KCoreDecomposition
+
+impl KCoreDecomposition {
+    /// returns the number of nodes
+    pub fn get_nb_nodes(&self) -> usize {
+        self.cores.len()
+    }
+
+    /// returns core number of a node
+    pub fn get_core(&self, node: usize) -> Option<u32> {
+        self.cores.get(node).copied()
+    }
+
+    /// returns core numbers of all nodes
+    pub fn get_cores(&self) -> &[u32] {
+        &self.cores
+    }
+
+    /// returns the degeneracy of the graph, i.e the maximal core number
+    pub fn get_degeneracy(&self) -> u32 {
+        self.shell_sizes.len().saturating_sub(1) as u32
+    }
+
+    /// returns a degeneracy ordering: each node has at most degeneracy neighbours after it in the ordering.
+    /// Core numbers are non decreasing along the ordering.
+    pub fn get_degeneracy_ordering(&self) -> &[usize] {
+        &self.ordering
+    }
+
+    /// returns the number of nodes in each shell. shell k contains nodes with core number k
+    pub fn get_shell_sizes(&self) -> &[usize] {
+        &self.shell_sizes
+    }
+
+    /// returns nodes of the k-core, i.e with core number >= k
+    pub fn get_kcore_nodes(&self, k: u32) -> Vec<usize> {
+        (0..self.cores.len())
+            .filter(|n| self.cores[*n] >= k)
+            .collect()
+    }
+} // end of impl KCoreDecomposition
+
+/// computes the k-core decomposition of the graph given by a (square) csr matrix in O(m).
+///
+/// - symetric must be set to true if the matrix stores both i->j and j->i for each edge,
+///   (as done by [csv_to_trimat](crate::io::csv::csv_to_trimat) for undirected graphs). In this case rows are used as adjacency lists.
+///   Otherwise the matrix is symetrized, which needs a transposed copy of the matrix structure.
+pub fn kcore_decomposition<F>(csmat: &CsMatI<F, usize>, symetric: bool) -> KCoreDecomposition
+where
+    F: Clone + Default,
+{
+    //
+    assert!(csmat.is_csr());
+    assert_eq!(csmat.rows(), csmat.cols());
+    //
+    kcore_from_adjacency(&get_adjacency(csmat, symetric))
+} // end of kcore_decomposition
+
+// k-core decomposition from adjacency lists as returned by get_adjacency, so that callers
+// needing the adjacency for other purposes build it once.
+pub(crate) fn kcore_from_adjacency(adjacency: &(Vec<usize>, Vec<usize>)) -> KCoreDecomposition {
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let nb_nodes = adjacency.0.len() - 1;
+    let neighbours = |i: usize| &adjacency.1[adjacency.0[i]..adjacency.0[i + 1]];
+    //
+    let mut degrees: Vec<usize> = (0..nb_nodes).map(|i| neighbours(i).len()).collect();
+    let max_degree = degrees.iter().max().copied().unwrap_or(0);
+    // bin[d] is the start in vert of nodes with current degree d
+    let mut bin = vec![0usize; max_degree + 1];
+    for d in &degrees {
+        bin[*d] += 1;
+    }
+    let mut start = 0;
+    for b in bin.iter_mut() {
+        let nb = *b;
+        *b = start;
+        start += nb;
+    }
+    let mut pos = vec![0usize; nb_nodes];
+    let mut vert = vec![0usize; nb_nodes];
+    for v in 0..nb_nodes {
+        pos[v] = bin[degrees[v]];
+        vert[pos[v]] = v;
+        bin[degrees[v]] += 1;
+    }
+    for d in (1..=max_degree).rev() {
+        bin[d] = bin[d - 1];
+    }
+    if !bin.is_empty() {
+        bin[0] = 0;
+    }
+    // peeling, vert is kept sorted by current degrees
+    for i in 0..nb_nodes {
+        let v = vert[i];
+        for &u in neighbours(v) {
+            if degrees[u] > degrees[v] {
+                let du = degrees[u];
+                let pu = pos[u];
+                let pw = bin[du];
+                let w = vert[pw];
+                if u != w {
+                    pos[u] = pw;
+                    vert[pu] = w;
+                    pos[w] = pu;
+                    vert[pw] = u;
+                }
+                bin[du] += 1;
+                degrees[u] -= 1;
+            }
+        }
+    }
+    // degrees now contain core numbers
+    let cores: Vec<u32> = degrees.into_iter().map(|d| d as u32).collect();
+    let degeneracy = cores.iter().max().copied().unwrap_or(0) as usize;
+    let mut shell_sizes = vec![0usize; degeneracy + 1];
+    for c in &cores {
+        shell_sizes[*c as usize] += 1;
+    }
+    //
+    log::info!(
+        "kcore_decomposition nb nodes : {}, degeneracy : {}, sys time(s) {:.2e} cpu time(s) {:.2e}",
+        nb_nodes,
+        degeneracy,
+        sys_start.elapsed().unwrap().as_secs(),
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    KCoreDecomposition {
+        cores,
+        ordering: vert,
+        shell_sizes,
+    }
+} // end of kcore_from_adjacency
+
+// adjacency lists of the undirected graph of the matrix, see kcore_decomposition for the symetric flag. Returns (offsets, neighbours)
+pub(crate) fn get_adjacency<F>(csmat: &CsMatI<F, usize>, symetric: bool) -> (Vec<usize>, Vec<usize>)
+where
+    F: Clone + Default,
+{
+    if symetric {
+        get_row_adjacency(csmat)
+    } else {
+        get_symetrized_adjacency(csmat)
+    }
+} // end of get_adjacency
+
+// adjacency lists from rows, without self loops. Returns (offsets, neighbours)
+fn get_row_adjacency<F>(csmat: &CsMatI<F, usize>) -> (Vec<usize>, Vec<usize>) {
+    let mut offsets = Vec::<usize>::with_capacity(csmat.rows() + 1);
+    let mut neighbours = Vec::<usize>::with_capacity(csmat.nnz());
+    offsets.push(0);
+    for (i, row) in csmat.outer_iterator().enumerate() {
+        neighbours.extend(row.indices().iter().filter(|j| **j != i));
+        offsets.push(neighbours.len());
+    }
+    (offsets, neighbours)
+} // end of get_row_adjacency
+
+// adjacency lists of the union of rows and columns, without self loops nor duplicates. Returns (offsets, neighbours)
+fn get_symetrized_adjacency<F>(csmat: &CsMatI<F, usize>) -> (Vec<usize>, Vec<usize>)
+where
+    F: Clone + Default,
+{
+    let transposed: CsMatI<F, usize> = csmat.transpose_view().to_csr();
+    let mut offsets = Vec::<usize>::with_capacity(csmat.rows() + 1);
+    let mut neighbours = Vec::<usize>::with_capacity(2 * csmat.nnz());
+    offsets.push(0);
+    for (i, (row, col)) in csmat
+        .outer_iterator()
+        .zip(transposed.outer_iterator())
+        .enumerate()
+    {
+        // indices are sorted in both so we merge
+        let (out, inc) = (row.indices(), col.indices());
+        let (mut a, mut b) = (0, 0);
+        while a < out.len() || b < inc.len() {
+            let j = if b >= inc.len() || (a < out.len() && out[a] < inc[b]) {
+                a += 1;
+                out[a - 1]
+            } else if a >= out.len() || inc[b] < out[a] {
+                b += 1;
+                inc[b - 1]
+            } else {
+                a += 1;
+                b += 1;
+                out[a - 1]
+            };
+            if j != i {
+                neighbours.push(j);
+            }
+        }
+        offsets.push(neighbours.len());
+    }
+    (offsets, neighbours)
+} // end of get_symetrized_adjacency
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::tools::degrees::sample_nodes_by_coreness;
+    use crate::io::csv::{csv_to_trimat, weighted_csv_to_graphmap};
+    use crate::structure::cores::pcore::pcore4;
+    use crate::structure::cores::pfunctions::p1;
+    use petgraph::Undirected;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use sprs::TriMatI;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_kcore_small() {
+        log_init_test();
+        // 4-clique 0..3, triangle 3,4,5 , pendant 6 linked to 5, isolated 7 with a self loop
+        let mut edges = Vec::<(usize, usize)>::new();
+        for i in 0..4 {
+            for j in i + 1..4 {
+                edges.push((i, j));
+            }
+        }
+        edges.extend([(3, 4), (4, 5), (5, 3), (5, 6), (7, 7)]);
+        let mut trimat = TriMatI::<f64, usize>::new((8, 8));
+        for (i, j) in &edges {
+            trimat.add_triplet(*i, *j, 1.);
+        }
+        // only one direction stored
+        let decomposition = kcore_decomposition(&trimat.to_csr::<usize>(), false);
+        assert_eq!(decomposition.get_cores(), &[3, 3, 3, 3, 2, 2, 1, 0]);
+        assert_eq!(decomposition.get_degeneracy(), 3);
+        assert_eq!(decomposition.get_shell_sizes(), &[1, 1, 2, 4]);
+        assert_eq!(decomposition.get_kcore_nodes(2), vec![0, 1, 2, 3, 4, 5]);
+        // both directions stored
+        for (i, j) in &edges {
+            if i != j {
+                trimat.add_triplet(*j, *i, 1.);
+            }
+        }
+        let symetric = kcore_decomposition(&trimat.to_csr::<usize>(), true);
+        assert_eq!(symetric.get_cores(), decomposition.get_cores());
+        // core numbers are non decreasing along degeneracy ordering
+        let ordering = symetric.get_degeneracy_ordering();
+        assert_eq!(ordering.len(), 8);
+        for w in ordering.windows(2) {
+            assert!(symetric.get_core(w[0]) <= symetric.get_core(w[1]));
+        }
+        // stratified sampling takes nodes from the inner core and from the outer shells
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(4664);
+        let sampled = sample_nodes_by_coreness(&symetric, 4, 2, &mut rng);
+        assert_eq!(sampled.len(), 4);
+        let inner = sampled
+            .iter()
+            .filter(|n| symetric.get_core(**n) == Some(3))
+            .count();
+        assert_eq!(inner, 2);
+    } // end of test_kcore_small
+
+    // the bucket algorithm must give the same shells as the generic pcore algorithm with degree as p-function
+    #[test]
+    fn test_kcore_miserables() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!("\n\n test_kcore_miserables, loading file {:?}", path);
+        let (trimat, _) = csv_to_trimat::<f64>(&path, false, b' ').unwrap();
+        let csrmat: CsMatI<f64, usize> = trimat.to_csr();
+        let decomposition = kcore_decomposition(&csrmat, true);
+        log::info!("shell sizes : {:?}", decomposition.get_shell_sizes());
+        //
+        let graph = weighted_csv_to_graphmap::<u32, f64, Undirected>(&path, b' ')
+            .unwrap()
+            .into_graph::<u32>();
+        let pcores = pcore4(&graph, p1);
+        let mut shells = vec![0usize; decomposition.get_shell_sizes().len()];
+        for c in pcores.get_cores() {
+            shells[*c as usize] += 1;
+        }
+        assert_eq!(shells.as_slice(), decomposition.get_shell_sizes());
+    } // end of test_kcore_miserables
+} // end of mod tests
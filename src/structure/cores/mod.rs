@@ -1,6 +1,9 @@
 //! core group determination according to Batagelj-Zaversnik
-//! 
+//!
 
 pub mod pfunctions;
 
-pub mod pcore;
\ No newline at end of file
+pub mod pcore;
+
+/// classical k-core decomposition on compressed matrices in O(m)
+pub mod kcore;
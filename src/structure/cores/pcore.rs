@@ -1,20 +1,274 @@
 //! implements p-core determination for p-functions (see associated file)
 //! We implement algo 4 of Batagelj-Zaversnik paper
+//!
+//! For a monotone vertex property function p (i.e $C_{1} \subset C_{2} \Rightarrow p(v,C_{1}) \le p(v,C_{2})$),
+//! the p-core at level t is the maximal subgraph C such that $p(v,C) \ge t$ for all v in C.
+//! The core number of a node is the highest level t of a p-core containing it.
+//! With [p1](super::pfunctions::p1) we get the classical k-cores, with [p11](super::pfunctions::p11) cores of weighted degree,
+//! [p2](super::pfunctions::p2) and [p3](super::pfunctions::p3) give in and out cores of directed graphs.
 
-#![allow(unused)]
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
 
+use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
-use anyhow::anyhow;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
 
 use indexmap::IndexSet;
 
 use ordered_float::OrderedFloat;
-use petgraph::graph::{Graph, NodeIndex};
+use petgraph::EdgeType;
+use petgraph::graph::{Graph, IndexType, NodeIndex};
+
+use crate::io::jsonio::{dump_json, reload_json};
+
+/// a p-function, evaluated for a node with respect to a subset of vertices (see module [pfunctions](super::pfunctions))
+pub type PFunction<N, E, Ty, Ix> =
+    fn(&Graph<N, E, Ty, Ix>, &IndexSet<NodeIndex<Ix>>, NodeIndex<Ix>) -> f64;
+
+/// The result of a p-core decomposition.
+/// Nodes are identified by their index in the graph (see petgraph::NodeIndex)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PCoreDecomposition {
+    /// core value of each node
+    cores: Vec<f64>,
+    /// distinct core values in decreasing order
+    core_values: Vec<f64>,
+    /// core_sizes\[i\] is the number of nodes of the p-core at level core_values\[i\], i.e nodes with core >= core_values\[i\]
+    core_sizes: Vec<usize>,
+} // end of struct PCoreDecomposition
+
+impl PCoreDecomposition {
+    /// builds the decomposition from the core value of each node
+    pub fn new(cores: Vec<f64>) -> Self {
+        let mut sorted = cores.clone();
+        sorted.sort_unstable_by(|a, b| b.total_cmp(a));
+        let mut core_values = Vec::<f64>::new();
+        let mut core_sizes = Vec::<usize>::new();
+        for (rank, c) in sorted.iter().enumerate() {
+            if core_values.last() == Some(c) {
+                *core_sizes.last_mut().unwrap() = rank + 1;
+            } else {
+                core_values.push(*c);
+                core_sizes.push(rank + 1);
+            }
+        }
+        PCoreDecomposition {
+            cores,
+            core_values,
+            core_sizes,
+        }
+    } // end of new
+
+    /// returns the number of nodes
+    pub fn get_nb_nodes(&self) -> usize {
+        self.cores.len()
+    }
+
+    /// returns the core value of a node (by its index)
+    pub fn get_core(&self, node: usize) -> Option<f64> {
+        self.cores.get(node).copied()
+    }
+
+    /// returns core values of all nodes
+    pub fn get_cores(&self) -> &[f64] {
+        &self.cores
+    }
+
+    /// returns the maximal core value
+    pub fn get_max_core(&self) -> Option<f64> {
+        self.core_values.first().copied()
+    }
+
+    /// returns the distinct core values, in decreasing order
+    pub fn get_core_values(&self) -> &[f64] {
+        &self.core_values
+    }
+
+    /// returns the number of nodes of the p-core at each level in [Self::get_core_values], in increasing order
+    pub fn get_core_sizes(&self) -> &[usize] {
+        &self.core_sizes
+    }
+
+    /// returns nodes of the p-core at level t, i.e nodes with core value >= t
+    pub fn get_core_nodes(&self, t: f64) -> Vec<usize> {
+        (0..self.cores.len())
+            .filter(|n| self.cores[*n] >= t)
+            .collect()
+    }
 
+    /// dump in json format
+    pub fn dump_json(&self, filepath: &Path) -> anyhow::Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reload from a json dump
+    pub fn reload_json(filepath: &Path) -> anyhow::Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl PCoreDecomposition
+
+/// computes the p-core decomposition of a graph for a monotone p-function (algorithm 4 of Batagelj-Zaversnik)
+///
+/// The p-function is called with the current set of remaining vertices, see module [pfunctions](super::pfunctions).
+/// Nodes are removed by increasing value of p, and after each removal the p value of the remaining neighbours
+/// (in both directions for directed graphs) are updated.
 pub fn pcore4<N, E, Ty, Ix>(
     graph: &Graph<N, E, Ty, Ix>,
-    pfunction: fn(&Graph<N, E, Ty, Ix>, &IndexSet<NodeIndex<Ix>>, NodeIndex<Ix>) -> f64,
-) {
-    let min_heap = BinaryHeap::<OrderedFloat<f64>>::new();
+    pfunction: PFunction<N, E, Ty, Ix>,
+) -> PCoreDecomposition
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let nb_nodes = graph.node_count();
+    let mut vset: IndexSet<NodeIndex<Ix>> = graph.node_indices().collect();
+    let mut p: Vec<f64> = (0..nb_nodes)
+        .map(|n| pfunction(graph, &vset, NodeIndex::new(n)))
+        .collect();
+    let mut cores = vec![0f64; nb_nodes];
+    // a min heap with lazy update, stale entries are skipped at extraction
+    let mut min_heap: BinaryHeap<Reverse<(OrderedFloat<f64>, usize)>> = (0..nb_nodes)
+        .map(|n| Reverse((OrderedFloat(p[n]), n)))
+        .collect();
+    while let Some(Reverse((value, node))) = min_heap.pop() {
+        let node_idx = NodeIndex::<Ix>::new(node);
+        if value.0 != p[node] || !vset.contains(&node_idx) {
+            continue;
+        }
+        vset.swap_remove(&node_idx);
+        cores[node] = p[node];
+        for neighbour in graph.neighbors_undirected(node_idx) {
+            if vset.contains(&neighbour) {
+                let n = neighbour.index();
+                let new_p = p[node].max(pfunction(graph, &vset, neighbour));
+                if new_p != p[n] {
+                    p[n] = new_p;
+                    min_heap.push(Reverse((OrderedFloat(new_p), n)));
+                }
+            }
+        }
+    }
+    //
+    log::info!(
+        "pcore4 sys time(s) {:.2e} cpu time(s) {:.2e}",
+        sys_start.elapsed().unwrap().as_secs(),
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    PCoreDecomposition::new(cores)
 } // end of pcore4
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::weighted_csv_to_graphmap;
+    use crate::structure::cores::pfunctions::*;
+    use petgraph::{Directed, Undirected};
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // a 4-clique with a triangle hanging from node 3 and a pendant node
+    fn clique_with_triangle() -> Graph<u32, f64, Undirected> {
+        let mut graph = Graph::<u32, f64, Undirected>::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..7).map(|i| graph.add_node(i)).collect();
+        for i in 0..4 {
+            for j in i + 1..4 {
+                graph.add_edge(nodes[i], nodes[j], 1.);
+            }
+        }
+        graph.add_edge(nodes[3], nodes[4], 2.);
+        graph.add_edge(nodes[4], nodes[5], 2.);
+        graph.add_edge(nodes[5], nodes[3], 2.);
+        graph.add_edge(nodes[5], nodes[6], 0.5);
+        graph
+    }
+
+    #[test]
+    fn test_pcore4_degree() {
+        log_init_test();
+        //
+        let graph = clique_with_triangle();
+        let decomposition = pcore4(&graph, p1);
+        log::info!("p1 cores : {:?}", decomposition.get_cores());
+        assert_eq!(decomposition.get_cores(), &[3., 3., 3., 3., 2., 2., 1.]);
+        assert_eq!(decomposition.get_core_values(), &[3., 2., 1.]);
+        assert_eq!(decomposition.get_core_sizes(), &[4, 6, 7]);
+        assert_eq!(decomposition.get_core_nodes(2.), vec![0, 1, 2, 3, 4, 5]);
+        // with weights the triangle is the most cohesive part
+        let decomposition = pcore4(&graph, p11::<u32, f64, Undirected, u32>);
+        log::info!("p11 cores : {:?}", decomposition.get_cores());
+        assert_eq!(decomposition.get_core(4), Some(4.));
+        assert_eq!(decomposition.get_core(5), Some(4.));
+        assert_eq!(decomposition.get_core(0), Some(3.));
+        assert_eq!(decomposition.get_core(6), Some(0.5));
+        //
+        let dumpfile = std::env::temp_dir().join("test_pcore4_degree.json");
+        decomposition.dump_json(&dumpfile).unwrap();
+        let reloaded = PCoreDecomposition::reload_json(&dumpfile).unwrap();
+        assert_eq!(reloaded.get_cores(), decomposition.get_cores());
+        assert_eq!(reloaded.get_core_sizes(), decomposition.get_core_sizes());
+    } // end of test_pcore4_degree
+
+    #[test]
+    fn test_pcore4_directed() {
+        log_init_test();
+        // a directed cycle 0 -> 1 -> 2 -> 0 fed by 3
+        let mut graph = Graph::<u32, f64, Directed>::new();
+        let nodes: Vec<NodeIndex> = (0..4).map(|i| graph.add_node(i)).collect();
+        graph.add_edge(nodes[0], nodes[1], 1.);
+        graph.add_edge(nodes[1], nodes[2], 1.);
+        graph.add_edge(nodes[2], nodes[0], 1.);
+        graph.add_edge(nodes[3], nodes[0], 1.);
+        let incores = pcore4(&graph, p2);
+        assert_eq!(incores.get_cores(), &[1., 1., 1., 0.]);
+        let outcores = pcore4(&graph, p3);
+        assert_eq!(outcores.get_cores(), &[1., 1., 1., 1.]);
+    } // end of test_pcore4_directed
+
+    #[test]
+    fn test_pcore4_miserables() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!("\n\n test_pcore4_miserables, loading file {:?}", path);
+        let res = weighted_csv_to_graphmap::<u32, f64, Undirected>(&path, b' ');
+        if res.is_err() {
+            log::error!("test_pcore4_miserables failed in weighted_csv_to_graphmap");
+            assert_eq!(1, 0);
+        }
+        let graph = res.unwrap().into_graph::<u32>();
+        let decomposition = pcore4(&graph, p1);
+        log::info!(
+            "miserables core values : {:?}, sizes : {:?}",
+            decomposition.get_core_values(),
+            decomposition.get_core_sizes()
+        );
+        // each node of a core at level t has at least t neighbours in the core
+        for t in decomposition.get_core_values() {
+            let core: IndexSet<NodeIndex> = decomposition
+                .get_core_nodes(*t)
+                .into_iter()
+                .map(NodeIndex::new)
+                .collect();
+            for n in &core {
+                assert!(p1(&graph, &core, *n) >= *t);
+            }
+        }
+    } // end of test_pcore4_miserables
+} // end of mod tests
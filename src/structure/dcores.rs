@@ -0,0 +1,281 @@
+//! D-cores of directed graphs according to Giatsidis Thilikos Vazirgiannis
+//! _D-cores: Measuring Collaboration of Directed Graphs Based on Degeneracy_ [2013](https://link.springer.com/article/10.1007/s10115-012-0539-0)
+//!
+//! The (k,l)-D-core of a directed graph is the maximal subgraph in which each node has at least k incoming edges
+//! and at least l outgoing edges (counted inside the subgraph).
+//! D-cores are nested: the (k,l)-D-core contains the (k',l')-D-core as soon as $k \le k'$ and $l \le l'$.
+//!
+//! To get a decomposition in nested blocks of decreasing density we follow the diagonal of the D-core matrix:
+//! the balanced core number of a node is the largest k such that the node is in the (k,k)-D-core.
+//! The blocks are then returned in a [StableDecomposition], block 0 being the innermost D-core,
+//! so that the decomposition can be used for structural validation of asymetric embeddings the same way
+//! the density decomposition of undirected graphs is used in [density_analysis](crate::validation::anndensity::density_analysis),
+//! see [density_analysis_asym](crate::validation::anndensity::density_analysis_asym).
+//!
+//! Edge weights are not taken into account, D-cores rely on in and out degrees.
+
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use ndarray::Array2;
+
+use petgraph::graph::{Graph, IndexType, NodeIndex};
+use petgraph::{Directed, Direction};
+
+use crate::structure::density::stable::StableDecomposition;
+
+/// returns the nodes (by their index) of the (k,l)-D-core of a directed graph.
+/// Each node of the result has in-degree >= k and out-degree >= l in the subgraph induced by the result.
+pub fn get_dcore<N, F, Ix>(graph: &Graph<N, F, Directed, Ix>, k: u32, l: u32) -> Vec<usize>
+where
+    Ix: IndexType,
+{
+    //
+    let nb_nodes = graph.node_count();
+    let mut in_degrees = get_directed_degrees(graph, Direction::Incoming);
+    let mut out_degrees = get_directed_degrees(graph, Direction::Outgoing);
+    let mut removed = vec![false; nb_nodes];
+    // nodes violating the constraints are peeled
+    let mut to_remove: Vec<usize> = (0..nb_nodes)
+        .filter(|n| in_degrees[*n] < k || out_degrees[*n] < l)
+        .collect();
+    to_remove.iter().for_each(|n| removed[*n] = true);
+    while let Some(node) = to_remove.pop() {
+        let node_idx = NodeIndex::<Ix>::new(node);
+        for w in graph.neighbors_directed(node_idx, Direction::Outgoing) {
+            let w = w.index();
+            in_degrees[w] = in_degrees[w].saturating_sub(1);
+            if !removed[w] && in_degrees[w] < k {
+                removed[w] = true;
+                to_remove.push(w);
+            }
+        }
+        for u in graph.neighbors_directed(node_idx, Direction::Incoming) {
+            let u = u.index();
+            out_degrees[u] = out_degrees[u].saturating_sub(1);
+            if !removed[u] && out_degrees[u] < l {
+                removed[u] = true;
+                to_remove.push(u);
+            }
+        }
+    }
+    //
+    (0..nb_nodes).filter(|n| !removed[*n]).collect()
+} // end of get_dcore
+
+/// computes for each node (by its index) the largest k such that the node belongs to the (k,k)-D-core.
+///
+/// This is the peeling algorithm of Batagelj-Zaversnik for the monotone vertex property min(in-degree, out-degree).
+pub fn get_balanced_core_numbers<N, F, Ix>(graph: &Graph<N, F, Directed, Ix>) -> Vec<u32>
+where
+    Ix: IndexType,
+{
+    //
+    let nb_nodes = graph.node_count();
+    let mut in_degrees = get_directed_degrees(graph, Direction::Incoming);
+    let mut out_degrees = get_directed_degrees(graph, Direction::Outgoing);
+    let mut removed = vec![false; nb_nodes];
+    let mut cores = vec![0u32; nb_nodes];
+    // a min heap with lazy update, stale entries are skipped at extraction
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = (0..nb_nodes)
+        .map(|n| Reverse((in_degrees[n].min(out_degrees[n]), n)))
+        .collect();
+    let mut current_core = 0u32;
+    while let Some(Reverse((value, node))) = heap.pop() {
+        if removed[node] || value != in_degrees[node].min(out_degrees[node]) {
+            continue;
+        }
+        current_core = current_core.max(value);
+        cores[node] = current_core;
+        removed[node] = true;
+        let node_idx = NodeIndex::<Ix>::new(node);
+        for w in graph.neighbors_directed(node_idx, Direction::Outgoing) {
+            let w = w.index();
+            if !removed[w] {
+                in_degrees[w] -= 1;
+                heap.push(Reverse((in_degrees[w].min(out_degrees[w]), w)));
+            }
+        }
+        for u in graph.neighbors_directed(node_idx, Direction::Incoming) {
+            let u = u.index();
+            if !removed[u] {
+                out_degrees[u] -= 1;
+                heap.push(Reverse((in_degrees[u].min(out_degrees[u]), u)));
+            }
+        }
+    }
+    //
+    cores
+} // end of get_balanced_core_numbers
+
+/// computes a decomposition of a directed graph in nested balanced D-cores.
+///
+/// Block 0 contains the nodes of the innermost (k,k)-D-core, block i the nodes whose balanced core number is the (i+1)-th largest.
+/// Degrees stored in the decomposition are in-degree + out-degree and block transitions count edges in both directions,
+/// so the result can be used as the decomposition argument of [density_analysis_asym](crate::validation::anndensity::density_analysis_asym).
+pub fn dcore_decomposition<N, F, Ix>(graph: &Graph<N, F, Directed, Ix>) -> StableDecomposition
+where
+    Ix: IndexType,
+{
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let nb_nodes = graph.node_count();
+    let cores = get_balanced_core_numbers(graph);
+    // distinct core values in decreasing order give block numbers
+    let mut core_values = cores.clone();
+    core_values.sort_unstable_by(|a, b| b.cmp(a));
+    core_values.dedup();
+    log::info!(
+        "dcore_decomposition, max balanced core : {:?}, nb blocks : {}",
+        core_values.first(),
+        core_values.len()
+    );
+    let nb_blocks = core_values.len();
+    let blocks: Vec<u32> = cores
+        .iter()
+        .map(|c| core_values.binary_search_by(|v| c.cmp(v)).unwrap() as u32)
+        .collect();
+    //
+    let mut degrees = vec![0u32; nb_nodes];
+    let mut block_transition = Array2::<f32>::zeros((nb_blocks, nb_blocks));
+    for node in 0..nb_nodes {
+        for neighbour in graph.neighbors_undirected(NodeIndex::<Ix>::new(node)) {
+            degrees[node] += 1;
+            block_transition[(blocks[node] as usize, blocks[neighbour.index()] as usize)] += 1.;
+        }
+    }
+    // for each block we get a transition probability to other blocks
+    for i in 0..nb_blocks {
+        let block_degree = block_transition.row(i).iter().sum::<f32>();
+        if block_degree > 0. {
+            block_transition
+                .row_mut(i)
+                .iter_mut()
+                .for_each(|v| *v /= block_degree);
+        }
+    }
+    //
+    log::info!(
+        "dcore_decomposition sys time(s) {:.2e} cpu time(s) {:.2e}",
+        sys_start.elapsed().unwrap().as_secs(),
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    StableDecomposition::new(blocks, degrees, block_transition)
+} // end of dcore_decomposition
+
+// degrees in a given direction, multiple edges are counted
+fn get_directed_degrees<N, F, Ix>(graph: &Graph<N, F, Directed, Ix>, dir: Direction) -> Vec<u32>
+where
+    Ix: IndexType,
+{
+    (0..graph.node_count())
+        .map(|n| {
+            graph
+                .neighbors_directed(NodeIndex::<Ix>::new(n), dir)
+                .count() as u32
+        })
+        .collect()
+} // end of get_directed_degrees
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::unweighted_csv_to_graphmap;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // a complete directed graph on 4 nodes with a directed path hanging from it
+    fn clique_with_tail() -> Graph<u32, f64, Directed> {
+        let mut graph = Graph::<u32, f64, Directed>::new();
+        let nodes: Vec<NodeIndex> = (0..7).map(|i| graph.add_node(i)).collect();
+        for i in 0..4 {
+            for j in 0..4 {
+                if i != j {
+                    graph.add_edge(nodes[i], nodes[j], 1.);
+                }
+            }
+        }
+        // 4 and 5 are mutually linked and linked to the clique in one direction, 6 is a sink
+        graph.add_edge(nodes[0], nodes[4], 1.);
+        graph.add_edge(nodes[4], nodes[5], 1.);
+        graph.add_edge(nodes[5], nodes[4], 1.);
+        graph.add_edge(nodes[5], nodes[1], 1.);
+        graph.add_edge(nodes[4], nodes[6], 1.);
+        graph
+    }
+
+    #[test]
+    fn test_dcores_clique_with_tail() {
+        log_init_test();
+        //
+        let graph = clique_with_tail();
+        let cores = get_balanced_core_numbers(&graph);
+        log::info!("balanced core numbers : {:?}", cores);
+        assert_eq!(cores, vec![3, 3, 3, 3, 1, 1, 0]);
+        //
+        assert_eq!(get_dcore(&graph, 3, 3), vec![0, 1, 2, 3]);
+        assert_eq!(get_dcore(&graph, 1, 1), vec![0, 1, 2, 3, 4, 5]);
+        // 6 has no out edge but in-degree 1
+        assert_eq!(get_dcore(&graph, 1, 0).len(), 7);
+        assert!(get_dcore(&graph, 4, 0).is_empty());
+        //
+        let decomposition = dcore_decomposition(&graph);
+        assert_eq!(decomposition.get_nb_blocks(), 3);
+        let mut block0 = decomposition.get_block_points(0).unwrap();
+        block0.sort_unstable();
+        assert_eq!(block0, vec![0, 1, 2, 3]);
+        assert_eq!(decomposition.get_densest_block(6).unwrap(), 2);
+        assert_eq!(decomposition.get_node_degree(4), 4);
+        let transition = decomposition.get_block_transition();
+        for i in 0..3 {
+            assert!((transition.row(i).sum() - 1.).abs() < 1.0e-5);
+        }
+    } // end of test_dcores_clique_with_tail
+
+    #[test]
+    fn test_dcores_wiki() {
+        log_init_test();
+        // Nodes: 7115 Edges: 103689
+        let path = std::path::Path::new(crate::DATADIR).join("wiki-Vote.txt");
+        log::info!("\n\n test_dcores_wiki, loading file {:?}", path);
+        let res = unweighted_csv_to_graphmap::<u32, Directed>(&path, b'\t');
+        if res.is_err() {
+            log::error!("test_dcores_wiki failed in unweighted_csv_to_graphmap");
+            assert_eq!(1, 0);
+        }
+        let graph = res.unwrap().into_graph::<u32>();
+        let cores = get_balanced_core_numbers(&graph);
+        let kmax = *cores.iter().max().unwrap();
+        log::info!("wiki-Vote max balanced core : {kmax}");
+        assert!(kmax > 0);
+        // the innermost balanced core is the (kmax, kmax)-D-core
+        let innermost = get_dcore(&graph, kmax, kmax);
+        assert!(!innermost.is_empty());
+        for (n, c) in cores.iter().enumerate() {
+            assert_eq!(innermost.binary_search(&n).is_ok(), *c == kmax);
+        }
+        assert!(get_dcore(&graph, kmax + 1, kmax + 1).is_empty());
+        //
+        let decomposition = dcore_decomposition(&graph);
+        let nb_blocks = decomposition.get_nb_blocks();
+        let nb_points: usize = (0..nb_blocks)
+            .map(|b| decomposition.get_nbpoints_in_block(b).unwrap())
+            .sum();
+        assert_eq!(nb_points, graph.node_count());
+        let mut block0 = decomposition.get_block_points(0).unwrap();
+        block0.sort_unstable();
+        assert_eq!(block0, innermost);
+    } // end of test_dcores_wiki
+} // end of mod tests
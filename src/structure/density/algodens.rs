@@ -1,6 +1,6 @@
 //! density decomposition algorithm.
 //!
-//! The graph representation relies on petgraph.
+//! The graph representation relies on petgraph or on a csr matrix, see [DensityGraph].
 //! The function doing the work is [approximate_decomposition]. The result is returned in the structure [StableDecomposition]
 
 use cpu_time::ProcessTime;
@@ -21,14 +21,20 @@ use hdrhistogram::Histogram;
 
 use ndarray::Array2;
 
-use petgraph::graph::{EdgeReference, Graph, NodeIndex};
-use petgraph::{Undirected, visit::*};
+use petgraph::Undirected;
+use petgraph::graph::{Graph, NodeIndex};
 
 // to get sorting with index as result
 //
+use super::densitygraph::DensityGraph;
 use super::pava::{IsotonicRegression, Point, PointBlockLocator, get_point_blocnum};
 use super::stable::StableDecomposition;
 
+/// Number of Frank-Wolfe iterations between 2 checks of the duality gap in [approximate_decomposition_with_tolerance].
+/// The gap costs a pass on edges and nodes so it is not computed at each iteration, the number of iterations done
+/// is thus a multiple of this interval (or max_iter).
+pub const FW_CHECK_INTERVAL: usize = 50;
+
 /// describes weight of each node of an edge.
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct WeightSplit(f32, f32);
@@ -41,25 +47,32 @@ impl Default for WeightSplit {
 
 /// Structure describing how weight of edges is dispatched to nodes.
 #[derive(Copy, Clone)]
-struct EdgeSplit<'a, F> {
-    edge: EdgeReference<'a, F>,
+struct EdgeSplit<F> {
+    source: usize,
+    target: usize,
+    weight: F,
     wsplit: WeightSplit,
 }
 
-impl<'a, F> EdgeSplit<'a, F> {
-    fn new(edge: EdgeReference<'a, F>, wsplit: WeightSplit) -> Self {
-        EdgeSplit { edge, wsplit }
+impl<F> EdgeSplit<F> {
+    fn new(edge: (usize, usize, F), wsplit: WeightSplit) -> Self {
+        EdgeSplit {
+            source: edge.0,
+            target: edge.1,
+            weight: edge.2,
+            wsplit,
+        }
     }
 }
 
 /// Structure describing how the weight of edges is dispatched onto tis vertices.
-struct AlphaR<'a, F> {
+struct AlphaR<F> {
     r: Vec<F>,
-    alpha: Vec<EdgeSplit<'a, F>>,
+    alpha: Vec<EdgeSplit<F>>,
 }
 
-impl<'a, F> AlphaR<'a, F> {
-    fn new(r: Vec<F>, alpha: Vec<EdgeSplit<'a, F>>) -> Self {
+impl<F> AlphaR<F> {
+    fn new(r: Vec<F>, alpha: Vec<EdgeSplit<F>>) -> Self {
         AlphaR { r, alpha }
     }
 
@@ -69,15 +82,25 @@ impl<'a, F> AlphaR<'a, F> {
     }
 
     /// get alpha field
-    pub fn get_alpha(&self) -> &Vec<EdgeSplit<'a, F>> {
+    pub fn get_alpha(&self) -> &Vec<EdgeSplit<F>> {
         &self.alpha
     }
 } // end of impl AlphaR
 
-/// initialize alpha and r (as defined in paper) by Frank-Wolfe algorithm
-/// returns (alpha, r) alpha is dimensioned to number of edges, r is dimensioned to number of vertex
-fn get_alpha_r<'a, N, F>(graph: &'a Graph<N, F, Undirected>, nbiter: usize) -> AlphaR<'a, F>
+/// result of Frank-Wolfe iterations with number of iterations done and relative duality gap reached
+struct FrankWolfeResult<F> {
+    alpha_r: AlphaR<F>,
+    nb_iter: usize,
+    gap: f64,
+}
+
+// initialize alpha and r (as defined in paper) by Frank-Wolfe algorithm
+// alpha is dimensioned to number of edges, r is dimensioned to number of vertex.
+// If tolerance is given, the relative duality gap is checked every FW_CHECK_INTERVAL iterations
+// and iterations stop as soon as it is below tolerance, otherwise nbiter iterations are done.
+fn frank_wolfe<G, F>(graph: &G, nbiter: usize, tolerance: Option<f64>) -> FrankWolfeResult<F>
 where
+    G: DensityGraph<F> + ?Sized,
     F: Float
         + FromPrimitive
         + std::ops::AddAssign<F>
@@ -91,19 +114,19 @@ where
     let cpu_start = ProcessTime::now();
     let sys_start = SystemTime::now();
     // how many nodes and edges
-    let nb_nodes = graph.node_count();
-    let nb_edges = graph.edge_count();
+    let nb_nodes = graph.get_nb_nodes();
+    let nb_edges = graph.get_nb_edges();
     // we will need to update r with // loops on edges.
     // We bet on low degree versus number of edges so low contention (See hogwild)
-    let mut alpha: Vec<Arc<RwLock<EdgeSplit<'a, F>>>> = Vec::with_capacity(nb_edges);
+    let mut alpha: Vec<Arc<RwLock<EdgeSplit<F>>>> = Vec::with_capacity(nb_edges);
     // asynchronuous is really faster! old code is kept for memory
     let asynchronuous = true;
     //
-    let edges = graph.edge_references();
-    for e in edges {
-        let weight = e.weight();
+    for e in 0..nb_edges {
+        let edge = graph.get_edge(e);
+        let weight = edge.2;
         let split = EdgeSplit::new(
-            e,
+            edge,
             WeightSplit(weight.to_f32().unwrap() / 2., weight.to_f32().unwrap() / 2.),
         );
         alpha.push(Arc::new(RwLock::new(split)));
@@ -115,7 +138,7 @@ where
     //
     // a function that computes r from alpha after each iteration
     //
-    let r_from_alpha = |r: &Vec<Arc<RwLock<f32>>>, alpha: &Vec<Arc<RwLock<EdgeSplit<'a, F>>>>| {
+    let r_from_alpha = |r: &Vec<Arc<RwLock<f32>>>, alpha: &Vec<Arc<RwLock<EdgeSplit<F>>>>| {
         // reset r to 0
         (0..nb_nodes).into_par_iter().for_each(|i| {
             *r[i].write() = 0.;
@@ -123,16 +146,43 @@ where
         // alpha's load transferred to r
         (0..alpha.len()).into_par_iter().for_each(|i| {
             let alpha_i = alpha[i].read();
-            *r[alpha_i.edge.source().index()].write() += alpha_i.wsplit.0;
+            *r[alpha_i.source].write() += alpha_i.wsplit.0;
             // process target
-            *r[alpha_i.edge.target().index()].write() += alpha_i.wsplit.1;
+            *r[alpha_i.target].write() += alpha_i.wsplit.1;
         });
     };
     //
     // We dispatch alpha to r
     r_from_alpha(&r, &alpha);
     //
+    // relative duality gap of min sum r_v^2, gap = 1 - sum_e w_e * min(r_u, r_v) / sum_v r_v^2
+    let relative_gap = |r: &Vec<Arc<RwLock<f32>>>, alpha: &Vec<Arc<RwLock<EdgeSplit<F>>>>| -> f64 {
+        let norm: f64 = r.par_iter().map(|v| (*v.read() as f64).powi(2)).sum();
+        let fw_dot: f64 = alpha
+            .par_iter()
+            .map(|a| {
+                let a = a.read();
+                let r_source = *r[a.source].read();
+                let r_target = *r[a.target].read();
+                a.weight.to_f64().unwrap() * r_source.min(r_target) as f64
+            })
+            .sum();
+        if norm > 0. { 1. - fw_dot / norm } else { 0. }
+    };
+    //
+    let mut nb_iter = nbiter;
     for iter in 0..nbiter {
+        if let Some(tolerance) = tolerance
+            && iter > 0
+            && iter % FW_CHECK_INTERVAL == 0
+        {
+            let gap = relative_gap(&r, &alpha);
+            log::info!("iteration : {}, relative gap : {:.3e}", iter, gap);
+            if gap <= tolerance {
+                nb_iter = iter;
+                break;
+            }
+        }
         let gamma = 2. / (2. + iter as f32);
         //
         if iter % 100 == 0 {
@@ -142,26 +192,26 @@ where
         (0..alpha.len()).into_par_iter().for_each(|i| {
             let mut delta_i = WeightSplit::default();
             let mut alpha_i = alpha[i].write();
-            let source = alpha_i.edge.source();
-            let target = alpha_i.edge.target();
-            let r_source = *r[source.index()].read();
-            let r_target = *r[target.index()].read();
+            let source = alpha_i.source;
+            let target = alpha_i.target;
+            let r_source = *r[source].read();
+            let r_target = *r[target].read();
             // get edge node with min r. The smaller gets the weight
             if r_source < r_target {
-                delta_i.0 = alpha_i.edge.weight().to_f32().unwrap(); // andd delta_i.1 = 0.;
+                delta_i.0 = alpha_i.weight.to_f32().unwrap(); // andd delta_i.1 = 0.;
                 alpha_i.wsplit.0 = (1. - gamma) * alpha_i.wsplit.0 + gamma * delta_i.0;
                 alpha_i.wsplit.1 *= 1. - gamma;
                 if asynchronuous {
-                    *r[source.index()].write() += (delta_i.0 - alpha_i.wsplit.0) * gamma;
-                    *r[target.index()].write() += (0. - alpha_i.wsplit.1) * gamma;
+                    *r[source].write() += (delta_i.0 - alpha_i.wsplit.0) * gamma;
+                    *r[target].write() += (0. - alpha_i.wsplit.1) * gamma;
                 }
             } else if r_target < r_source {
-                delta_i.1 = alpha_i.edge.weight().to_f32().unwrap(); // and delta_i.0 = 0.
+                delta_i.1 = alpha_i.weight.to_f32().unwrap(); // and delta_i.0 = 0.
                 alpha_i.wsplit.0 *= 1. - gamma;
                 alpha_i.wsplit.1 = (1. - gamma) * alpha_i.wsplit.1 + gamma * delta_i.1;
                 if asynchronuous {
-                    *r[source.index()].write() += (0. - alpha_i.wsplit.0) * gamma;
-                    *r[target.index()].write() += (delta_i.1 - alpha_i.wsplit.1) * gamma;
+                    *r[source].write() += (0. - alpha_i.wsplit.0) * gamma;
+                    *r[target].write() += (delta_i.1 - alpha_i.wsplit.1) * gamma;
                 }
             }
             // else e do nothing!
@@ -171,18 +221,28 @@ where
             r_from_alpha(&r, &alpha);
         }
     } // end of // loop on edges
+    let gap = relative_gap(&r, &alpha);
+    log::info!(
+        "frank_wolfe nb iterations : {}, relative gap : {:.3e}",
+        nb_iter,
+        gap
+    );
     // We do not need locks any more, simplify
     let r_s: Vec<F> = r.iter().map(|v| F::from(*v.read()).unwrap()).collect();
-    let alpha_s: Vec<EdgeSplit<'a, F>> = alpha.iter().map(|a| *a.read()).collect();
+    let alpha_s: Vec<EdgeSplit<F>> = alpha.iter().map(|a| *a.read()).collect();
     //
     log::info!(
-        "frank_wolfe (fn get_alpha_r) sys time(s) {:.2e} cpu time(s) {:.2e}",
+        "frank_wolfe sys time(s) {:.2e} cpu time(s) {:.2e}",
         sys_start.elapsed().unwrap().as_secs(),
         cpu_start.elapsed().as_secs()
     );
     //
-    AlphaR::new(r_s, alpha_s)
-} // end of get_alpha_r
+    FrankWolfeResult {
+        alpha_r: AlphaR::new(r_s, alpha_s),
+        nb_iter,
+        gap,
+    }
+} // end of frank_wolfe
 
 /// returns the dgree of a node
 pub fn get_degree_undirected<N, F>(
@@ -198,12 +258,13 @@ pub fn get_degree_undirected<N, F>(
 } // end of get_degree_undirected
 
 /// check stability of a given vertex block with respect to alfar (algo 2 of Danisch paper)
-fn check_stability<'a, F, N>(
-    graph: &'a Graph<N, F, Undirected>,
-    alphar: &'a AlphaR<'a, F>,
-    iso_regression: &'a IsotonicRegression<F>,
+fn check_stability<G, F>(
+    graph: &G,
+    alphar: &AlphaR<F>,
+    iso_regression: &IsotonicRegression<F>,
 ) -> StableDecomposition
 where
+    G: DensityGraph<F> + ?Sized,
     F: Float
         + std::iter::Sum
         + FromPrimitive
@@ -213,14 +274,13 @@ where
         + std::fmt::Debug
         + Sync
         + Send,
-    N: Copy,
 {
     //
     let cpu_start = ProcessTime::now();
     let sys_start = SystemTime::now();
     //
     let nb_reg_blocks = iso_regression.get_nb_block();
-    let nb_nodes = graph.node_count();
+    let nb_nodes = graph.get_nb_nodes();
     let mut degrees = (0..nb_nodes).map(|_| 0).collect::<Vec<u32>>();
     let pointblocklocator = PointBlockLocator::new(iso_regression);
     //
@@ -242,40 +302,33 @@ where
         // TODO this iteration can be made // if necessary
         let ptiter = block.get_point_iter();
         for (&_pt, rank_pt) in ptiter {
-            let pt_idx = NodeIndex::new(rank_pt);
-            points_waiting.push(pt_idx.index());
+            points_waiting.push(rank_pt);
             // rank guve us the index in graph
             let mut degree = 0;
-            let mut neighbours = graph.neighbors(pt_idx).detach();
             // is neighbor in block
-            while let Some((edge_idx, neighbor)) = neighbours.next(graph) {
+            for (neighbor_u, edge_idx) in graph.get_neighbours(rank_pt) {
                 degree += 1;
-                let neighbor_u = neighbor.index();
                 // TODO >= or ==
                 let neighbour_block = pointblocklocator.get_point_block_num(neighbor_u).unwrap();
                 block_transition[(numbloc, neighbour_block)] += 1.;
                 if neighbour_block > numbloc {
                     // then we get edge corresponding to (pt , neighbor), modify alfa. Cannot fail
-                    let edge = graph.edge_endpoints(edge_idx).unwrap();
+                    let edge = &alfa_tmp[edge_idx];
                     // we must check for order. We have the same order of of the 2-uple in wsplit and in edge
-                    if edge.0 == pt_idx && edge.1 == neighbor {
-                        r_test[edge.0.index()] -=
-                            F::from(alfa_tmp[edge_idx.index()].wsplit.0).unwrap();
-                        r_test[edge.1.index()] +=
-                            F::from(alfa_tmp[edge_idx.index()].wsplit.0).unwrap();
-                    } else if edge.0 == neighbor && edge.1 == pt_idx {
-                        r_test[edge.1.index()] -=
-                            F::from(alfa_tmp[edge_idx.index()].wsplit.1).unwrap();
-                        r_test[edge.0.index()] +=
-                            F::from(alfa_tmp[edge_idx.index()].wsplit.1).unwrap();
+                    if edge.source == rank_pt && edge.target == neighbor_u {
+                        r_test[edge.source] -= F::from(edge.wsplit.0).unwrap();
+                        r_test[edge.target] += F::from(edge.wsplit.0).unwrap();
+                    } else if edge.source == neighbor_u && edge.target == rank_pt {
+                        r_test[edge.target] -= F::from(edge.wsplit.1).unwrap();
+                        r_test[edge.source] += F::from(edge.wsplit.1).unwrap();
                     } else {
                         panic!("should not happen");
                     }
                 }
             } // end while on neighbours
             // affect degree for this node
-            assert_eq!(degrees[pt_idx.index()], 0); // consistency check...
-            degrees[pt_idx.index()] = degree;
+            assert_eq!(degrees[rank_pt], 0); // consistency check...
+            degrees[rank_pt] = degree;
         } // end while on point in blocks
         // we must check that r is greater on block than outside
         let mut min_in_block = F::max_value();
@@ -379,9 +432,39 @@ where
 
 /// computes an approximate decomposition of graph in blocks of vertices of decreasing density.  
 /// nb_iter is the number of iteration asked for. A standard value is 500.
-pub fn approximate_decomposition<N, F>(
-    graph: &Graph<N, F, Undirected>,
-    nbiter: usize,
+///
+/// The graph can be an undirected petgraph Graph or a symetric compressed matrix wrapped in a [CsrGraph](super::densitygraph::CsrGraph),
+/// edge weights are taken into account.
+///
+/// The relative duality gap reached is stored in the result, see [StableDecomposition::get_gap].
+pub fn approximate_decomposition<G, F>(graph: &G, nbiter: usize) -> StableDecomposition
+where
+    F: Float
+        + std::fmt::Debug
+        + std::iter::Sum
+        + FromPrimitive
+        + std::ops::AddAssign
+        + std::ops::DivAssign
+        + std::ops::SubAssign
+        + Sync
+        + Send,
+    G: DensityGraph<F> + ?Sized,
+{
+    let fw_result = frank_wolfe(graph, nbiter, None);
+    decomposition_from_frank_wolfe(graph, fw_result)
+} // end of approximate_decomposition
+
+/// computes an approximate decomposition of graph in blocks of vertices of decreasing density,
+/// stopping Frank-Wolfe iterations as soon as the relative duality gap is below tolerance (checked every [FW_CHECK_INTERVAL] iterations).
+///
+/// The gap is 1 - sum_e w_e min(r_u, r_v) / sum_v r_v^2 where r are the loads of nodes and (u,v) the nodes of edge e.
+/// At most max_iter iterations are done. The number of iterations done and the gap reached are stored in the result
+/// (see [StableDecomposition::get_nb_iter] and [StableDecomposition::get_gap]).
+/// A tolerance of 1.0e-3 is a reasonable choice.
+pub fn approximate_decomposition_with_tolerance<G, F>(
+    graph: &G,
+    tolerance: f64,
+    max_iter: usize,
 ) -> StableDecomposition
 where
     F: Float
@@ -393,30 +476,50 @@ where
         + std::ops::SubAssign
         + Sync
         + Send,
-    N: Copy,
+    G: DensityGraph<F> + ?Sized,
+{
+    let fw_result = frank_wolfe(graph, max_iter, Some(tolerance));
+    if fw_result.gap > tolerance {
+        log::warn!(
+            "approximate_decomposition_with_tolerance, tolerance {:.3e} not reached after {} iterations, gap : {:.3e}",
+            tolerance,
+            max_iter,
+            fw_result.gap
+        );
+    }
+    decomposition_from_frank_wolfe(graph, fw_result)
+} // end of approximate_decomposition_with_tolerance
+
+// PAVA and stability check after Frank-Wolfe iterations
+fn decomposition_from_frank_wolfe<G, F>(
+    graph: &G,
+    fw_result: FrankWolfeResult<F>,
+) -> StableDecomposition
+where
+    F: Float
+        + std::fmt::Debug
+        + std::iter::Sum
+        + FromPrimitive
+        + std::ops::AddAssign
+        + std::ops::DivAssign
+        + std::ops::SubAssign
+        + Sync
+        + Send,
+    G: DensityGraph<F> + ?Sized,
 {
     //
-    let cpu_start = ProcessTime::now();
-    let sys_start = SystemTime::now();
-    //
-    let alpha_r = get_alpha_r(graph, nbiter);
-    log::info!(
-        "fn get_alpha_r sys time(s) {:.2e} cpu time(s) {:.2e}",
-        sys_start.elapsed().unwrap().as_secs(),
-        cpu_start.elapsed().as_secs()
-    );
-    //
+    let alpha_r = fw_result.alpha_r;
     let alpha = alpha_r.get_alpha();
     let r = alpha_r.get_r();
     //
     let mut y: Vec<F> = (0..r.len()).map(|_| F::zero()).collect();
     for esplit in alpha {
         let node_max = if esplit.wsplit.0 > esplit.wsplit.1 {
-            esplit.edge.source().index()
+            esplit.source
         } else {
-            esplit.edge.target().index()
+            esplit.target
         };
-        y[node_max] += *esplit.edge.weight();
+        y[node_max] += esplit.weight;
     } // end of for i
     // go to PAVA algorithm , the decomposition of y in blocks makes a tentative decomposition
     // as -r increases , y decreases. We begin algo by densest blocks!
@@ -440,7 +543,8 @@ where
     log::info!(" unionization and stability check");
     let cpu_start = ProcessTime::now();
     let sys_start = SystemTime::now();
-    let s = check_stability(graph, &alpha_r, &iso_regression);
+    let mut s = check_stability(graph, &alpha_r, &iso_regression);
+    s.set_convergence(fw_result.nb_iter, fw_result.gap);
     //
     log::info!(
         "\n approximate_decomposition sys time(s) {:.2e} cpu time(s) {:.2e}",
@@ -449,7 +553,7 @@ where
     );
     //
     s
-} // end of approximate_decomposition
+} // end of decomposition_from_frank_wolfe
 
 /// log::info histograms of degrees of incremental blocks S_i whose union make B_i
 #[allow(unused)]
@@ -518,20 +622,20 @@ mod tests {
             assert_eq!(1, 0);
         }
         // now we can convert into a Graph
-        let graph = res.unwrap().into_graph();
+        let graph = res.unwrap().into_graph::<u32>();
         // check get_alpha_r
-        let alpha_r = get_alpha_r(&graph, 400);
+        let alpha_r = frank_wolfe(&graph, 400, None).alpha_r;
         let alpha = alpha_r.get_alpha();
         let r = alpha_r.get_r();
         //
         let mut y: Vec<f64> = (0..r.len()).into_iter().map(|_| 0.).collect();
         for i in 0..alpha.len() {
             let node_max = if alpha[i].wsplit.0 > alpha[i].wsplit.1 {
-                alpha[i].edge.source().index()
+                alpha[i].source
             } else {
-                alpha[i].edge.target().index()
+                alpha[i].target
             };
-            y[node_max] += *alpha[i].edge.weight();
+            y[node_max] += alpha[i].weight;
         } // end of for i
         // go to PAVA algorithm , the decomposition of y in blocks makes a tentative decomposition
         // as -r increases , y decreases. We begin algo by densest blocks!
@@ -598,7 +702,7 @@ mod tests {
             assert_eq!(1, 0);
         }
         // now we can convert into a Graph
-        let graph = res.unwrap().into_graph();
+        let graph = res.unwrap().into_graph::<u32>();
         //
         let nb_iter = 100;
         let decomposition = approximate_decomposition(&graph, nb_iter);
@@ -627,5 +731,52 @@ mod tests {
                 block
             );
         }
+        assert_eq!(decomposition.get_nb_iter(), Some(nb_iter));
+        assert!(decomposition.get_gap().is_some());
+    }
+
+    #[test]
+    fn density_miserables_tolerance() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n algodens::density_miserables_tolerance, loading file {:?}",
+            path
+        );
+        let res = weighted_csv_to_graphmap::<u32, f64, Undirected>(&path, b' ');
+        if res.is_err() {
+            log::error!("algodens::density_miserables_tolerance failed in csv_to_trimat");
+            assert_eq!(1, 0);
+        }
+        let graph = res.unwrap().into_graph::<u32>();
+        //
+        let max_iter = 2000;
+        let tolerance = 1.0e-2;
+        let decomposition = approximate_decomposition_with_tolerance(&graph, tolerance, max_iter);
+        let nb_iter = decomposition.get_nb_iter().unwrap();
+        let gap = decomposition.get_gap().unwrap();
+        log::info!("density_miserables_tolerance nb_iter : {nb_iter}, gap : {gap:.3e}");
+        assert!(nb_iter <= max_iter);
+        if nb_iter < max_iter {
+            assert!(gap <= tolerance);
+        }
+        // iterations stop at a gap check
+        assert!(nb_iter == max_iter || nb_iter % FW_CHECK_INTERVAL == 0);
+        // a tighter tolerance is checked against its own tolerance
+        let tighter = tolerance / 10.;
+        let decomposition_tight =
+            approximate_decomposition_with_tolerance(&graph, tighter, max_iter);
+        let nb_iter_tight = decomposition_tight.get_nb_iter().unwrap();
+        if nb_iter_tight < max_iter {
+            assert!(decomposition_tight.get_gap().unwrap() <= tighter);
+        }
+        // convergence is kept in json dump
+        let dumpfile = std::env::temp_dir().join("density_miserables_tolerance.json");
+        decomposition.dump_json(&dumpfile).unwrap();
+        let reloaded = StableDecomposition::reload_json(&dumpfile).unwrap();
+        assert_eq!(reloaded.get_nb_iter(), Some(nb_iter));
     }
 } // end of mod tests
@@ -0,0 +1,225 @@
+//! Graph access needed by the density decomposition.
+//!
+//! The decomposition can run on an undirected petgraph Graph or directly on the symetric compressed matrix
+//! produced by [csv_to_trimat](crate::io::csv::csv_to_trimat) (with directed set to false) and used by embedders,
+//! so that there is no need to keep a second copy of a large graph in memory.
+
+use anyhow::anyhow;
+
+use petgraph::Undirected;
+use petgraph::graph::{EdgeIndex, Graph, IndexType, NodeIndex};
+
+use sprs::CsMatI;
+
+/// What the density decomposition needs to know of an undirected weighted graph.
+///
+/// Edges are identified by an index in 0..get_nb_edges(), each undirected edge having exactly one index.
+pub trait DensityGraph<F> {
+    /// number of nodes
+    fn get_nb_nodes(&self) -> usize;
+    /// number of undirected edges
+    fn get_nb_edges(&self) -> usize;
+    /// returns (source, target, weight) of edge of index e
+    fn get_edge(&self, e: usize) -> (usize, usize, F);
+    /// returns neighbours of a node, each with the index of the edge joining them
+    fn get_neighbours(&self, node: usize) -> Vec<(usize, usize)>;
+}
+
+impl<N, F, Ix> DensityGraph<F> for Graph<N, F, Undirected, Ix>
+where
+    F: Copy,
+    Ix: IndexType,
+{
+    fn get_nb_nodes(&self) -> usize {
+        self.node_count()
+    }
+
+    fn get_nb_edges(&self) -> usize {
+        self.edge_count()
+    }
+
+    fn get_edge(&self, e: usize) -> (usize, usize, F) {
+        let edge_idx = EdgeIndex::<Ix>::new(e);
+        let (source, target) = self.edge_endpoints(edge_idx).unwrap();
+        (source.index(), target.index(), self[edge_idx])
+    }
+
+    fn get_neighbours(&self, node: usize) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::<(usize, usize)>::new();
+        let mut walker = self.neighbors(NodeIndex::<Ix>::new(node)).detach();
+        while let Some((edge_idx, neighbour)) = walker.next(self) {
+            neighbours.push((neighbour.index(), edge_idx.index()));
+        }
+        neighbours
+    }
+} // end of impl DensityGraph for Graph
+
+/// A symetric csr matrix seen as an undirected weighted graph.
+///
+/// The undirected edge {i,j} is the matrix entry (i,j) with i <= j, its weight is the value of this entry.
+/// Only an index of size the number of nodes is allocated.
+pub struct CsrGraph<'a, F> {
+    csmat: &'a CsMatI<F, usize>,
+    /// upper_start\[i\] is the index of the first edge stored in row i (edges (i,j) with i <= j)
+    upper_start: Vec<usize>,
+}
+
+impl<'a, F> CsrGraph<'a, F>
+where
+    F: Copy,
+{
+    /// wraps a square csr matrix. An error is returned if the matrix is not structurally symetric.
+    pub fn new(csmat: &'a CsMatI<F, usize>) -> anyhow::Result<Self> {
+        if !csmat.is_csr() || csmat.rows() != csmat.cols() {
+            return Err(anyhow!("CsrGraph::new needs a square csr matrix"));
+        }
+        let mut upper_start = Vec::<usize>::with_capacity(csmat.rows() + 1);
+        upper_start.push(0);
+        for (i, row) in csmat.outer_iterator().enumerate() {
+            for j in row.indices() {
+                if csmat.get_outer_inner(*j, i).is_none() {
+                    log::error!(
+                        "CsrGraph::new matrix not symetric, entry ({i},{j}) has no transpose"
+                    );
+                    return Err(anyhow!("CsrGraph::new needs a symetric matrix"));
+                }
+            }
+            let nb_upper = row.indices().len() - row.indices().partition_point(|j| *j < i);
+            upper_start.push(upper_start[i] + nb_upper);
+        }
+        Ok(CsrGraph { csmat, upper_start })
+    } // end of new
+
+    // index of entry (i,j) with i <= j
+    fn get_edge_index(&self, i: usize, j: usize) -> usize {
+        let row = self.csmat.outer_view(i).unwrap();
+        let indices = row.indices();
+        let first_upper = indices.partition_point(|k| *k < i);
+        let pos = indices.binary_search(&j).unwrap();
+        self.upper_start[i] + pos - first_upper
+    }
+} // end of impl CsrGraph
+
+impl<F> DensityGraph<F> for CsrGraph<'_, F>
+where
+    F: Copy,
+{
+    fn get_nb_nodes(&self) -> usize {
+        self.csmat.rows()
+    }
+
+    fn get_nb_edges(&self) -> usize {
+        self.upper_start[self.csmat.rows()]
+    }
+
+    fn get_edge(&self, e: usize) -> (usize, usize, F) {
+        // row i such that upper_start[i] <= e < upper_start[i+1]
+        let i = self.upper_start.partition_point(|s| *s <= e) - 1;
+        let row = self.csmat.outer_view(i).unwrap();
+        let first_upper = row.indices().partition_point(|k| *k < i);
+        let pos = first_upper + e - self.upper_start[i];
+        (i, row.indices()[pos], row.data()[pos])
+    }
+
+    fn get_neighbours(&self, node: usize) -> Vec<(usize, usize)> {
+        let row = self.csmat.outer_view(node).unwrap();
+        let indices = row.indices();
+        let first_upper = indices.partition_point(|k| *k < node);
+        indices
+            .iter()
+            .enumerate()
+            .map(|(pos, j)| {
+                if pos >= first_upper {
+                    (*j, self.upper_start[node] + pos - first_upper)
+                } else {
+                    (*j, self.get_edge_index(*j, node))
+                }
+            })
+            .collect()
+    }
+} // end of impl DensityGraph for CsrGraph
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::{csv_to_trimat, weighted_csv_to_graphmap};
+    use crate::structure::density::algodens::approximate_decomposition;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_csrgraph_edges() {
+        log_init_test();
+        // triangle 0,1,2 with weights and a self loop on 3 linked to 2
+        let mut trimat = sprs::TriMatI::<f64, usize>::new((4, 4));
+        for (i, j, w) in [(0, 1, 1.), (1, 2, 2.), (0, 2, 3.), (2, 3, 4.)] {
+            trimat.add_triplet(i, j, w);
+            trimat.add_triplet(j, i, w);
+        }
+        trimat.add_triplet(3, 3, 5.);
+        let csmat: CsMatI<f64, usize> = trimat.to_csr();
+        let graph = CsrGraph::new(&csmat).unwrap();
+        assert_eq!(graph.get_nb_nodes(), 4);
+        assert_eq!(graph.get_nb_edges(), 5);
+        assert_eq!(graph.get_edge(2), (1, 2, 2.));
+        assert_eq!(graph.get_edge(4), (3, 3, 5.));
+        // each edge index seen from its 2 ends
+        for node in 0..4 {
+            for (neighbour, e) in graph.get_neighbours(node) {
+                let (i, j, _) = graph.get_edge(e);
+                assert!((i, j) == (node, neighbour) || (i, j) == (neighbour, node));
+            }
+        }
+        // a directed matrix is rejected
+        let mut directed = sprs::TriMatI::<f64, usize>::new((2, 2));
+        directed.add_triplet(0, 1, 1.);
+        assert!(CsrGraph::new(&directed.to_csr::<usize>()).is_err());
+    } // end of test_csrgraph_edges
+
+    // decomposition from csr matrix and from petgraph Graph
+    #[test]
+    fn test_csrgraph_decomposition_miserables() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n test_csrgraph_decomposition_miserables, loading file {:?}",
+            path
+        );
+        let (trimat, _) = csv_to_trimat::<f64>(&path, false, b' ').unwrap();
+        let csmat: CsMatI<f64, usize> = trimat.to_csr();
+        let csrgraph = CsrGraph::new(&csmat).unwrap();
+        let nb_iter = 500;
+        let from_csr = approximate_decomposition(&csrgraph, nb_iter);
+        //
+        let graph = weighted_csv_to_graphmap::<u32, f64, Undirected>(&path, b' ')
+            .unwrap()
+            .into_graph::<u32>();
+        assert_eq!(csrgraph.get_nb_edges(), graph.edge_count());
+        let from_graph = approximate_decomposition(&graph, nb_iter);
+        let block_sizes = |d: &crate::structure::density::StableDecomposition| {
+            (0..d.get_nb_blocks())
+                .map(|b| d.get_nbpoints_in_block(b).unwrap())
+                .collect::<Vec<usize>>()
+        };
+        log::info!("block sizes csr : {:?}", block_sizes(&from_csr));
+        log::info!("block sizes graph : {:?}", block_sizes(&from_graph));
+        // Frank-Wolfe iterations are asynchronous, so we only check consistency of results
+        let nb_nodes = graph.node_count();
+        assert_eq!(block_sizes(&from_csr).iter().sum::<usize>(), nb_nodes);
+        assert_eq!(block_sizes(&from_graph).iter().sum::<usize>(), nb_nodes);
+        log::info!(
+            "gap csr : {:?}, gap graph : {:?}",
+            from_csr.get_gap(),
+            from_graph.get_gap()
+        );
+    } // end of test_csrgraph_decomposition_miserables
+} // end of mod tests
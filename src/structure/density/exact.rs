@@ -0,0 +1,620 @@
+//! Exact density-friendly decomposition for small graphs.
+//!
+//! This is the decomposition of Tatti-Gionis _Density-friendly graph decomposition_ [2015](https://dl.acm.org/doi/10.1145/2736277.2741119),
+//! whose approximation is computed by [approximate_decomposition](super::algodens::approximate_decomposition).
+//! Block $B_{i}$ is the maximal set containing $B_{i-1}$ maximizing $(e(B) - e(B_{i-1}))/(|B| - |B_{i-1}|)$.
+//!
+//! For a given $\lambda$ the maximal set S maximizing $e(S) - \lambda |S|$ with $S \supset B_{i-1}$ is obtained by a minimum cut
+//! (Goldberg construction), and the best ratio is reached by Dinkelbach iterations on $\lambda$.
+//! Each block needs a few max flow computations, so this is meant for graphs up to about $10^{5}$ edges,
+//! to certify the approximate decomposition (see [compare_decompositions]) and tune its number of iterations.
+
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
+
+use std::collections::VecDeque;
+
+use num_traits::float::Float;
+
+use serde::{Deserialize, Serialize};
+
+use ndarray::Array2;
+
+use petgraph::Undirected;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+use super::stable::StableDecomposition;
+
+// above this number of edges exact decomposition will be slow
+const EXACT_NB_EDGES_WARNING: usize = 100_000;
+
+// relative tolerance on flows and densities
+const EXACT_EPSIL: f64 = 1.0e-9;
+
+/// The exact decomposition and the density of its blocks.
+pub struct ExactDecomposition {
+    /// blocks as in approximate decomposition
+    decomposition: StableDecomposition,
+    /// density of each block S_i: (e(B_i) - e(B_{i-1})) / |S_i|
+    densities: Vec<f64>,
+    /// number of max flow computations done
+    nb_flows: usize,
+} // end of struct ExactDecomposition
+
+impl ExactDecomposition {
+    /// returns the decomposition in blocks
+    pub fn get_decomposition(&self) -> &StableDecomposition {
+        &self.decomposition
+    }
+
+    /// returns density of blocks, in decreasing order
+    pub fn get_block_densities(&self) -> &[f64] {
+        &self.densities
+    }
+
+    /// returns number of max flow computations that were necessary
+    pub fn get_nb_flows(&self) -> usize {
+        self.nb_flows
+    }
+} // end of impl ExactDecomposition
+
+/// Agreement between the exact decomposition and an approximate one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DecompositionComparison {
+    /// number of blocks of exact decomposition
+    nb_blocks_exact: usize,
+    /// number of blocks of approximate decomposition
+    nb_blocks_approx: usize,
+    /// fraction of nodes assigned to the same block number
+    same_block_fraction: f64,
+    /// jaccard index between cumulated blocks B_i of both decompositions, for i less than the min of the number of blocks
+    block_jaccard: Vec<f64>,
+    /// density of blocks in exact decomposition
+    exact_densities: Vec<f64>,
+    /// density of blocks in approximate decomposition, computed on the graph
+    approx_densities: Vec<f64>,
+    /// mean over nodes of the relative error of the density of the block of the node
+    mean_density_error: f64,
+    /// max over nodes of the relative error of the density of the block of the node
+    max_density_error: f64,
+} // end of struct DecompositionComparison
+
+impl DecompositionComparison {
+    /// number of blocks of exact decomposition
+    pub fn get_nb_blocks_exact(&self) -> usize {
+        self.nb_blocks_exact
+    }
+
+    /// number of blocks of approximate decomposition
+    pub fn get_nb_blocks_approx(&self) -> usize {
+        self.nb_blocks_approx
+    }
+
+    /// fraction of nodes having the same block number in both decompositions
+    pub fn get_same_block_fraction(&self) -> f64 {
+        self.same_block_fraction
+    }
+
+    /// jaccard index between cumulated blocks of both decompositions
+    pub fn get_block_jaccard(&self) -> &[f64] {
+        &self.block_jaccard
+    }
+
+    /// density of blocks of exact decomposition
+    pub fn get_exact_densities(&self) -> &[f64] {
+        &self.exact_densities
+    }
+
+    /// density of blocks of approximate decomposition
+    pub fn get_approx_densities(&self) -> &[f64] {
+        &self.approx_densities
+    }
+
+    /// mean relative density error by node
+    pub fn get_mean_density_error(&self) -> f64 {
+        self.mean_density_error
+    }
+
+    /// max relative density error by node
+    pub fn get_max_density_error(&self) -> f64 {
+        self.max_density_error
+    }
+} // end of impl DecompositionComparison
+
+//==========================================================================================================
+
+// Dinic max flow on a graph with real capacities
+struct FlowNetwork {
+    // for each node the list of its arcs, as indexes in to and cap
+    adjacency: Vec<Vec<usize>>,
+    to: Vec<usize>,
+    cap: Vec<f64>,
+    epsil: f64,
+}
+
+impl FlowNetwork {
+    fn new(nb_nodes: usize, epsil: f64) -> Self {
+        FlowNetwork {
+            adjacency: vec![Vec::new(); nb_nodes],
+            to: Vec::new(),
+            cap: Vec::new(),
+            epsil,
+        }
+    }
+
+    // adds arc u->v with capacity cap_uv and its reverse with capacity cap_vu. Arc a and a^1 are reverse of each other
+    fn add_arc(&mut self, u: usize, v: usize, cap_uv: f64, cap_vu: f64) {
+        self.adjacency[u].push(self.to.len());
+        self.to.push(v);
+        self.cap.push(cap_uv);
+        self.adjacency[v].push(self.to.len());
+        self.to.push(u);
+        self.cap.push(cap_vu);
+    }
+
+    fn bfs_levels(&self, s: usize, t: usize, level: &mut [i64]) -> bool {
+        level.iter_mut().for_each(|l| *l = -1);
+        level[s] = 0;
+        let mut queue = VecDeque::from([s]);
+        while let Some(u) = queue.pop_front() {
+            for &a in &self.adjacency[u] {
+                let v = self.to[a];
+                if level[v] < 0 && self.cap[a] > self.epsil {
+                    level[v] = level[u] + 1;
+                    queue.push_back(v);
+                }
+            }
+        }
+        level[t] >= 0
+    }
+
+    // blocking flow by iterative dfs
+    fn push_blocking(&mut self, s: usize, t: usize, level: &[i64], iter: &mut [usize]) -> f64 {
+        let mut total = 0.;
+        loop {
+            // find an augmenting path in level graph
+            let mut path = Vec::<usize>::new();
+            let mut u = s;
+            while u != t {
+                let mut advanced = false;
+                while iter[u] < self.adjacency[u].len() {
+                    let a = self.adjacency[u][iter[u]];
+                    let v = self.to[a];
+                    if self.cap[a] > self.epsil && level[v] == level[u] + 1 {
+                        path.push(a);
+                        u = v;
+                        advanced = true;
+                        break;
+                    }
+                    iter[u] += 1;
+                }
+                if !advanced {
+                    if u == s {
+                        return total;
+                    }
+                    // dead end, retreat
+                    let a = path.pop().unwrap();
+                    u = self.to[a ^ 1];
+                    iter[u] += 1;
+                }
+            }
+            let delta = path
+                .iter()
+                .map(|a| self.cap[*a])
+                .fold(f64::INFINITY, f64::min);
+            for a in &path {
+                self.cap[*a] -= delta;
+                self.cap[*a ^ 1] += delta;
+            }
+            total += delta;
+        }
+    }
+
+    fn max_flow(&mut self, s: usize, t: usize) -> f64 {
+        let nb_nodes = self.adjacency.len();
+        let mut level = vec![-1i64; nb_nodes];
+        let mut flow = 0.;
+        while self.bfs_levels(s, t, &mut level) {
+            let mut iter = vec![0usize; nb_nodes];
+            flow += self.push_blocking(s, t, &level, &mut iter);
+        }
+        flow
+    }
+
+    // after max flow, nodes that cannot reach t in residual graph. This is the maximal source side of a min cut.
+    fn maximal_source_side(&self, t: usize) -> Vec<bool> {
+        let mut reach_t = vec![false; self.adjacency.len()];
+        reach_t[t] = true;
+        let mut queue = VecDeque::from([t]);
+        while let Some(x) = queue.pop_front() {
+            for &a in &self.adjacency[x] {
+                // a goes x->u, a^1 goes u->x
+                let u = self.to[a];
+                if !reach_t[u] && self.cap[a ^ 1] > self.epsil {
+                    reach_t[u] = true;
+                    queue.push_back(u);
+                }
+            }
+        }
+        reach_t.iter().map(|r| !r).collect()
+    }
+} // end of impl FlowNetwork
+
+// weighted edges as (u, v, w), and weighted degrees (self loops count twice)
+fn get_weighted_edges<N, F>(graph: &Graph<N, F, Undirected>) -> (Vec<(usize, usize, f64)>, Vec<f64>)
+where
+    F: Float,
+{
+    let mut degrees = vec![0f64; graph.node_count()];
+    let edges: Vec<(usize, usize, f64)> = graph
+        .edge_references()
+        .map(|e| {
+            let w = e.weight().to_f64().unwrap();
+            let (u, v) = (e.source().index(), e.target().index());
+            degrees[u] += w;
+            degrees[v] += w;
+            (u, v, w)
+        })
+        .collect();
+    (edges, degrees)
+}
+
+// weight of edges inside a set
+fn get_inner_weight(edges: &[(usize, usize, f64)], inset: &[bool]) -> f64 {
+    edges
+        .iter()
+        .filter(|(u, v, _)| inset[*u] && inset[*v])
+        .map(|e| e.2)
+        .sum()
+}
+
+// maximal S containing forced maximizing e(S) - lambda * |S|
+fn get_maximal_maximizer(
+    edges: &[(usize, usize, f64)],
+    degrees: &[f64],
+    forced: &[bool],
+    lambda: f64,
+    epsil: f64,
+) -> Vec<bool> {
+    let nb_nodes = degrees.len();
+    let (s, t) = (nb_nodes, nb_nodes + 1);
+    let mut network = FlowNetwork::new(nb_nodes + 2, epsil);
+    for (v, d) in degrees.iter().enumerate() {
+        if forced[v] {
+            network.add_arc(s, v, f64::INFINITY, 0.);
+        } else if *d > 2. * lambda {
+            network.add_arc(s, v, d - 2. * lambda, 0.);
+        } else if *d < 2. * lambda {
+            network.add_arc(v, t, 2. * lambda - d, 0.);
+        }
+    }
+    for (u, v, w) in edges {
+        if u != v {
+            network.add_arc(*u, *v, *w, *w);
+        }
+    }
+    network.max_flow(s, t);
+    let mut side = network.maximal_source_side(t);
+    side.truncate(nb_nodes);
+    side
+} // end of get_maximal_maximizer
+
+// builds degrees and block transitions as in approximate decomposition
+fn make_stable_decomposition<N, F>(
+    graph: &Graph<N, F, Undirected>,
+    blocks: Vec<u32>,
+    nb_blocks: usize,
+) -> StableDecomposition {
+    let nb_nodes = graph.node_count();
+    let mut degrees = vec![0u32; nb_nodes];
+    let mut block_transition = Array2::<f32>::zeros((nb_blocks, nb_blocks));
+    for (node, degree) in degrees.iter_mut().enumerate() {
+        for neighbour in graph.neighbors(NodeIndex::new(node)) {
+            *degree += 1;
+            block_transition[(blocks[node] as usize, blocks[neighbour.index()] as usize)] += 1.;
+        }
+    }
+    for i in 0..nb_blocks {
+        let block_degree = block_transition.row(i).iter().sum::<f32>();
+        if block_degree > 0. {
+            block_transition
+                .row_mut(i)
+                .iter_mut()
+                .for_each(|v| *v /= block_degree);
+        }
+    }
+    StableDecomposition::new(blocks, degrees, block_transition)
+} // end of make_stable_decomposition
+
+/// computes the exact density-friendly decomposition of an undirected (weighted) graph
+pub fn exact_decomposition<N, F>(graph: &Graph<N, F, Undirected>) -> ExactDecomposition
+where
+    F: Float,
+{
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let nb_nodes = graph.node_count();
+    if graph.edge_count() > EXACT_NB_EDGES_WARNING {
+        log::warn!(
+            "exact_decomposition on a graph with {} edges, this can be long",
+            graph.edge_count()
+        );
+    }
+    let (edges, degrees) = get_weighted_edges(graph);
+    let total_weight: f64 = edges.iter().map(|e| e.2).sum();
+    let epsil = EXACT_EPSIL * total_weight.max(1.);
+    //
+    let mut blocks = vec![0u32; nb_nodes];
+    let mut densities = Vec::<f64>::new();
+    let mut current = vec![false; nb_nodes];
+    let mut nb_current = 0usize;
+    let mut weight_current = 0.;
+    let mut nb_flows = 0;
+    while nb_current < nb_nodes {
+        // Dinkelbach iterations, starting from density of what remains
+        let mut lambda = (total_weight - weight_current) / (nb_nodes - nb_current) as f64;
+        let next = loop {
+            let candidate = get_maximal_maximizer(&edges, &degrees, &current, lambda, epsil);
+            nb_flows += 1;
+            let nb_candidate = candidate.iter().filter(|b| **b).count();
+            let gain = get_inner_weight(&edges, &candidate) - weight_current;
+            let added = (nb_candidate - nb_current) as f64;
+            if added == 0. || gain - lambda * added <= epsil {
+                break candidate;
+            }
+            lambda = gain / added;
+        };
+        let nb_next = next.iter().filter(|b| **b).count();
+        if nb_next == nb_current {
+            // can happen only by numerical trouble, we take all that remains
+            log::error!(
+                "exact_decomposition could not increase block {}",
+                densities.len()
+            );
+            let remaining = (total_weight - weight_current) / (nb_nodes - nb_current) as f64;
+            (0..nb_nodes)
+                .filter(|v| !current[*v])
+                .for_each(|v| blocks[v] = densities.len() as u32);
+            densities.push(remaining);
+            break;
+        }
+        log::debug!(
+            "exact_decomposition block {} size {} density {:.3e}",
+            densities.len(),
+            nb_next - nb_current,
+            lambda
+        );
+        for v in 0..nb_nodes {
+            if next[v] && !current[v] {
+                blocks[v] = densities.len() as u32;
+            }
+        }
+        densities.push(lambda);
+        current = next;
+        nb_current = nb_next;
+        weight_current = get_inner_weight(&edges, &current);
+    }
+    //
+    log::info!(
+        "exact_decomposition nb blocks : {}, nb flows : {}, sys time(s) {:.2e} cpu time(s) {:.2e}",
+        densities.len(),
+        nb_flows,
+        sys_start.elapsed().unwrap().as_secs(),
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    let decomposition = make_stable_decomposition(graph, blocks, densities.len());
+    ExactDecomposition {
+        decomposition,
+        densities,
+        nb_flows,
+    }
+} // end of exact_decomposition
+
+/// computes the density (e(B_i) - e(B_{i-1})) / |S_i| of each block of a decomposition
+pub fn get_block_densities<N, F>(
+    graph: &Graph<N, F, Undirected>,
+    decomposition: &StableDecomposition,
+) -> Vec<f64>
+where
+    F: Float,
+{
+    let (edges, _) = get_weighted_edges(graph);
+    let mut inset = vec![false; graph.node_count()];
+    let mut weight = 0.;
+    let mut densities = Vec::<f64>::with_capacity(decomposition.get_nb_blocks());
+    for b in 0..decomposition.get_nb_blocks() {
+        let points = decomposition.get_block_points(b).unwrap();
+        points.iter().for_each(|p| inset[*p] = true);
+        let new_weight = get_inner_weight(&edges, &inset);
+        densities.push((new_weight - weight) / points.len().max(1) as f64);
+        weight = new_weight;
+    }
+    densities
+} // end of get_block_densities
+
+/// compares an approximate decomposition (see [approximate_decomposition](super::algodens::approximate_decomposition)) with the exact one.
+pub fn compare_decompositions<N, F>(
+    graph: &Graph<N, F, Undirected>,
+    exact: &ExactDecomposition,
+    approx: &StableDecomposition,
+) -> DecompositionComparison
+where
+    F: Float,
+{
+    //
+    let nb_nodes = graph.node_count();
+    let exact_dec = exact.get_decomposition();
+    let exact_densities = exact.get_block_densities().to_vec();
+    let approx_densities = get_block_densities(graph, approx);
+    //
+    let mut nb_same = 0usize;
+    let mut mean_density_error = 0.;
+    let mut max_density_error: f64 = 0.;
+    for v in 0..nb_nodes {
+        let (be, ba) = (
+            exact_dec.get_densest_block(v).unwrap(),
+            approx.get_densest_block(v).unwrap(),
+        );
+        if be == ba {
+            nb_same += 1;
+        }
+        let d = exact_densities[be];
+        let error = if d > 0. {
+            (approx_densities[ba] - d).abs() / d
+        } else {
+            approx_densities[ba].abs()
+        };
+        mean_density_error += error;
+        max_density_error = max_density_error.max(error);
+    }
+    if nb_nodes > 0 {
+        mean_density_error /= nb_nodes as f64;
+    }
+    // jaccard between cumulated blocks
+    let nb_common = exact_dec.get_nb_blocks().min(approx.get_nb_blocks());
+    let mut block_jaccard = Vec::<f64>::with_capacity(nb_common);
+    let (mut in_exact, mut in_approx) = (vec![false; nb_nodes], vec![false; nb_nodes]);
+    for b in 0..nb_common {
+        exact_dec
+            .get_block_points(b)
+            .unwrap()
+            .iter()
+            .for_each(|p| in_exact[*p] = true);
+        approx
+            .get_block_points(b)
+            .unwrap()
+            .iter()
+            .for_each(|p| in_approx[*p] = true);
+        let inter = (0..nb_nodes)
+            .filter(|v| in_exact[*v] && in_approx[*v])
+            .count();
+        let union = (0..nb_nodes)
+            .filter(|v| in_exact[*v] || in_approx[*v])
+            .count();
+        block_jaccard.push(inter as f64 / union.max(1) as f64);
+    }
+    //
+    let comparison = DecompositionComparison {
+        nb_blocks_exact: exact_dec.get_nb_blocks(),
+        nb_blocks_approx: approx.get_nb_blocks(),
+        same_block_fraction: nb_same as f64 / nb_nodes.max(1) as f64,
+        block_jaccard,
+        exact_densities,
+        approx_densities,
+        mean_density_error,
+        max_density_error,
+    };
+    log::info!(
+        "compare_decompositions nb blocks exact : {}, approx : {}, same block fraction : {:.3e}, mean density error : {:.3e}, max : {:.3e}",
+        comparison.nb_blocks_exact,
+        comparison.nb_blocks_approx,
+        comparison.same_block_fraction,
+        comparison.mean_density_error,
+        comparison.max_density_error
+    );
+    comparison
+} // end of compare_decompositions
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::weighted_csv_to_graphmap;
+    use crate::structure::density::algodens::approximate_decomposition;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 4-clique, triangle linked to clique by an edge and a pendant node linked to the triangle
+    fn clique_triangle_pendant() -> Graph<u32, f64, Undirected> {
+        let mut graph = Graph::<u32, f64, Undirected>::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..8).map(|i| graph.add_node(i)).collect();
+        for i in 0..4 {
+            for j in i + 1..4 {
+                graph.add_edge(nodes[i], nodes[j], 1.);
+            }
+        }
+        graph.add_edge(nodes[4], nodes[5], 1.);
+        graph.add_edge(nodes[5], nodes[6], 1.);
+        graph.add_edge(nodes[6], nodes[4], 1.);
+        graph.add_edge(nodes[3], nodes[4], 1.);
+        graph.add_edge(nodes[6], nodes[7], 1.);
+        graph
+    }
+
+    #[test]
+    fn test_exact_decomposition_small() {
+        log_init_test();
+        //
+        let graph = clique_triangle_pendant();
+        let exact = exact_decomposition(&graph);
+        let densities = exact.get_block_densities();
+        log::info!("block densities : {:?}", densities);
+        assert_eq!(densities.len(), 3);
+        assert!((densities[0] - 1.5).abs() < 1.0e-10);
+        assert!((densities[1] - 4. / 3.).abs() < 1.0e-10);
+        assert!((densities[2] - 1.).abs() < 1.0e-10);
+        let decomposition = exact.get_decomposition();
+        let mut block0 = decomposition.get_block_points(0).unwrap();
+        block0.sort_unstable();
+        assert_eq!(block0, vec![0, 1, 2, 3]);
+        assert_eq!(decomposition.get_densest_block(5).unwrap(), 1);
+        assert_eq!(decomposition.get_densest_block(7).unwrap(), 2);
+        // densities recomputed from blocks must be the same
+        let recomputed = get_block_densities(&graph, decomposition);
+        for (d, r) in densities.iter().zip(recomputed.iter()) {
+            assert!((d - r).abs() < 1.0e-10);
+        }
+        // comparison of exact decomposition with itself
+        let comparison = compare_decompositions(&graph, &exact, decomposition);
+        assert_eq!(comparison.get_nb_blocks_exact(), 3);
+        assert_eq!(comparison.get_same_block_fraction(), 1.);
+        assert!(comparison.get_block_jaccard().iter().all(|j| *j == 1.));
+        assert!(comparison.get_max_density_error() < 1.0e-10);
+    } // end of test_exact_decomposition_small
+
+    #[test]
+    fn test_exact_decomposition_miserables() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n test_exact_decomposition_miserables, loading file {:?}",
+            path
+        );
+        let res = weighted_csv_to_graphmap::<u32, f64, Undirected>(&path, b' ');
+        if res.is_err() {
+            log::error!("test_exact_decomposition_miserables failed in weighted_csv_to_graphmap");
+            assert_eq!(1, 0);
+        }
+        let graph = res.unwrap().into_graph();
+        let exact = exact_decomposition(&graph);
+        let densities = exact.get_block_densities();
+        // densities of blocks of a density friendly decomposition are decreasing
+        for w in densities.windows(2) {
+            assert!(w[0] > w[1]);
+        }
+        for nbiter in [10, 500] {
+            let approx = approximate_decomposition(&graph, nbiter);
+            let comparison = compare_decompositions(&graph, &exact, &approx);
+            log::info!("nbiter : {nbiter}, comparison : {:?}", comparison);
+            assert!(comparison.get_max_density_error() >= comparison.get_mean_density_error());
+            assert!(
+                comparison
+                    .get_block_jaccard()
+                    .iter()
+                    .all(|j| (0. ..=1.).contains(j))
+            );
+        }
+    } // end of test_exact_decomposition_miserables
+} // end of mod tests
@@ -11,6 +11,13 @@ pub use pava::{IsotonicRegression, PointIterator};
 
 pub mod algodens;
 pub use stable::*;
-pub use algodens::{approximate_decomposition};
+pub use algodens::{approximate_decomposition, approximate_decomposition_with_tolerance};
 /// Describe stable block results
-pub mod stable;
\ No newline at end of file
+pub mod stable;
+
+/// Exact decomposition by max flow, for small graphs
+pub mod exact;
+
+/// Graph representations accepted by the decomposition (petgraph Graph or csr matrix)
+pub mod densitygraph;
+pub use densitygraph::{CsrGraph, DensityGraph};
\ No newline at end of file
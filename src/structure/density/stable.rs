@@ -47,6 +47,12 @@ pub struct StableDecomposition {
     degrees: Vec<u32>,
     /// We count edges crossing from block i to block j.
     block_transition: Array2<f32>,
+    /// number of Frank-Wolfe iterations done, if decomposition comes from [approximate_decomposition](super::algodens::approximate_decomposition)
+    #[serde(default)]
+    nb_iter: Option<usize>,
+    /// relative duality gap reached by Frank-Wolfe iterations
+    #[serde(default)]
+    gap: Option<f64>,
 } // end of struct StabeDecomposition
 
 impl StableDecomposition {
@@ -97,9 +103,27 @@ impl StableDecomposition {
             block_start,
             degrees,
             block_transition,
+            nb_iter: None,
+            gap: None,
         }
     } // end of new StableDecomposition
 
+    // records convergence of iterations
+    pub(crate) fn set_convergence(&mut self, nb_iter: usize, gap: f64) {
+        self.nb_iter = Some(nb_iter);
+        self.gap = Some(gap);
+    }
+
+    /// returns the number of Frank-Wolfe iterations used to get the decomposition (None if not obtained by iterations)
+    pub fn get_nb_iter(&self) -> Option<usize> {
+        self.nb_iter
+    }
+
+    /// returns the relative duality gap reached by Frank-Wolfe iterations (None if not obtained by iterations)
+    pub fn get_gap(&self) -> Option<f64> {
+        self.gap
+    }
+
     /// get number of points in a block
     pub fn get_nbpoints_in_block(&self, blocknum: usize) -> Result<usize, ()> {
         let size = self.block_start.len();
@@ -0,0 +1,552 @@
+//! Community detection by modularity optimization, Louvain algorithm of Blondel Guillaume Lambiotte Lefebvre
+//! _Fast unfolding of communities in large networks_ [2008](https://arxiv.org/abs/0803.0476)
+//!
+//! The modularity of a partition of an undirected weighted graph in communities is
+//! $$ Q = \sum_{c} \frac{in_{c}}{2m} - \gamma \left( \frac{tot_{c}}{2m} \right)^{2} $$
+//! where $in_{c}$ is the sum of weights of edges inside community c (counted in both directions), $tot_{c}$ the sum of weighted degrees
+//! of nodes of c, m the total weight of edges and $\gamma$ a resolution parameter (1. gives the classical modularity).
+//!
+//! Each level alternates local moves of nodes to the neighbouring community giving the best modularity gain, and aggregation of communities
+//! into the nodes of the graph of the next level.
+//! Local moves are computed in parallel for all nodes with respect to the current partition and then applied together,
+//! as in Lu Halappanavar Kalyanaraman _Parallel heuristics for scalable community detection_ [2015](https://arxiv.org/abs/1410.1237).
+//! To avoid swaps between isolated nodes, a node alone in its community moves to another singleton community only if its label is smaller.
+//!
+//! The graph is accessed via the trait [DensityGraph] so a petgraph Graph or a symetric csr matrix (see [CsrGraph](crate::structure::density::CsrGraph)) can be used.
+//! The partition can be converted to a [StableDecomposition] and then passed to [density_analysis](crate::validation::anndensity::density_analysis)
+//! to compare embedded distances inside communities and across community frontiers.
+
+use cpu_time::ProcessTime;
+use std::time::SystemTime;
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use num_traits::float::Float;
+use rayon::prelude::*;
+
+use crate::io::jsonio::{dump_json, reload_json};
+use crate::structure::density::densitygraph::DensityGraph;
+use crate::structure::density::stable::StableDecomposition;
+
+// maximum number of sweeps of local moves at each level
+const MAX_SWEEPS: usize = 100;
+
+/// The result of community detection. Nodes are identified by their index in the graph.
+///
+/// Communities are numbered by decreasing size, community 0 is the largest one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Partition {
+    /// community of each node
+    communities: Vec<u32>,
+    /// community_sizes\[c\] is the number of nodes in community c
+    community_sizes: Vec<usize>,
+    /// modularity of the partition
+    modularity: f64,
+    /// number of aggregation levels done
+    nb_levels: usize,
+} // end of struct Partition
+
+impl Partition {
+    /// builds a partition from the community of each node. Communities are renumbered by decreasing size.
+    pub fn new(communities: &[u32], modularity: f64, nb_levels: usize) -> Self {
+        let nb_communities = communities
+            .iter()
+            .max()
+            .map(|c| *c as usize + 1)
+            .unwrap_or(0);
+        let mut sizes = vec![0usize; nb_communities];
+        for c in communities {
+            sizes[*c as usize] += 1;
+        }
+        let mut order: Vec<usize> = (0..nb_communities).filter(|c| sizes[*c] > 0).collect();
+        order.sort_by(|a, b| sizes[*b].cmp(&sizes[*a]).then(a.cmp(b)));
+        let mut renum = vec![0u32; nb_communities];
+        for (rank, c) in order.iter().enumerate() {
+            renum[*c] = rank as u32;
+        }
+        Partition {
+            communities: communities.iter().map(|c| renum[*c as usize]).collect(),
+            community_sizes: order.iter().map(|c| sizes[*c]).collect(),
+            modularity,
+            nb_levels,
+        }
+    } // end of new
+
+    /// returns the number of nodes
+    pub fn get_nb_nodes(&self) -> usize {
+        self.communities.len()
+    }
+
+    /// returns the number of communities
+    pub fn get_nb_communities(&self) -> usize {
+        self.community_sizes.len()
+    }
+
+    /// returns community of a node
+    pub fn get_community(&self, node: usize) -> Option<u32> {
+        self.communities.get(node).copied()
+    }
+
+    /// returns communities of all nodes
+    pub fn get_communities(&self) -> &[u32] {
+        &self.communities
+    }
+
+    /// returns sizes of communities, in decreasing order
+    pub fn get_community_sizes(&self) -> &[usize] {
+        &self.community_sizes
+    }
+
+    /// returns nodes of a community
+    pub fn get_community_nodes(&self, community: u32) -> Vec<usize> {
+        (0..self.communities.len())
+            .filter(|n| self.communities[*n] == community)
+            .collect()
+    }
+
+    /// returns modularity of the partition
+    pub fn get_modularity(&self) -> f64 {
+        self.modularity
+    }
+
+    /// returns the number of aggregation levels done by [louvain]
+    pub fn get_nb_levels(&self) -> usize {
+        self.nb_levels
+    }
+
+    /// converts the partition in a [StableDecomposition], block i being community i.
+    ///
+    /// Degrees stored are the number of neighbours of nodes and block transitions are computed along edges of the graph,
+    /// so the result can be used as the decomposition argument of [density_analysis](crate::validation::anndensity::density_analysis).
+    /// Note that the blocks of a partition are not nested in decreasing density as blocks of a density decomposition.
+    pub fn to_stable_decomposition<G, F>(&self, graph: &G) -> StableDecomposition
+    where
+        G: DensityGraph<F> + ?Sized,
+    {
+        //
+        assert_eq!(graph.get_nb_nodes(), self.communities.len());
+        let nb_blocks = self.community_sizes.len();
+        let mut degrees = vec![0u32; self.communities.len()];
+        let mut block_transition = Array2::<f32>::zeros((nb_blocks, nb_blocks));
+        for (node, degree) in degrees.iter_mut().enumerate() {
+            for (neighbour, _) in graph.get_neighbours(node) {
+                *degree += 1;
+                block_transition[(
+                    self.communities[node] as usize,
+                    self.communities[neighbour] as usize,
+                )] += 1.;
+            }
+        }
+        // for each block we get a transition probability to other blocks
+        for i in 0..nb_blocks {
+            let block_degree = block_transition.row(i).iter().sum::<f32>();
+            if block_degree > 0. {
+                block_transition
+                    .row_mut(i)
+                    .iter_mut()
+                    .for_each(|v| *v /= block_degree);
+            }
+        }
+        //
+        StableDecomposition::new(self.communities.clone(), degrees, block_transition)
+    } // end of to_stable_decomposition
+
+    /// dump in json format
+    pub fn dump_json(&self, filepath: &Path) -> anyhow::Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reload from a json dump
+    pub fn reload_json(filepath: &Path) -> anyhow::Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl Partition
+
+// weighted graph of a level. Self loops weights are counted twice (once in each direction) as in adjacency matrix.
+struct LevelGraph {
+    // neighbours (self excluded) with weight of joining edges, sorted by neighbour
+    adjacency: Vec<Vec<(usize, f64)>>,
+    self_loops: Vec<f64>,
+    // weighted degrees
+    degrees: Vec<f64>,
+    // sum of degrees, i.e 2m
+    total: f64,
+}
+
+impl LevelGraph {
+    fn new(adjacency: Vec<Vec<(usize, f64)>>, self_loops: Vec<f64>) -> Self {
+        let degrees: Vec<f64> = adjacency
+            .par_iter()
+            .zip(self_loops.par_iter())
+            .map(|(a, s)| a.iter().map(|(_, w)| w).sum::<f64>() + s)
+            .collect();
+        let total = degrees.iter().sum::<f64>();
+        LevelGraph {
+            adjacency,
+            self_loops,
+            degrees,
+            total,
+        }
+    }
+
+    fn get_nb_nodes(&self) -> usize {
+        self.degrees.len()
+    }
+
+    // modularity of partition of nodes of level
+    fn modularity(&self, community: &[usize], tot: &[f64], resolution: f64) -> f64 {
+        if self.total <= 0. {
+            return 0.;
+        }
+        let inside: f64 = (0..self.get_nb_nodes())
+            .into_par_iter()
+            .map(|i| {
+                self.self_loops[i]
+                    + self.adjacency[i]
+                        .iter()
+                        .filter(|(j, _)| community[*j] == community[i])
+                        .map(|(_, w)| w)
+                        .sum::<f64>()
+            })
+            .sum();
+        let expected: f64 = tot
+            .iter()
+            .map(|t| (t / self.total) * (t / self.total))
+            .sum();
+        inside / self.total - resolution * expected
+    }
+} // end of impl LevelGraph
+
+// collects weights from the graph, merging multiple edges
+fn get_first_level<G, F>(graph: &G) -> LevelGraph
+where
+    F: Float,
+    G: DensityGraph<F> + Sync + ?Sized,
+{
+    let (adjacency, self_loops): (Vec<Vec<(usize, f64)>>, Vec<f64>) = (0..graph.get_nb_nodes())
+        .into_par_iter()
+        .map(|node| {
+            let mut self_loop = 0.;
+            let mut neighbours = Vec::<(usize, f64)>::new();
+            for (neighbour, e) in graph.get_neighbours(node) {
+                let w = graph.get_edge(e).2.to_f64().unwrap();
+                if neighbour == node {
+                    self_loop += 2. * w;
+                } else {
+                    neighbours.push((neighbour, w));
+                }
+            }
+            (merge_neighbours(neighbours), self_loop)
+        })
+        .unzip();
+    LevelGraph::new(adjacency, self_loops)
+} // end of get_first_level
+
+// sorts neighbours and sums weights of duplicates
+fn merge_neighbours(mut neighbours: Vec<(usize, f64)>) -> Vec<(usize, f64)> {
+    neighbours.sort_unstable_by_key(|(j, _)| *j);
+    let mut merged = Vec::<(usize, f64)>::with_capacity(neighbours.len());
+    for (j, w) in neighbours {
+        match merged.last_mut() {
+            Some((k, v)) if *k == j => *v += w,
+            _ => merged.push((j, w)),
+        }
+    }
+    merged
+}
+
+// returns the community giving the best modularity gain for node i, given the current partition.
+fn get_best_community(
+    level: &LevelGraph,
+    i: usize,
+    community: &[usize],
+    tot: &[f64],
+    resolution: f64,
+) -> usize {
+    let own = community[i];
+    let k_i = level.degrees[i];
+    let mut links = HashMap::<usize, f64>::new();
+    for (j, w) in &level.adjacency[i] {
+        *links.entry(community[*j]).or_insert(0.) += w;
+    }
+    let scale = resolution * k_i / level.total;
+    let mut best = own;
+    let mut best_gain = links.get(&own).copied().unwrap_or(0.) - scale * (tot[own] - k_i);
+    for (c, k_ic) in &links {
+        if *c == own {
+            continue;
+        }
+        let gain = k_ic - scale * tot[*c];
+        // ties are broken by smallest label to get a result independant of hashing
+        if gain > best_gain || (gain == best_gain && best != own && *c < best) {
+            best = *c;
+            best_gain = gain;
+        }
+    }
+    best
+} // end of get_best_community
+
+// local moves of nodes of a level. Returns community of each node and modularity reached
+fn local_moves(level: &LevelGraph, resolution: f64, tolerance: f64) -> (Vec<usize>, f64) {
+    //
+    let nb_nodes = level.get_nb_nodes();
+    let mut community: Vec<usize> = (0..nb_nodes).collect();
+    let mut tot = level.degrees.clone();
+    let mut sizes = vec![1usize; nb_nodes];
+    let mut modularity = level.modularity(&community, &tot, resolution);
+    if level.total <= 0. {
+        return (community, modularity);
+    }
+    //
+    for sweep in 0..MAX_SWEEPS {
+        let targets: Vec<usize> = (0..nb_nodes)
+            .into_par_iter()
+            .map(|i| {
+                let own = community[i];
+                let best = get_best_community(level, i, &community, &tot, resolution);
+                // singletons moving to singletons only to smaller labels
+                if best != own && sizes[own] == 1 && sizes[best] == 1 && best > own {
+                    own
+                } else {
+                    best
+                }
+            })
+            .collect();
+        //
+        let nb_moves = (0..nb_nodes)
+            .filter(|i| targets[*i] != community[*i])
+            .count();
+        if nb_moves == 0 {
+            break;
+        }
+        let mut new_tot = vec![0.; nb_nodes];
+        let mut new_sizes = vec![0usize; nb_nodes];
+        for i in 0..nb_nodes {
+            new_tot[targets[i]] += level.degrees[i];
+            new_sizes[targets[i]] += 1;
+        }
+        let mut new_modularity = level.modularity(&targets, &new_tot, resolution);
+        log::debug!(
+            "sweep : {sweep}, nb moves : {nb_moves}, modularity : {:.5e}",
+            new_modularity
+        );
+        if new_modularity >= modularity {
+            community = targets;
+            tot = new_tot;
+            sizes = new_sizes;
+        } else {
+            // moves are simultaneous, so modularity can decrease (typically with chains of nodes moving to their neighbour).
+            // We keep the previous partition and do a sequential sweep, which cannot decrease modularity.
+            log::debug!("sweep : {sweep}, modularity decreased, doing sequential sweep");
+            let mut nb_moves = 0;
+            for i in 0..nb_nodes {
+                let own = community[i];
+                let best = get_best_community(level, i, &community, &tot, resolution);
+                if best != own {
+                    tot[own] -= level.degrees[i];
+                    tot[best] += level.degrees[i];
+                    sizes[own] -= 1;
+                    sizes[best] += 1;
+                    community[i] = best;
+                    nb_moves += 1;
+                }
+            }
+            if nb_moves == 0 {
+                break;
+            }
+            new_modularity = level.modularity(&community, &tot, resolution);
+        }
+        let gain = new_modularity - modularity;
+        modularity = new_modularity;
+        if gain < tolerance {
+            break;
+        }
+    }
+    (community, modularity)
+} // end of local_moves
+
+// aggregates each community of a level in a node. community must be numbered in 0..nb_communities
+fn aggregate(level: &LevelGraph, community: &[usize], nb_communities: usize) -> LevelGraph {
+    let mut members = vec![Vec::<usize>::new(); nb_communities];
+    for (i, c) in community.iter().enumerate() {
+        members[*c].push(i);
+    }
+    let (adjacency, self_loops): (Vec<Vec<(usize, f64)>>, Vec<f64>) = members
+        .par_iter()
+        .enumerate()
+        .map(|(c, nodes)| {
+            let mut self_loop = 0.;
+            let mut links = HashMap::<usize, f64>::new();
+            for i in nodes {
+                self_loop += level.self_loops[*i];
+                for (j, w) in &level.adjacency[*i] {
+                    if community[*j] == c {
+                        self_loop += w;
+                    } else {
+                        *links.entry(community[*j]).or_insert(0.) += w;
+                    }
+                }
+            }
+            (merge_neighbours(links.into_iter().collect()), self_loop)
+        })
+        .unzip();
+    LevelGraph::new(adjacency, self_loops)
+} // end of aggregate
+
+/// computes a partition of the graph in communities with the Louvain algorithm.
+///
+/// - resolution : the $\gamma$ parameter of modularity. Larger values give smaller communities, 1. gives the classical modularity.
+/// - tolerance : a level stops when a sweep of local moves increases the modularity by less than tolerance (1.0e-6 is a reasonable value).
+///
+/// The algorithm stops when a level does not merge any communities.
+pub fn louvain<G, F>(graph: &G, resolution: f64, tolerance: f64) -> Partition
+where
+    F: Float,
+    G: DensityGraph<F> + Sync + ?Sized,
+{
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let nb_nodes = graph.get_nb_nodes();
+    let mut level = get_first_level(graph);
+    // community of each original node in current level
+    let mut node_community: Vec<usize> = (0..nb_nodes).collect();
+    let mut modularity = level.modularity(&node_community, &level.degrees, resolution);
+    let mut nb_levels = 0;
+    loop {
+        let (community, level_modularity) = local_moves(&level, resolution, tolerance);
+        // renumber communities in 0..nb_communities
+        let mut renum = vec![usize::MAX; level.get_nb_nodes()];
+        let mut nb_communities = 0;
+        for c in &community {
+            if renum[*c] == usize::MAX {
+                renum[*c] = nb_communities;
+                nb_communities += 1;
+            }
+        }
+        if nb_communities == level.get_nb_nodes() {
+            break;
+        }
+        let community: Vec<usize> = community.iter().map(|c| renum[*c]).collect();
+        node_community
+            .par_iter_mut()
+            .for_each(|c| *c = community[*c]);
+        modularity = level_modularity;
+        nb_levels += 1;
+        log::info!(
+            "louvain level : {nb_levels}, nb communities : {nb_communities}, modularity : {:.5e}",
+            modularity
+        );
+        level = aggregate(&level, &community, nb_communities);
+    }
+    //
+    let communities: Vec<u32> = node_community.iter().map(|c| *c as u32).collect();
+    let partition = Partition::new(&communities, modularity, nb_levels);
+    //
+    log::info!(
+        "louvain nb communities : {}, modularity : {:.5e}, sys time(s) {:.2e} cpu time(s) {:.2e}",
+        partition.get_nb_communities(),
+        modularity,
+        sys_start.elapsed().unwrap().as_secs(),
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    partition
+} // end of louvain
+
+//==========================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::csv_to_trimat;
+    use crate::structure::density::CsrGraph;
+    use petgraph::Undirected;
+    use petgraph::graph::{Graph, NodeIndex};
+    use sprs::CsMatI;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 2 cliques of size 5 and 4 joined by one edge, and an isolated node
+    #[test]
+    fn test_louvain_cliques() {
+        log_init_test();
+        //
+        let mut graph = Graph::<u32, f64, Undirected>::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..10).map(|i| graph.add_node(i)).collect();
+        for (start, end) in [(0, 5), (5, 9)] {
+            for i in start..end {
+                for j in i + 1..end {
+                    graph.add_edge(nodes[i], nodes[j], 1.);
+                }
+            }
+        }
+        graph.add_edge(nodes[4], nodes[5], 1.);
+        let partition = louvain(&graph, 1., 1.0e-6);
+        log::info!("communities : {:?}", partition.get_communities());
+        assert_eq!(partition.get_community_sizes(), &[5, 4, 1]);
+        assert_eq!(partition.get_community_nodes(0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(partition.get_community_nodes(1), vec![5, 6, 7, 8]);
+        // 17 edges, 16 inside communities. degrees of communities are 21 and 13
+        let expected = 16. / 17. - (21. * 21. + 13. * 13.) / (34. * 34.);
+        assert!((partition.get_modularity() - expected).abs() < 1.0e-10);
+        //
+        let decomposition = partition.to_stable_decomposition(&graph);
+        assert_eq!(decomposition.get_nb_blocks(), 3);
+        assert_eq!(decomposition.get_densest_block(7).unwrap(), 1);
+        assert_eq!(decomposition.get_node_degree(4), 5);
+        //
+        let dumpfile = std::env::temp_dir().join("test_louvain_cliques.json");
+        partition.dump_json(&dumpfile).unwrap();
+        let reloaded = Partition::reload_json(&dumpfile).unwrap();
+        assert_eq!(reloaded.get_communities(), partition.get_communities());
+    } // end of test_louvain_cliques
+
+    #[test]
+    fn test_louvain_miserables() {
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!("\n\n test_louvain_miserables, loading file {:?}", path);
+        let (trimat, _) = csv_to_trimat::<f64>(&path, false, b' ').unwrap();
+        let csmat: CsMatI<f64, usize> = trimat.to_csr();
+        let graph = CsrGraph::new(&csmat).unwrap();
+        let partition = louvain(&graph, 1., 1.0e-6);
+        log::info!(
+            "miserables nb communities : {}, sizes : {:?}, modularity : {:.3e}",
+            partition.get_nb_communities(),
+            partition.get_community_sizes(),
+            partition.get_modularity()
+        );
+        // best known weighted modularity is around 0.56
+        assert!(partition.get_modularity() > 0.5);
+        // modularity recomputed from the partition
+        let level = get_first_level(&graph);
+        let community: Vec<usize> = partition
+            .get_communities()
+            .iter()
+            .map(|c| *c as usize)
+            .collect();
+        let mut tot = vec![0.; partition.get_nb_communities()];
+        for (i, c) in community.iter().enumerate() {
+            tot[*c] += level.degrees[i];
+        }
+        let recomputed = level.modularity(&community, &tot, 1.);
+        assert!((recomputed - partition.get_modularity()).abs() < 1.0e-8);
+        // a larger resolution gives more communities
+        let finer = louvain(&graph, 2., 1.0e-6);
+        assert!(finer.get_nb_communities() > partition.get_nb_communities());
+    } // end of test_louvain_miserables
+} // end of mod tests
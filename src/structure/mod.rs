@@ -7,6 +7,8 @@
 //!   - Danisch Chan Sozio _Large Scale decomposition via convex programming_  [2017](https://dl.acm.org/doi/10.1145/3038912.3052619)
 //!
 //!   - Batagelj Zaversnik _Fast algorithms for determining generalized core in networks_ [2011](https://link.springer.com/article/10.1007/s11634-010-0079-y)
+//!
+//!   - Blondel Guillaume Lambiotte Lefebvre _Fast unfolding of communities in large networks_ [2008](https://arxiv.org/abs/0803.0476)
 
 //! 
 //!  See also:  
@@ -14,15 +16,18 @@
 //!     - _Tatti Density-friendly graph decomposition_ [2019](https://arxiv.org/abs/1904.03467)
 //! 
 
-// - for directed graph:
-//     - Giatsidis Thilikos Vazirgiannis D_cores: Measuring Collaboration in Directed graphs based on degeneracy 2013.
-//
-
-// implements generalized core decomposition according to Batagelj Zaversnik paper
-// pub mod kcore;
+//! - for directed graph:
+//!
+//!   - Giatsidis Thilikos Vazirgiannis _D-cores: Measuring Collaboration of Directed Graphs Based on Degeneracy_ [2013](https://link.springer.com/article/10.1007/s10115-012-0539-0)
 
 /// density decomposition according to Danisch Chan Sozio _Large Scale decomposition via convex programming 2017_
 pub mod density;
 
 /// core decompositions according to Batagelj Zaversnik 2011
-pub mod cores;
\ No newline at end of file
+pub mod cores;
+
+/// D-cores decomposition of directed graphs according to Giatsidis Thilikos Vazirgiannis 2013
+pub mod dcores;
+
+/// community detection by the Louvain algorithm of Blondel Guillaume Lambiotte Lefebvre 2008
+pub mod louvain;
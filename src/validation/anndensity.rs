@@ -11,6 +11,7 @@
 //! Kullbach-Leibler divergence.
 //!
 //! The interface to the validation is the function [density_analysis] that takes as arguments the original graph and the embedded data.
+//! For asymetric embeddings the function [density_analysis_asym] uses source, target or concatenated representations of nodes.
 //! Optional arguments can be specified to gain control over the Ann and densitiy decomposition.
 
 // We construct an Hnsw structure on embedded data.
@@ -31,8 +32,8 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 use serde_json::to_writer;
 
-use petgraph::Undirected;
 use petgraph::graph::Graph;
+use petgraph::{Directed, Undirected};
 
 use hdrhistogram::Histogram;
 
@@ -40,6 +41,7 @@ use hnsw_rs::flatten::FlatNeighborhood;
 use hnsw_rs::prelude::*;
 
 use crate::embedding::*;
+use crate::structure::dcores::dcore_decomposition;
 use crate::structure::density::stable::*;
 use crate::structure::density::*;
 
@@ -304,29 +306,76 @@ impl BlockCheck {
 
 //====================================================================================================
 
-/// Builds the Hnsw structure from the embedded data
+/// Bridge between the distance shared by an embedding (see [EmbeddedDistance]) and the Distance trait of hnsw_rs.  
+/// It makes it possible to use any distance of an embedding (closures with state included) in a Hnsw structure.
+#[derive(Clone)]
+pub struct HnswDistance<F> {
+    distance: DistanceRef<F>,
+}
+
+impl<F> HnswDistance<F> {
+    pub fn new(distance: DistanceRef<F>) -> Self {
+        HnswDistance { distance }
+    }
+
+    /// returns the embedding distance wrapped
+    pub fn get_distance(&self) -> &DistanceRef<F> {
+        &self.distance
+    }
+} // end of impl HnswDistance
+
+impl<F> From<DistanceRef<F>> for HnswDistance<F> {
+    fn from(distance: DistanceRef<F>) -> Self {
+        HnswDistance::new(distance)
+    }
+}
+
+impl<F: Send + Sync> Distance<F> for HnswDistance<F> {
+    fn eval(&self, va: &[F], vb: &[F]) -> f32 {
+        self.distance.eval(va, vb) as f32
+    }
+}
+
+/// Representation of nodes of an asymetric embedding inserted in the Hnsw structure
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AsymRepresentation {
+    /// source vectors, compared with the distance of the embedding
+    Source,
+    /// target vectors, compared with the distance of the embedding
+    Target,
+    /// concatenation of source and target vectors, compared with the [AsymDistance] strategy of the embedding
+    #[default]
+    Concatenated,
+}
+
+/// Builds the Hnsw structure from the (symetric) embedded data, an error is returned for an asymetric embedding (see [embeddedasymtohnsw]).
 /// In the Hnsw structure original nodes of the graph are identified by their NodeIndex or rank in embedded structure.  
 /// (The N type of the graph structure is not used anymore at this step)
-pub fn embeddedtohnsw<'a, 'b, F, D>(
+/// The Hnsw uses the distance returned by [EmbeddedT::get_distance] through the bridge [HnswDistance].
+pub fn embeddedtohnsw<'a, 'b, F>(
     embedded: &'a dyn EmbeddedT<F>,
     max_nb_connection: usize,
     ef_c: usize,
-) -> Result<Hnsw<'b, F, DistPtr<F, f64>>, anyhow::Error>
+) -> Result<Hnsw<'b, F, HnswDistance<F>>, anyhow::Error>
 where
     F: Copy + Clone + Send + Sync,
-    D: Distance<F>,
     'b: 'a,
 {
     //
-    let distance_e = embedded.get_distance();
-    let distance = DistPtr::<F, f64>::new(distance_e);
+    if !embedded.is_symetric() {
+        log::error!(
+            "embeddedtohnsw needs a symetric embedding, use embeddedasymtohnsw for asymetric embeddings"
+        );
+        return Err(anyhow!("embeddedtohnsw needs a symetric embedding"));
+    }
+    let distance = HnswDistance::new(embedded.get_distance());
     let nbdata = embedded.get_nb_nodes();
     let nb_layer = 16;
     //
     let cpu_start = ProcessTime::now();
     let sys_start = SystemTime::now();
     //
-    let hnsw = Hnsw::<F, DistPtr<F, f64>>::new(max_nb_connection, nbdata, nb_layer, ef_c, distance);
+    let hnsw = Hnsw::<F, HnswDistance<F>>::new(max_nb_connection, nbdata, nb_layer, ef_c, distance);
     // we will insert by blocks
     let block_size = 10000;
     let nb_blocks_min = nbdata / block_size;
@@ -366,6 +415,94 @@ where
     Ok(hnsw)
 } // end of embedtohnsw
 
+/// Builds the Hnsw structure from asymetric embedded data.
+///
+/// Each node is inserted as the concatenation of its source and target representations, and the Hnsw distance
+/// from a node to another is the dissimilarity given by the [AsymDistance] strategy of the embedding,
+/// so that knn queries are consistent with [EmbeddedAsym::get_noderank_distance](crate::embedding::EmbeddedT::get_noderank_distance).
+/// A query for the neighbours of node of rank i must be done with the concatenation of its source and target vectors.
+pub fn embeddedasymtohnsw<'b, F>(
+    embedded: &EmbeddedAsym<F>,
+    max_nb_connection: usize,
+    ef_c: usize,
+) -> Result<Hnsw<'b, F, HnswDistance<F>>, anyhow::Error>
+where
+    F: Copy + Clone + Send + Sync + 'static,
+{
+    embeddedasymtohnsw_with_representation(
+        embedded,
+        AsymRepresentation::Concatenated,
+        max_nb_connection,
+        ef_c,
+    )
+} // end of embeddedasymtohnsw
+
+/// Builds the Hnsw structure from asymetric embedded data, nodes being inserted with the given representation.
+///
+/// With [AsymRepresentation::Source] (resp. [AsymRepresentation::Target]) the source (resp. target) vectors are inserted
+/// and compared with the distance of the embedding. With [AsymRepresentation::Concatenated] we get [embeddedasymtohnsw].
+pub fn embeddedasymtohnsw_with_representation<'b, F>(
+    embedded: &EmbeddedAsym<F>,
+    representation: AsymRepresentation,
+    max_nb_connection: usize,
+    ef_c: usize,
+) -> Result<Hnsw<'b, F, HnswDistance<F>>, anyhow::Error>
+where
+    F: Copy + Clone + Send + Sync + 'static,
+{
+    //
+    log::info!(
+        "embeddedasymtohnsw, representation : {:?}, asym distance : {:?}",
+        representation,
+        embedded.get_asym_distance()
+    );
+    let distance = match representation {
+        AsymRepresentation::Concatenated => HnswDistance::new(embedded.get_concatenated_distance()),
+        _ => HnswDistance::new(embedded.get_distance()),
+    };
+    let nbdata = embedded.get_nb_nodes();
+    let nb_layer = 16;
+    //
+    let cpu_start = ProcessTime::now();
+    let sys_start = SystemTime::now();
+    //
+    let hnsw = Hnsw::<F, HnswDistance<F>>::new(max_nb_connection, nbdata, nb_layer, ef_c, distance);
+    let block_size = 10000;
+    let mut first = 0;
+    while first < nbdata {
+        let last = (first + block_size).min(nbdata);
+        let embeded_v: Vec<(Vec<F>, usize)> = (first..last)
+            .map(|rank| {
+                let v = match representation {
+                    AsymRepresentation::Source => embedded.get_embedded_source().row(rank).to_vec(),
+                    AsymRepresentation::Target => embedded.get_embedded_target().row(rank).to_vec(),
+                    AsymRepresentation::Concatenated => {
+                        let mut v = embedded.get_embedded_source().row(rank).to_vec();
+                        v.extend(embedded.get_embedded_target().row(rank).iter());
+                        v
+                    }
+                };
+                (v, rank)
+            })
+            .collect();
+        let data_with_id: Vec<(&[F], usize)> = embeded_v
+            .iter()
+            .map(|data| (data.0.as_slice(), data.1))
+            .collect();
+        hnsw.parallel_insert_slice(&data_with_id);
+        first = last;
+    }
+    //
+    let sys_t: f64 = sys_start.elapsed().unwrap().as_millis() as f64 / 1000.;
+    log::info!(
+        " embeddedasymtohnsw sys time(s) {:.2e} cpu time(s) {:.2e}",
+        sys_t,
+        cpu_start.elapsed().as_secs()
+    );
+    //
+    Ok(hnsw)
+} // end of embeddedasymtohnsw_with_representation
+
 // We compute transition probabilities between blocks after embedding and compare it with data
 // before embedding by computing K.L divergence between distributions for corresponding block
 /// TODO have many/better density
@@ -502,15 +639,16 @@ fn get_block_stats(blocnum: usize, blockout: &[f32]) -> f64 {
 ///
 /// Computing transition probabilities between blocks along edges and compared with the original graph.
 ///
-pub fn density_analysis<F, D, N>(
+/// If no decomposition is given, it is computed by [approximate_decomposition] with 500 iterations. To stop iterations on the duality gap
+/// instead, pass a decomposition computed by [approximate_decomposition_with_tolerance].
+pub fn density_analysis<F, N>(
     graph: &Graph<N, f64, Undirected>,
     embedded: &Embedded<F>,
-    hnsw_opt: Option<Hnsw<F, DistPtr<F, f64>>>,
+    hnsw_opt: Option<Hnsw<F, HnswDistance<F>>>,
     decomposition_opt: Option<StableDecomposition>,
 ) -> Result<BlockCheck, anyhow::Error>
 where
     F: Copy + Clone + Send + Sync,
-    D: Distance<F>,
     N: std::marker::Copy,
 {
     // temporarily we pass stable decomposition as optional arg
@@ -532,7 +670,7 @@ where
                 "density_analysis : construction hnsw using max_nb_onnection : {max_nb_connection}"
             );
             let ef_construction: usize = 48;
-            let hnsw_res = embeddedtohnsw::<F, D>(embedded, max_nb_connection, ef_construction);
+            let hnsw_res = embeddedtohnsw::<F>(embedded, max_nb_connection, ef_construction);
             if hnsw_res.is_err() {
                 return Err(anyhow!("density_analysis cannot do the hnsw construction"));
             }
@@ -540,7 +678,69 @@ where
         }
     };
     //
-    let flathnsw = FlatNeighborhood::from(&hnsw);
+    Ok(analyze_blocks(&hnsw, &decomposition))
+} // end of density_analysis
+
+/// A tentative assesment of asymetric embedding by density comparison edges length after embedding.
+///
+/// This is the analog of [density_analysis] for asymetric embeddings (Hope, NodeSketchAsym).
+/// The representation argument specifies which vectors of nodes are inserted in the Hnsw structure (see [AsymRepresentation]).
+/// If no decomposition is given, the decomposition in balanced D-cores of the directed graph is used (see [dcore_decomposition]).
+pub fn density_analysis_asym<F, N>(
+    graph: &Graph<N, f64, Directed>,
+    embedded: &EmbeddedAsym<F>,
+    representation: AsymRepresentation,
+    hnsw_opt: Option<Hnsw<F, HnswDistance<F>>>,
+    decomposition_opt: Option<StableDecomposition>,
+) -> Result<BlockCheck, anyhow::Error>
+where
+    F: Copy + Clone + Send + Sync + 'static,
+{
+    //
+    let decomposition = match decomposition_opt {
+        Some(decomposition) => decomposition,
+        None => {
+            log::info!("doing dcore_decomposition");
+            dcore_decomposition(graph)
+        }
+    };
+    //
+    let hnsw = match hnsw_opt {
+        Some(hnsw) => hnsw,
+        None => {
+            let max_nb_connection: usize = decomposition.get_mean_block_size().min(64);
+            log::info!(
+                "density_analysis_asym : construction hnsw using max_nb_connection : {max_nb_connection}"
+            );
+            let ef_construction: usize = 48;
+            let hnsw_res = embeddedasymtohnsw_with_representation::<F>(
+                embedded,
+                representation,
+                max_nb_connection,
+                ef_construction,
+            );
+            if hnsw_res.is_err() {
+                return Err(anyhow!(
+                    "density_analysis_asym cannot do the hnsw construction"
+                ));
+            }
+            hnsw_res.unwrap()
+        }
+    };
+    //
+    Ok(analyze_blocks(&hnsw, &decomposition))
+} // end of density_analysis_asym
+
+// compares blocks of decomposition with neighbourhoods of hnsw
+fn analyze_blocks<F>(
+    hnsw: &Hnsw<F, HnswDistance<F>>,
+    decomposition: &StableDecomposition,
+) -> BlockCheck
+where
+    F: Copy + Clone + Send + Sync,
+{
+    //
+    let flathnsw = FlatNeighborhood::from(hnsw);
     //
     let nb_blocks = decomposition.get_nb_blocks();
     // now we can loop ( //) on densest blocks and more populated blocks.
@@ -549,7 +749,7 @@ where
     let nb_dense_blocks = nb_blocks.min(nb_max_blocks);
     let res_analysis: Vec<BlockStat> = (0..nb_dense_blocks)
         .into_par_iter()
-        .map(|i| compare_block_density(&flathnsw, &decomposition, i))
+        .map(|i| compare_block_density(&flathnsw, decomposition, i))
         .collect();
     //
     log::info!("\n\n nb neighbours by blocks");
@@ -577,8 +777,8 @@ where
     //
     blockcheck.get_divergence_histogram();
     //
-    Ok(blockcheck)
-} // end of density_analysis
+    blockcheck
+} // end of analyze_blocks
 
 //========================================================================================================
 
@@ -589,6 +789,9 @@ mod tests {
 
     use crate::prelude::*;
 
+    use crate::embed::tools::jaccard::jaccard_distance;
+    use std::sync::Arc;
+
     fn log_init_test() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
@@ -643,8 +846,7 @@ mod tests {
         // now we can convert into a Graph
         let graph = res.unwrap().into_graph::<u32>();
         //
-        let block_analysis =
-            density_analysis::<usize, DistPtr<usize, f64>, u32>(&graph, &embedded, None, None);
+        let block_analysis = density_analysis::<usize, u32>(&graph, embedded, None, None);
         if block_analysis.is_err() {
             log::error!("block_analysis failed");
         }
@@ -652,4 +854,160 @@ mod tests {
         let block_check = block_analysis.unwrap();
         let _ratio_quants = block_check.get_in_out_distance_ratio();
     } // end of ann_check_density_miserables
+
+    // a distance carrying state must be used as is by the hnsw structure
+    #[test]
+    fn ann_stateful_distance_miserables() {
+        //
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n ann_stateful_distance_miserables, loading file {:?}",
+            path
+        );
+        let (trimat, _) = csv_to_trimat::<f64>(&path, false, b' ').unwrap();
+        let params = NodeSketchParams {
+            sketch_size: 50,
+            decay: 0.15,
+            nb_iter: 3,
+            symetric: true,
+            parallel: false,
+        };
+        let mut nodesketch = NodeSketch::new(params, trimat);
+        let mut embedded = nodesketch.embed().unwrap();
+        // a scaled jaccard distance, the scale is captured
+        let scale = 2.0f64;
+        let scaled: DistanceRef<usize> =
+            Arc::new(move |v1: &[usize], v2: &[usize]| scale * jaccard_distance(v1, v2));
+        embedded.set_distance(scaled);
+        //
+        let hnsw = embeddedtohnsw::<usize>(&embedded, 16, 48).unwrap();
+        let node = 0;
+        let query = embedded.get_embedded_node(node, TAG_IN_OUT).to_vec();
+        let neighbours = hnsw.search(&query, 10, 48);
+        assert!(!neighbours.is_empty());
+        for n in &neighbours {
+            let dist = embedded.get_noderank_distance(node, n.get_origin_id());
+            let jac = jaccard_distance(
+                &query,
+                embedded
+                    .get_embedded_node(n.get_origin_id(), TAG_IN_OUT)
+                    .as_slice()
+                    .unwrap(),
+            );
+            assert!((dist - scale * jac).abs() < 1.0e-10);
+            assert!((dist - n.get_distance() as f64).abs() < 1.0e-5);
+        }
+    } // end of ann_stateful_distance_miserables
+
+    // hnsw distances on asymetric embedding must be consistent with node dissimilarities of the embedding
+    #[test]
+    fn ann_asym_distance_miserables() {
+        //
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!("\n\n ann_asym_distance_miserables, loading file {:?}", path);
+        let res = csv_to_trimat::<f64>(&path, true, b' ');
+        if res.is_err() {
+            log::error!("ann_asym_distance_miserables failed in csv_to_trimat");
+            assert_eq!(1, 0);
+        }
+        let (trimat, _) = res.unwrap();
+        let csrmat: sprs::CsMatI<f64, usize> = trimat.to_csr();
+        let params = NodeSketchParams {
+            sketch_size: 50,
+            decay: 0.2,
+            nb_iter: 3,
+            symetric: false,
+            parallel: false,
+        };
+        let mut nodesketch = NodeSketchAsym::new(params, trimat);
+        nodesketch.set_asym_distance(AsymDistance::SourceTarget);
+        let mut embedded = nodesketch.embed().unwrap();
+        assert_eq!(embedded.get_asym_distance(), AsymDistance::SourceTarget);
+        // sketches are not float vectors
+        assert!(
+            embedded
+                .set_asym_distance(AsymDistance::DotProduct)
+                .is_err()
+        );
+        // a graph without edges gives an error and keeps the strategy
+        let empty = sprs::CsMatI::<f64, usize>::zero(csrmat.shape());
+        assert!(embedded.learn_asym_weights(&empty, 500, 4567).is_err());
+        assert_eq!(embedded.get_asym_distance(), AsymDistance::SourceTarget);
+        let weights = embedded.learn_asym_weights(&csrmat, 500, 4567).unwrap();
+        assert!((weights.iter().sum::<f64>() - 1.).abs() < 1.0e-10);
+        //
+        let hnsw = embeddedasymtohnsw(&embedded, 16, 48).unwrap();
+        let node = 0;
+        let mut query = embedded.get_embedded_source().row(node).to_vec();
+        query.extend(embedded.get_embedded_target().row(node).iter());
+        let neighbours = hnsw.search(&query, 10, 48);
+        assert!(!neighbours.is_empty());
+        for n in &neighbours {
+            let dist = embedded.get_noderank_distance(node, n.get_origin_id());
+            assert!((dist - n.get_distance() as f64).abs() < 1.0e-5);
+        }
+    } // end of ann_asym_distance_miserables
+
+    // block analysis of an asymetric embedding with the 3 representations
+    #[test]
+    fn ann_check_density_asym_miserables() {
+        //
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n ann_check_density_asym_miserables, loading file {:?}",
+            path
+        );
+        let (trimat, _) = csv_to_trimat::<f64>(&path, true, b' ').unwrap();
+        let params = NodeSketchParams {
+            sketch_size: 50,
+            decay: 0.2,
+            nb_iter: 3,
+            symetric: false,
+            parallel: false,
+        };
+        let mut nodesketch = NodeSketchAsym::new(params, trimat);
+        let embedded = nodesketch.embed().unwrap();
+        // symetric hnsw construction must not accept an asymetric embedding
+        assert!(embeddedtohnsw::<usize>(&embedded, 16, 48).is_err());
+        //
+        let graph = weighted_csv_to_graphmap::<u32, f64, Directed>(&path, b' ')
+            .unwrap()
+            .into_graph::<u32>();
+        let decomposition = dcore_decomposition(&graph);
+        for representation in [
+            AsymRepresentation::Source,
+            AsymRepresentation::Target,
+            AsymRepresentation::Concatenated,
+        ] {
+            let hnsw =
+                embeddedasymtohnsw_with_representation(&embedded, representation, 16, 48).unwrap();
+            assert_eq!(hnsw.get_nb_point(), graph.node_count());
+            let block_check = density_analysis_asym::<usize, u32>(
+                &graph,
+                &embedded,
+                representation,
+                Some(hnsw),
+                None,
+            )
+            .unwrap();
+            assert_eq!(
+                block_check.get_blockstat().len(),
+                decomposition.get_nb_blocks().min(500)
+            );
+            log::info!("representation : {:?}", representation);
+            let _ratio_quants = block_check.get_in_out_distance_ratio();
+        }
+    } // end of ann_check_density_asym_miserables
 } // end of mod test
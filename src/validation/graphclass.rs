@@ -0,0 +1,292 @@
+//! Graph classification by k nearest neighbours on global graph sketches.
+//!
+//! Each graph of a collection is represented by a global sketch (see [TuDataset](crate::embed::gkernel::tudataset::TuDataset)),
+//! graphs are compared with the Jaccard distance between sketches, which estimates the weighted Jaccard distance
+//! between the label distributions of graphs (as in Weisfeiler-Lehman kernels).
+//!
+//! The classification accuracy is estimated by k-fold cross validation: graphs are randomly dispatched in nb_folds folds,
+//! each fold being in turn the test set classified with the other folds as training set.
+//! The class of a test graph is the majority class of its knn nearest training graphs, ties being broken by the sum of distances.
+
+use anyhow::*;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use ndarray::{Array1, CowArray, Ix1};
+
+use rand::seq::SliceRandom;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rand_xoshiro::rand_core::SeedableRng;
+use rayon::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use super::linkreport::MetricSummary;
+use crate::embed::tools::jaccard::jaccard_distance;
+use crate::io::jsonio::{dump_json, reload_json};
+
+/// Results of graph classification by cross validation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphClassificationReport {
+    /// number of neighbours used in vote
+    knn: usize,
+    /// number of graphs classified
+    nb_graphs: usize,
+    /// accuracy of each fold
+    accuracy: MetricSummary,
+} // end of GraphClassificationReport
+
+impl GraphClassificationReport {
+    /// returns the number of neighbours used in vote
+    pub fn get_knn(&self) -> usize {
+        self.knn
+    }
+
+    /// returns the number of graphs classified
+    pub fn get_nb_graphs(&self) -> usize {
+        self.nb_graphs
+    }
+
+    /// returns the summary of fold accuracies
+    pub fn get_accuracy(&self) -> &MetricSummary {
+        &self.accuracy
+    }
+
+    /// dump in json format
+    pub fn dump_json(&self, filepath: &Path) -> Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reload from a json dump
+    pub fn reload_json(filepath: &Path) -> Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl GraphClassificationReport
+
+// majority class among neighbours given as (distance, class), ties broken by smallest sum of distances
+fn vote(neighbours: &[(f64, i64)]) -> i64 {
+    let mut votes = HashMap::<i64, (usize, f64)>::new();
+    for (d, c) in neighbours {
+        let v = votes.entry(*c).or_insert((0, 0.));
+        v.0 += 1;
+        v.1 += d;
+    }
+    votes
+        .into_iter()
+        .max_by(|a, b| {
+            a.1.0
+                .cmp(&b.1.0)
+                .then(b.1.1.total_cmp(&a.1.1))
+                .then(b.0.cmp(&a.0))
+        })
+        .map(|(c, _)| c)
+        .unwrap()
+} // end of vote
+
+/// estimates accuracy of knn classification of graphs from their global sketches by nb_folds cross validation.
+///
+/// - sketches : the global sketch of each graph, all of same size (an error is returned otherwise)
+/// - classes : the class of each graph
+/// - seed : seed of the random dispatch of graphs in folds
+pub fn knn_graph_classification<T>(
+    sketches: &[Array1<T>],
+    classes: &[i64],
+    knn: usize,
+    nb_folds: usize,
+    seed: u64,
+) -> Result<GraphClassificationReport>
+where
+    T: Clone + Eq + Sync,
+{
+    //
+    let nb_graphs = sketches.len();
+    if classes.len() != nb_graphs {
+        return Err(anyhow!(
+            "knn_graph_classification got {} sketches and {} classes",
+            nb_graphs,
+            classes.len()
+        ));
+    }
+    if knn == 0 || nb_folds < 2 || nb_folds > nb_graphs {
+        return Err(anyhow!(
+            "knn_graph_classification needs knn > 0 and 2 <= nb_folds <= nb graphs"
+        ));
+    }
+    if let Some(rank) = sketches.iter().position(|s| s.len() != sketches[0].len()) {
+        return Err(anyhow!(
+            "knn_graph_classification sketch of graph {} has size {}, expected {}",
+            rank,
+            sketches[rank].len(),
+            sketches[0].len()
+        ));
+    }
+    // jaccard distance needs slices, sketches not in standard layout are copied
+    let standard: Vec<CowArray<T, Ix1>> = sketches.iter().map(|s| s.as_standard_layout()).collect();
+    let slices: Vec<&[T]> = standard
+        .iter()
+        .map(|s| s.as_slice().unwrap_or_default())
+        .collect();
+    //
+    let mut ranks: Vec<usize> = (0..nb_graphs).collect();
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    ranks.shuffle(&mut rng);
+    let fold = |i: usize| i % nb_folds;
+    //
+    let accuracies: Vec<f64> = (0..nb_folds)
+        .map(|f| {
+            let train: Vec<usize> = (0..nb_graphs)
+                .filter(|i| fold(*i) != f)
+                .map(|i| ranks[i])
+                .collect();
+            let test: Vec<usize> = (0..nb_graphs)
+                .filter(|i| fold(*i) == f)
+                .map(|i| ranks[i])
+                .collect();
+            let nb_right = test
+                .par_iter()
+                .filter(|g| {
+                    let mut neighbours: Vec<(f64, i64)> = train
+                        .iter()
+                        .map(|t| (jaccard_distance(slices[**g], slices[*t]), classes[*t]))
+                        .collect();
+                    neighbours.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+                    neighbours.truncate(knn);
+                    vote(&neighbours) == classes[**g]
+                })
+                .count();
+            let accuracy = nb_right as f64 / test.len() as f64;
+            log::debug!("fold {f}, accuracy : {:.3e}", accuracy);
+            accuracy
+        })
+        .collect();
+    //
+    let accuracy = MetricSummary::new(accuracies);
+    log::info!(
+        "knn_graph_classification nb graphs : {}, knn : {}, nb folds : {}, accuracy : {:.3e} +- {:.3e}",
+        nb_graphs,
+        knn,
+        nb_folds,
+        accuracy.get_mean(),
+        accuracy.get_std_dev()
+    );
+    //
+    Ok(GraphClassificationReport {
+        knn,
+        nb_graphs,
+        accuracy,
+    })
+} // end of knn_graph_classification
+
+//=====================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::tudataset::TuDataset;
+    use std::io::Write;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // writes a dataset of labeled cycles (class 1) and labeled stars (class -1) in TU format
+    fn write_cycles_and_stars(dir: &Path, name: &str) {
+        let mut a = std::fs::File::create(dir.join(format!("{name}_A.txt"))).unwrap();
+        let mut indicator =
+            std::fs::File::create(dir.join(format!("{name}_graph_indicator.txt"))).unwrap();
+        let mut glabels =
+            std::fs::File::create(dir.join(format!("{name}_graph_labels.txt"))).unwrap();
+        let mut nlabels =
+            std::fs::File::create(dir.join(format!("{name}_node_labels.txt"))).unwrap();
+        let mut elabels =
+            std::fs::File::create(dir.join(format!("{name}_edge_labels.txt"))).unwrap();
+        let mut first = 1;
+        for g in 0..40 {
+            let size = 5 + g % 7;
+            let cycle = g % 2 == 0;
+            for i in 0..size {
+                writeln!(indicator, "{}", g + 1).unwrap();
+                let label = if cycle {
+                    i % 2
+                } else if i == 0 {
+                    2
+                } else {
+                    0
+                };
+                writeln!(nlabels, "{label}").unwrap();
+            }
+            let edges: Vec<(usize, usize)> = if cycle {
+                (0..size).map(|i| (i, (i + 1) % size)).collect()
+            } else {
+                (1..size).map(|i| (0, i)).collect()
+            };
+            for (i, j) in edges {
+                // both directions as in TU files
+                writeln!(a, "{}, {}", first + i, first + j).unwrap();
+                writeln!(a, "{}, {}", first + j, first + i).unwrap();
+                let elabel = if cycle { 1 } else { 2 };
+                writeln!(elabels, "{elabel}").unwrap();
+                writeln!(elabels, "{elabel}").unwrap();
+            }
+            writeln!(glabels, "{}", if cycle { 1 } else { -1 }).unwrap();
+            first += size;
+        }
+    } // end of write_cycles_and_stars
+
+    #[test]
+    fn test_knn_graph_classification() {
+        log_init_test();
+        //
+        let dir = std::env::temp_dir();
+        let name = "CYCLESTARS";
+        write_cycles_and_stars(&dir, name);
+        let dataset = TuDataset::<u8, u8>::load(&dir, name).unwrap();
+        assert_eq!(dataset.get_nb_graphs(), 40);
+        assert!(dataset.has_edge_labels());
+        let graph = dataset.get_graph(0).unwrap();
+        // each undirected edge is kept once
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 5);
+        //
+        let params = SketchParams::new(20, 0.1, 2, true, false);
+        let sketches = dataset.get_global_sketches_n(params.clone(), 50).unwrap();
+        assert_eq!(sketches.len(), 40);
+        let report =
+            knn_graph_classification(&sketches, dataset.get_classes(), 3, 5, 1234).unwrap();
+        log::info!("accuracy : {:?}", report.get_accuracy());
+        assert!(report.get_accuracy().get_mean() > 0.9);
+        //
+        let sketches = dataset.get_global_sketches_ne(params, 50).unwrap();
+        let report =
+            knn_graph_classification(&sketches, dataset.get_classes(), 3, 5, 1234).unwrap();
+        assert!(report.get_accuracy().get_mean() > 0.9);
+        //
+        let dumpfile = dir.join("test_knn_graph_classification.json");
+        report.dump_json(&dumpfile).unwrap();
+        let reloaded = GraphClassificationReport::reload_json(&dumpfile).unwrap();
+        assert_eq!(reloaded.get_nb_graphs(), 40);
+    } // end of test_knn_graph_classification
+
+    #[test]
+    fn test_knn_graph_classification_sketches() {
+        log_init_test();
+        //
+        let classes: Vec<i64> = (0..6).map(|i| i % 2).collect();
+        let mut sketches: Vec<Array1<u32>> = (0..6)
+            .map(|i| Array1::from_iter((0..10).map(|j| ((i % 2) * 100 + j) as u32)))
+            .collect();
+        // a sketch not in standard layout, with the same values
+        sketches[1] = Array1::from_iter((0..10).rev().map(|j| (100 + j) as u32));
+        sketches[1].invert_axis(ndarray::Axis(0));
+        assert!(sketches[1].as_slice().is_none());
+        let report = knn_graph_classification(&sketches, &classes, 1, 3, 1234).unwrap();
+        assert_eq!(report.get_accuracy().get_mean(), 1.);
+        // sketches of different sizes
+        sketches[2] = Array1::from_iter(0..5u32);
+        assert!(knn_graph_classification(&sketches, &classes, 1, 3, 1234).is_err());
+    } // end of test_knn_graph_classification_sketches
+} // end of mod tests
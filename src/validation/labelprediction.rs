@@ -0,0 +1,433 @@
+//! Node label prediction from labeled graph sketches.
+//!
+//! A fraction of labeled nodes have their labels hidden with [hide_node_labels()] before sketching the graph,
+//! so that hidden labels are not propagated into sketches. Then labels of hidden nodes are predicted from the embedding:
+//!
+//! - [LabelPredictionMode::Knn] : weighted vote of the k nearest visible labeled nodes in the embedded (Jaccard) space.
+//!   Each neighbour at distance d votes for each of its labels with weight 1 - d.
+//!
+//! - [LabelPredictionMode::Sketch] : labels are scored by their number of occurrences in the node's own sketch
+//!   (both OUT and IN sketches for an asymetric embedding). As sketching propagates labels of neighbours, the sketch of a node
+//!   with hidden labels is a sample of labels around it.
+//!
+//! For each hidden node the labels with highest scores are predicted (as many as the hidden labels by default, as in node2vec PPI experiments),
+//! and multi-label precision and recall against hidden labels are reported in a [LabelPredictionReport]:
+//! per node values (summarized in [MetricSummary]) and micro averaged values (counting all predictions together).
+
+use anyhow::*;
+
+use std::path::Path;
+
+use indexmap::IndexMap;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rand_xoshiro::rand_core::SeedableRng;
+use rayon::prelude::*;
+
+use petgraph::EdgeType;
+use petgraph::graph::{Graph, IndexType, NodeIndex};
+
+use serde::{Deserialize, Serialize};
+
+use super::linkreport::MetricSummary;
+use crate::embed::gkernel::pgraph::{HasNweightMut, LabelT};
+use crate::embed::tools::edge::{IN, INOUT, OUT};
+use crate::embedding::EmbeddedT;
+use crate::io::jsonio::{dump_json, reload_json};
+
+/// Strategy used to score labels of a node
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum LabelPredictionMode {
+    /// weighted vote of the k nearest visible labeled nodes
+    Knn(usize),
+    /// occurrences of labels in the node's own sketch
+    Sketch,
+}
+
+/// Results of label prediction on hidden nodes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LabelPredictionReport {
+    /// strategy used
+    mode: LabelPredictionMode,
+    /// number of hidden nodes examined
+    nb_nodes: usize,
+    /// fraction of predicted labels that are hidden labels, by node
+    precision: MetricSummary,
+    /// fraction of hidden labels that are predicted, by node
+    recall: MetricSummary,
+    /// fraction of all predicted labels that are right
+    micro_precision: f64,
+    /// fraction of all hidden labels that are predicted
+    micro_recall: f64,
+} // end of LabelPredictionReport
+
+impl LabelPredictionReport {
+    /// returns the strategy used
+    pub fn get_mode(&self) -> LabelPredictionMode {
+        self.mode
+    }
+
+    /// returns the number of hidden nodes examined
+    pub fn get_nb_nodes(&self) -> usize {
+        self.nb_nodes
+    }
+
+    /// summary of precision by node
+    pub fn get_precision(&self) -> &MetricSummary {
+        &self.precision
+    }
+
+    /// summary of recall by node
+    pub fn get_recall(&self) -> &MetricSummary {
+        &self.recall
+    }
+
+    /// precision over all predictions
+    pub fn get_micro_precision(&self) -> f64 {
+        self.micro_precision
+    }
+
+    /// recall over all hidden labels
+    pub fn get_micro_recall(&self) -> f64 {
+        self.micro_recall
+    }
+
+    /// harmonic mean of micro precision and micro recall
+    pub fn get_micro_f1(&self) -> f64 {
+        if self.micro_precision + self.micro_recall > 0. {
+            2. * self.micro_precision * self.micro_recall
+                / (self.micro_precision + self.micro_recall)
+        } else {
+            0.
+        }
+    }
+
+    /// dump in json format
+    pub fn dump_json(&self, filepath: &Path) -> Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reload a report from a json dump done by [Self::dump_json]
+    pub fn reload_json(filepath: &Path) -> Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl LabelPredictionReport
+
+//==================================================================================================
+
+/// Removes labels of a random fraction of labeled nodes of the graph.
+///
+/// Returns for each hidden node its rank (NodeIndex) and its hidden labels. Must be called before sketching the graph.
+pub fn hide_node_labels<Nlabel, N, E, Ty, Ix>(
+    graph: &mut Graph<N, E, Ty, Ix>,
+    fraction: f64,
+    seed: u64,
+) -> Vec<(usize, Vec<Nlabel>)>
+where
+    Nlabel: LabelT,
+    N: HasNweightMut<Nlabel>,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    assert!(
+        (0. ..=1.).contains(&fraction),
+        "fraction of hidden nodes must be in [0,1]"
+    );
+    let labeled: Vec<usize> = graph
+        .node_indices()
+        .filter(|n| !graph[*n].get_nweight().get_labels().is_empty())
+        .map(|n| n.index())
+        .collect();
+    let nb_hidden = (fraction * labeled.len() as f64).round() as usize;
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut hidden: Vec<usize> = rand::seq::index::sample(&mut rng, labeled.len(), nb_hidden)
+        .into_iter()
+        .map(|i| labeled[i])
+        .collect();
+    hidden.sort_unstable();
+    log::info!(
+        "hide_node_labels hiding labels of {} nodes among {} labeled nodes",
+        nb_hidden,
+        labeled.len()
+    );
+    hidden
+        .into_iter()
+        .map(|i| {
+            let labels = graph[NodeIndex::new(i)].get_nweight_mut().take_labels();
+            (i, labels)
+        })
+        .collect()
+} // end of hide_node_labels
+
+/// Scores labels of node.
+///
+/// - labels : labels\[i\] are the visible labels of node of rank i (empty for hidden nodes),
+///   they can be obtained with [get_graph_node_labels()](super::nodelabel::get_graph_node_labels) after hiding.
+///
+/// Returns labels with a positive score by decreasing score.
+pub fn predict_node_labels<Nlabel, E>(
+    embedded: &E,
+    labels: &[Vec<Nlabel>],
+    node: usize,
+    mode: LabelPredictionMode,
+) -> Vec<(Nlabel, f64)>
+where
+    Nlabel: LabelT,
+    E: EmbeddedT<Nlabel>,
+{
+    let mut scores = IndexMap::<Nlabel, f64>::new();
+    match mode {
+        LabelPredictionMode::Knn(k) => {
+            let mut dists: Vec<(f64, usize)> = labels
+                .iter()
+                .enumerate()
+                .filter(|(j, labels_j)| *j != node && !labels_j.is_empty())
+                .map(|(j, _)| (embedded.get_noderank_distance(node, j), j))
+                .collect();
+            let cmp = |a: &(f64, usize), b: &(f64, usize)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+            let knn = k.min(dists.len());
+            if knn == 0 {
+                return Vec::new();
+            }
+            if knn < dists.len() {
+                dists.select_nth_unstable_by(knn - 1, cmp);
+            }
+            for (d, j) in &dists[..knn] {
+                for label in &labels[*j] {
+                    *scores.entry(label.clone()).or_insert(0.) += 1. - d;
+                }
+            }
+        }
+        LabelPredictionMode::Sketch => {
+            let tags = if embedded.is_symetric() {
+                vec![INOUT]
+            } else {
+                vec![OUT, IN]
+            };
+            let default = Nlabel::default();
+            for tag in tags {
+                for label in embedded.get_embedded_node(node, tag).iter() {
+                    if *label != default {
+                        *scores.entry(label.clone()).or_insert(0.) += 1.;
+                    }
+                }
+            }
+        }
+    }
+    let mut scores: Vec<(Nlabel, f64)> = scores.into_iter().filter(|(_, s)| *s > 0.).collect();
+    // stable sort, ties are kept in order of first encounter
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+} // end of predict_node_labels
+
+/// Estimates precision and recall of the prediction of hidden labels.
+///
+/// - labels : visible labels of nodes, indexed by rank, see [predict_node_labels()].
+/// - hidden : hidden nodes and their labels as returned by [hide_node_labels()].
+/// - nb_predicted : number of labels predicted for each node. If None, as many labels as hidden labels of the node are predicted.
+pub fn estimate_label_prediction<Nlabel, E>(
+    embedded: &E,
+    labels: &[Vec<Nlabel>],
+    hidden: &[(usize, Vec<Nlabel>)],
+    mode: LabelPredictionMode,
+    nb_predicted: Option<usize>,
+) -> Result<LabelPredictionReport>
+where
+    Nlabel: LabelT,
+    E: EmbeddedT<Nlabel> + Sync,
+{
+    //
+    if labels.len() != embedded.get_nb_nodes() {
+        return Err(anyhow!(
+            "estimate_label_prediction got labels for {} nodes, embedding has {} nodes",
+            labels.len(),
+            embedded.get_nb_nodes()
+        ));
+    }
+    if hidden.is_empty() {
+        return Err(anyhow!("estimate_label_prediction : no hidden node"));
+    }
+    if let LabelPredictionMode::Knn(0) = mode {
+        return Err(anyhow!("estimate_label_prediction needs k > 0"));
+    }
+    // for each node : (nb right predictions, nb predictions, nb hidden labels)
+    let counts: Vec<(usize, usize, usize)> = hidden
+        .par_iter()
+        .map(|(node, truth)| {
+            let nb = nb_predicted.unwrap_or(truth.len());
+            let predicted = predict_node_labels(embedded, labels, *node, mode);
+            let nb_right = predicted
+                .iter()
+                .take(nb)
+                .filter(|(l, _)| truth.contains(l))
+                .count();
+            (nb_right, predicted.len().min(nb), truth.len())
+        })
+        .collect();
+    //
+    let precision: Vec<f64> = counts
+        .iter()
+        .map(|(r, p, _)| if *p > 0 { *r as f64 / *p as f64 } else { 0. })
+        .collect();
+    let recall: Vec<f64> = counts
+        .iter()
+        .filter(|(_, _, t)| *t > 0)
+        .map(|(r, _, t)| *r as f64 / *t as f64)
+        .collect();
+    let nb_right: usize = counts.iter().map(|c| c.0).sum();
+    let nb_predictions: usize = counts.iter().map(|c| c.1).sum();
+    let nb_truth: usize = counts.iter().map(|c| c.2).sum();
+    let micro_precision = if nb_predictions > 0 {
+        nb_right as f64 / nb_predictions as f64
+    } else {
+        0.
+    };
+    let micro_recall = if nb_truth > 0 {
+        nb_right as f64 / nb_truth as f64
+    } else {
+        0.
+    };
+    //
+    let report = LabelPredictionReport {
+        mode,
+        nb_nodes: hidden.len(),
+        precision: MetricSummary::new(precision),
+        recall: MetricSummary::new(recall),
+        micro_precision,
+        micro_recall,
+    };
+    log::info!(
+        "label prediction {:?}, nb hidden nodes : {}, micro precision : {:.3e}, micro recall : {:.3e}",
+        mode,
+        report.nb_nodes,
+        micro_precision,
+        micro_recall
+    );
+    //
+    Ok(report)
+} // end of estimate_label_prediction
+
+//================================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::embed::gkernel::exio::ppisapiens::read_ppi_undirected_data;
+    use crate::embed::gkernel::loader::{LabeledEdge, LabeledGraph, LabeledNode};
+    use crate::embed::gkernel::params::SketchParams;
+    use crate::embed::gkernel::pgraph::Nweight;
+    use crate::embed::gkernel::psketch::MgraphSketch;
+    use crate::validation::nodelabel::get_graph_node_labels;
+    use petgraph::Undirected;
+
+    const PPI_DIR: &str = "/home/jpboth/Data/Graphs/PPI";
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 3 cliques of 8 nodes, nodes of clique c have label c+1, nodes of the 2 first cliques have also label 4
+    #[test]
+    fn test_label_prediction_cliques() {
+        log_init_test();
+        //
+        let clique_size = 8;
+        let mut graph = LabeledGraph::<u8, u8, Undirected>::default();
+        let nodes: Vec<_> = (0..3 * clique_size)
+            .map(|i| {
+                let c = (i / clique_size) as u8;
+                let labels = if c < 2 { vec![c + 1, 4] } else { vec![c + 1] };
+                graph.add_node(LabeledNode::new(i as u32, Nweight::new(labels)))
+            })
+            .collect();
+        for c in 0..3 {
+            for i in c * clique_size..(c + 1) * clique_size {
+                for j in i + 1..(c + 1) * clique_size {
+                    graph.add_edge(nodes[i], nodes[j], LabeledEdge::default());
+                }
+            }
+            let next = ((c + 1) % 3) * clique_size;
+            graph.add_edge(
+                nodes[c * clique_size],
+                nodes[next + 1],
+                LabeledEdge::default(),
+            );
+        }
+        //
+        let hidden = hide_node_labels(&mut graph, 0.25, 1234);
+        assert_eq!(hidden.len(), 6);
+        let labels = get_graph_node_labels::<u8, _, _, _, _>(&graph);
+        for (node, truth) in &hidden {
+            assert!(labels[*node].is_empty());
+            assert!(!truth.is_empty());
+        }
+        //
+        let skparams = SketchParams::new(50, 0.1, 2, true, false);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, false);
+        skgraph.compute_embedded().unwrap();
+        let embedded = skgraph.get_n_embedded_ref().unwrap();
+        //
+        let report = estimate_label_prediction(
+            embedded,
+            &labels,
+            &hidden,
+            LabelPredictionMode::Knn(5),
+            None,
+        )
+        .unwrap();
+        log::info!("knn report : {:?}", report);
+        assert_eq!(report.get_nb_nodes(), 6);
+        assert!(report.get_micro_recall() > 0.8);
+        let report = estimate_label_prediction(
+            embedded,
+            &labels,
+            &hidden,
+            LabelPredictionMode::Sketch,
+            None,
+        )
+        .unwrap();
+        log::info!("sketch report : {:?}", report);
+        assert!(report.get_micro_recall() > 0.8);
+        // with as many predictions as hidden labels precision equals recall
+        assert!((report.get_micro_precision() - report.get_micro_recall()).abs() < 1.0e-10);
+        //
+        let dumpfile = std::env::temp_dir().join("test_label_prediction_cliques.json");
+        report.dump_json(&dumpfile).unwrap();
+        let reloaded = LabelPredictionReport::reload_json(&dumpfile).unwrap();
+        assert_eq!(reloaded.get_nb_nodes(), 6);
+    } // end of test_label_prediction_cliques
+
+    #[test]
+    fn test_label_prediction_ppi() {
+        log_init_test();
+        //
+        if !Path::new(PPI_DIR).exists() {
+            log::info!(
+                "test_label_prediction_ppi : no data in {}, test skipped",
+                PPI_DIR
+            );
+            return;
+        }
+        let (mut graph, _idmap) = read_ppi_undirected_data(String::from(PPI_DIR)).unwrap();
+        let hidden = hide_node_labels(&mut graph, 0.1, 1234);
+        let labels = get_graph_node_labels::<u8, _, _, _, _>(&graph);
+        //
+        let skparams = SketchParams::new(100, 0.1, 3, true, true);
+        let mut skgraph = MgraphSketch::new(&graph, skparams, false);
+        skgraph.compute_embedded().unwrap();
+        let embedded = skgraph.get_n_embedded_ref().unwrap();
+        //
+        for mode in [LabelPredictionMode::Knn(10), LabelPredictionMode::Sketch] {
+            let report = estimate_label_prediction(embedded, &labels, &hidden, mode, None).unwrap();
+            log::info!(
+                "ppi {:?} precision : {:.3e} +- {:.3e}, micro f1 : {:.3e}",
+                mode,
+                report.get_precision().get_mean(),
+                report.get_precision().get_std_dev(),
+                report.get_micro_f1()
+            );
+            assert!(report.get_micro_f1() > 0.);
+        }
+    } // end of test_label_prediction_ppi
+} // end of mod tests
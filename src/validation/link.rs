@@ -12,6 +12,7 @@
 //! It is possible to treat edge deletion for a symetric graph as in the asymetric. See [crate::embed]
 //!
 //! The methods [estimate_centric_auc()] give also a variation of a node centric quality assessment. See also [estimate_vcmpr()]
+//! The nodes examined by these methods can be sampled uniformly, by degree or stratified by coreness (see [NodeSampling]).
 
 #![allow(clippy::needless_range_loop)]
 
@@ -41,17 +42,75 @@ use quantiles::ckms::CKMS;
 use rayon::prelude::*;
 
 use crate::embed::tools::{correlation::*, degrees::*, edge::Edge, edge::IN, edge::OUT};
-use crate::embedding::EmbeddedT;
+use crate::embedding::{EmbeddedAsym, EmbeddedT};
+use crate::structure::cores::kcore::kcore_decomposition;
 
+use super::linkreport::*;
+
+/// Validation modes of embeddings.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ValidationMode {
+    /// link prediction : edges are deleted and recovered from the embedding of the remaining graph.
+    /// See [estimate_auc()], [estimate_precision()], [estimate_vcmpr()] and [estimate_centric_auc()]
+    LINK,
+    /// node label consistency : nodes close in the embedding should share their labels.
+    /// See [super::nodelabel::estimate_label_homophily()]
     NODELABEL,
+    /// direction of edges for asymetric embeddings : a deleted edge i->j is compared with its inexistent reversal j->i.
+    /// See [estimate_direction_auc()]
+    DIRECTION,
+}
+
+/// Sampling of the nodes examined by node centric validations,
+/// see [estimate_vcmpr_with_sampling()] and [estimate_centric_auc_with_sampling()].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NodeSampling {
+    /// each node is sampled with the same probability, as in the vcmpr paper
+    #[default]
+    Uniform,
+    /// nodes are sampled with probability proportional to their degree, see [sample_nodes_by_degrees()]
+    Degree,
+    /// nodes are stratified by their core number, so that the few nodes of dense inner cores are examined,
+    /// see [sample_nodes_by_coreness()]
+    Coreness,
 }
 
+// number of strata in coreness sampling
+const NB_CORENESS_STRATA: usize = 5;
+
+// samples the nodes examined by node centric validations
+fn sample_validation_nodes<F>(
+    csmat: &CsMatI<F, usize>,
+    nb_to_sample: usize,
+    symetric: bool,
+    sampling: NodeSampling,
+    rng: &mut Xoshiro256PlusPlus,
+) -> Vec<usize>
+where
+    F: Default + Copy,
+{
+    log::info!("sampling of validation nodes : {:?}", sampling);
+    match sampling {
+        NodeSampling::Uniform => sample_nodes_uniform(csmat, nb_to_sample, rng),
+        NodeSampling::Degree => sample_nodes_by_degrees(csmat, nb_to_sample, rng),
+        NodeSampling::Coreness => {
+            let kcore = kcore_decomposition(csmat, symetric);
+            sample_nodes_by_coreness(&kcore, nb_to_sample, NB_CORENESS_STRATA, rng)
+        }
+    }
+} // end of sample_validation_nodes
+
+/// number of couples (deleted edge, inexistent edge) sampled in an auc pass
+pub(crate) const NB_AUC_SAMPLE: usize = 10000;
+
+// a couple (deleted edge, inexistent edge) sampled in an auc pass
+pub(crate) type AucCouple = ((usize, usize), (usize, usize));
+
 // ======================================================================================
 
 // filter out edge with proba delete_proba
 // The return a tuple containing the matrix of the graph with some edge deleted and a perfect hash on deleted edges
-fn filter_csmat<F>(
+pub(crate) fn filter_csmat<F>(
     csrmat: &CsMatI<F, usize>,
     delete_proba: f64,
     symetric: bool,
@@ -327,13 +386,16 @@ where
 ///
 /// Other alternatives are [estimate_centric_auc()] or [estimate_vcmpr()]
 ///
+/// The returned report contains per pass precision and recall.
+/// The seed initializes the random generators of the passes (see [DEFAULT_VALIDATION_SEED](super::linkparams::DEFAULT_VALIDATION_SEED)).
 pub fn estimate_precision<F, G, E>(
     csmat: &CsMatI<F, usize>,
     nbiter: usize,
     delete_proba: f64,
     symetric: bool,
+    seed: u64,
     embedder: &dyn Fn(TriMatI<F, usize>) -> E,
-) -> (Vec<f64>, Vec<f64>)
+) -> LinkPredictionReport
 where
     F: Default + Copy + Sync,
     E: EmbeddedT<G> + std::marker::Sync,
@@ -353,7 +415,7 @@ where
         symetric
     );
     // we prepair a family of random generator for possible parallel iterations
-    let mut rng = Xoshiro256PlusPlus::seed_from_u64(1235437);
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
     let mut rngs = Vec::<Xoshiro256PlusPlus>::with_capacity(nbiter);
     for _ in 0..nbiter {
         let new_rng = rng.clone();
@@ -380,25 +442,30 @@ where
         mean_precision
     );
     //
-    (precision, recall)
+    let mut report =
+        LinkPredictionReport::new(LinkEstimator::Precision, symetric, delete_proba, nbiter);
+    report.set_precision(MetricSummary::new(precision));
+    report.set_recall(MetricSummary::new(recall));
+    report
 } // end of estimate_precision
 
 /// type G is necessary beccause we embed in possibly different type than F. (for example in Array<usize> with nodesketch)
+///
+/// returns the metrics of the pass and an estimation of the memory (in bytes) the pass needed.
 fn one_auc_iteration<F, G, E>(
     csmat: &CsMatI<F, usize>,
     delete_proba: f64,
     symetric: bool,
     embedder: &dyn Fn(TriMatI<F, usize>) -> E,
     mut rng: Xoshiro256PlusPlus,
-) -> f64
+) -> (PassMetrics, u64)
 where
     F: Default + Copy + std::marker::Sync,
     G: std::fmt::Debug,
     E: EmbeddedT<G> + std::marker::Sync,
 {
     //
-    let nb_sample = 10000;
-    let mut nb_dist_equality: usize = 0;
+    let nb_sample = NB_AUC_SAMPLE;
     log::debug!(
         "\n\n in one_auc_iteration nb_sample : {:?}, delete_proba : {:.3e}, symetric {:?}",
         nb_sample,
@@ -419,41 +486,21 @@ where
     // embedder (passed as a closure)
     //
     let embedded = &embedder(trimat);
-    let mut good = 0.;
     //
-    let nb_deleted = deleted_edges.len();
     let nb_nodes = csmat.shape().0;
-    // as we can have large graph , mostly sparse to sample an inexistent edge we sample until we are outside csmat edges
-    log::trace!("nb deleted edges : {:?}", nb_deleted);
-    let del_uniform = Uniform::<usize>::new(0, nb_deleted).unwrap();
-    let node_random = Uniform::<usize>::new(0, nb_nodes).unwrap();
-    for _k in 0..nb_sample {
-        let del_edge = deleted_edges
-            .get_index(del_uniform.sample(&mut rng))
-            .unwrap();
-        let no_edge = loop {
-            let i = node_random.sample(&mut rng);
-            let j = node_random.sample(&mut rng);
-            if i != j
-                && !trimat_set.contains(&(i, j))
-                && deleted_edges.get_index_of(&(i, j)).is_none()
-            {
-                // edge (i,j) not on diagonal and neither in trimat set neither in deleted_edges, so inexistent edge
-                break (i, j);
-            }
-            if !symetric
-                && i != j
-                && !trimat_set.contains(&(j, i))
-                && deleted_edges.get_index_of(&(j, i)).is_none()
-            {
-                // edge (i,j) not on diagonal and neither in trimat set neither in deleted_edges, so inexistent edge
-                break (j, i);
-            }
-        };
-        let dist_del_edge = embedded.get_noderank_distance(del_edge.0, del_edge.1);
-        let dist_no_edge = embedded.get_noderank_distance(no_edge.0, no_edge.1);
-        // debug stuff
-        if log_enabled!(log::Level::Trace) {
+    let couples = sample_auc_couples(
+        nb_nodes,
+        symetric,
+        &trimat_set,
+        &deleted_edges,
+        nb_sample,
+        &mut rng,
+    );
+    // debug stuff
+    if log_enabled!(log::Level::Trace) {
+        for (del_edge, no_edge) in &couples {
+            let dist_del_edge = embedded.get_noderank_distance(del_edge.0, del_edge.1);
+            let dist_no_edge = embedded.get_noderank_distance(no_edge.0, no_edge.1);
             log::debug!(
                 "distance between deleted edge nodes {} and {} : {:.3e}",
                 del_edge.0,
@@ -513,19 +560,243 @@ where
                 }
             }
         }
-        // end debug stuff
+    }
+    // end debug stuff
+    let metrics = score_auc_couples(&couples, &|i, j| embedded.get_noderank_distance(i, j));
+    //
+    let pass_memory = estimate_pass_memory::<F, G, E>(trimat_set.len(), embedded);
+    //
+    (metrics, pass_memory)
+} // end of one_auc_iteration
+
+// samples nb_sample couples made of a deleted edge and an inexistent edge (neither in train set nor in deleted edges).
+// These couples are the positive and negative samples of an auc pass.
+pub(crate) fn sample_auc_couples(
+    nb_nodes: usize,
+    symetric: bool,
+    trimat_set: &HashSet<(usize, usize)>,
+    deleted_edges: &IndexSet<(usize, usize)>,
+    nb_sample: usize,
+    rng: &mut Xoshiro256PlusPlus,
+) -> Vec<AucCouple> {
+    let nb_deleted = deleted_edges.len();
+    // as we can have large graph , mostly sparse to sample an inexistent edge we sample until we are outside csmat edges
+    log::trace!("nb deleted edges : {:?}", nb_deleted);
+    let del_uniform = Uniform::<usize>::new(0, nb_deleted).unwrap();
+    let node_random = Uniform::<usize>::new(0, nb_nodes).unwrap();
+    let mut couples = Vec::<AucCouple>::with_capacity(nb_sample);
+    for _k in 0..nb_sample {
+        let del_edge = deleted_edges.get_index(del_uniform.sample(rng)).unwrap();
+        let no_edge = loop {
+            let i = node_random.sample(rng);
+            let j = node_random.sample(rng);
+            if i != j
+                && !trimat_set.contains(&(i, j))
+                && deleted_edges.get_index_of(&(i, j)).is_none()
+            {
+                // edge (i,j) not on diagonal and neither in trimat set neither in deleted_edges, so inexistent edge
+                break (i, j);
+            }
+            if !symetric
+                && i != j
+                && !trimat_set.contains(&(j, i))
+                && deleted_edges.get_index_of(&(j, i)).is_none()
+            {
+                // edge (i,j) not on diagonal and neither in trimat set neither in deleted_edges, so inexistent edge
+                break (j, i);
+            }
+        };
+        couples.push((*del_edge, no_edge));
+    }
+    couples
+} // end of sample_auc_couples
+
+// computes auc and ranking metrics of couples (deleted edge, inexistent edge) given a node dissimilarity.
+// We count the number of times the distance of the deleted edge is less than the distance of the inexistent one.
+pub(crate) fn score_auc_couples(
+    couples: &[AucCouple],
+    distance: &dyn Fn(usize, usize) -> f64,
+) -> PassMetrics {
+    let nb_sample = couples.len();
+    let mut good = 0.;
+    let mut nb_dist_equality: usize = 0;
+    let mut dist_pos = Vec::<f64>::with_capacity(nb_sample);
+    let mut dist_neg = Vec::<f64>::with_capacity(nb_sample);
+    for (del_edge, no_edge) in couples {
+        let dist_del_edge = distance(del_edge.0, del_edge.1);
+        let dist_no_edge = distance(no_edge.0, no_edge.1);
         if dist_del_edge < dist_no_edge {
             good += 1.;
         } else if dist_del_edge <= dist_no_edge {
             good += 0.5;
             nb_dist_equality += 1;
         }
+        dist_pos.push(dist_del_edge);
+        dist_neg.push(dist_no_edge);
     }
     let auc = good / nb_sample as f64;
     log::info!(" auc = {:3.e} nb dist equality : {}", auc, nb_dist_equality);
     //
-    auc
-} // end of one_auc_iteration
+    let metrics = ranking_metrics(auc, &dist_pos, &dist_neg);
+    log::info!(
+        " average precision = {:.3e}, hits@10 = {:.3e}, mrr = {:.3e}",
+        metrics.average_precision,
+        metrics.hits_at_10,
+        metrics.mrr
+    );
+    metrics
+} // end of score_auc_couples
+
+// estimates the memory needed by a pass : triplets of train matrix, edge sets and embedded data.
+// We double the estimation to account for the memory the embedder needs during its computations.
+pub(crate) fn estimate_pass_memory<F, G, E>(nb_edges: usize, embedded: &E) -> u64
+where
+    E: EmbeddedT<G>,
+{
+    let edges_memory = nb_edges * (2 * std::mem::size_of::<usize>() + std::mem::size_of::<F>())
+        + 2 * nb_edges * 2 * std::mem::size_of::<(usize, usize)>();
+    let nb_vectors = if embedded.is_symetric() { 1 } else { 2 };
+    let embedded_memory =
+        nb_vectors * embedded.get_nb_nodes() * embedded.get_dimension() * std::mem::size_of::<G>();
+    2 * (edges_memory + embedded_memory) as u64
+} // end of estimate_pass_memory
+
+// returns available memory in bytes as given by /proc/meminfo, None if it cannot be determined
+fn get_available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    for line in meminfo.lines() {
+        if let Some(value) = line.strip_prefix("MemAvailable:") {
+            let kb = value.split_whitespace().next()?.parse::<u64>().ok()?;
+            return Some(kb * 1024);
+        }
+    }
+    None
+} // end of get_available_memory
+
+// returns the number of passes we can run concurrently so that they use at most half of available memory.
+// If available memory is unknown we run passes one after another.
+pub(crate) fn get_nb_concurrent_passes(pass_memory: u64) -> usize {
+    let nb_threads = rayon::current_num_threads() as u64;
+    match get_available_memory() {
+        Some(available) => (available / 2 / pass_memory.max(1)).clamp(1, nb_threads) as usize,
+        None => {
+            log::info!("cannot determine available memory, running validation passes serially");
+            1
+        }
+    }
+} // end of get_nb_concurrent_passes
+
+// Runs nbiter validation passes and returns their results in pass order.
+// Pass i gets its own random generator, obtained by i jumps from a generator initialized with seed,
+// so results do not depend on passes being run serially or in parallel.
+// pass returns its result and an estimation of the memory it needed. The first pass is run alone to get this estimation,
+// the following passes are run in parallel by chunks of get_nb_concurrent_passes() passes.
+pub(crate) fn run_passes<T: Send>(
+    nbiter: usize,
+    seed: u64,
+    pass: impl Fn(Xoshiro256PlusPlus) -> (T, u64) + Sync,
+) -> Vec<T> {
+    //
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut rngs = Vec::<Xoshiro256PlusPlus>::with_capacity(nbiter);
+    for _ in 0..nbiter {
+        rngs.push(rng.clone());
+        rng.jump();
+    }
+    //
+    let mut results = Vec::<T>::with_capacity(nbiter);
+    if nbiter == 0 {
+        return results;
+    }
+    let (first, pass_memory) = pass(rngs[0].clone());
+    results.push(first);
+    let nb_concurrent = get_nb_concurrent_passes(pass_memory);
+    log::info!(
+        "run_passes : memory estimated for a pass : {:.3e} Mb, nb concurrent passes : {}",
+        pass_memory as f64 / (1024. * 1024.),
+        nb_concurrent
+    );
+    let mut next = 1;
+    while next < nbiter {
+        let last = (next + nb_concurrent).min(nbiter);
+        let mut chunk: Vec<T> = (next..last)
+            .into_par_iter()
+            .map(|i| pass(rngs[i].clone()).0)
+            .collect();
+        results.append(&mut chunk);
+        next = last;
+    }
+    //
+    results
+} // end of run_passes
+
+// computes average precision, Hits@k and mrr from distances of positive (deleted edges) and negative (inexistent edges) samples.
+// Hits@k is the fraction of positives with distance smaller than the k-th smallest negative distance (as in Open Graph Benchmark).
+// The reciprocal rank of a positive sample is computed against a window of MRR_NB_NEG negative samples, ties count for one half.
+fn ranking_metrics(auc: f64, dist_pos: &[f64], dist_neg: &[f64]) -> PassMetrics {
+    const MRR_NB_NEG: usize = 100;
+    //
+    let nb_pos = dist_pos.len();
+    let nb_neg = dist_neg.len();
+    if nb_pos == 0 || nb_neg == 0 {
+        return PassMetrics {
+            auc,
+            ..Default::default()
+        };
+    }
+    // NaN distances (degenerate embeddings) are mapped to infinity so that they are ranked last,
+    // whatever their sign bit, in the sort and in the Hits@k and mrr comparisons.
+    let not_nan = |d: &f64| if d.is_nan() { f64::INFINITY } else { *d };
+    let dist_pos: Vec<f64> = dist_pos.iter().map(not_nan).collect();
+    let dist_neg: Vec<f64> = dist_neg.iter().map(not_nan).collect();
+    // average precision. In case of equal distances negative samples are ranked first (pessimistic).
+    let mut samples: Vec<(f64, bool)> = dist_pos
+        .iter()
+        .map(|d| (*d, true))
+        .chain(dist_neg.iter().map(|d| (*d, false)))
+        .collect();
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    let mut nb_pos_seen = 0usize;
+    let mut sum_precision = 0.;
+    for (rank, sample) in samples.iter().enumerate() {
+        if sample.1 {
+            nb_pos_seen += 1;
+            sum_precision += nb_pos_seen as f64 / (rank + 1) as f64;
+        }
+    }
+    let average_precision = sum_precision / nb_pos as f64;
+    // hits@k
+    let mut sorted_neg = dist_neg.clone();
+    sorted_neg.sort_unstable_by(|a, b| a.total_cmp(b));
+    let hits_at = |k: usize| -> f64 {
+        let threshold = sorted_neg[(k - 1).min(nb_neg - 1)];
+        dist_pos.iter().filter(|d| **d < threshold).count() as f64 / nb_pos as f64
+    };
+    // mrr
+    let window = MRR_NB_NEG.min(nb_neg);
+    let mut sum_rr = 0.;
+    for (k, d) in dist_pos.iter().enumerate() {
+        let mut rank = 1.;
+        for m in 0..window {
+            let d_neg = dist_neg[(k + m) % nb_neg];
+            if d_neg < *d {
+                rank += 1.;
+            } else if d_neg <= *d {
+                rank += 0.5;
+            }
+        }
+        sum_rr += 1. / rank;
+    }
+    //
+    PassMetrics {
+        auc,
+        average_precision,
+        hits_at_1: hits_at(1),
+        hits_at_10: hits_at(10),
+        hits_at_50: hits_at(50),
+        mrr: sum_rr / nb_pos as f64,
+    }
+} // end of ranking_metrics
 
 //
 //
@@ -534,14 +805,23 @@ where
 /// estimate AUC as described in Link Prediction in complex Networks : A survey
 ///             Lü, Zhou. Physica 2011
 ///
+/// Each pass also computes average precision, Hits@{1,10,50} and MRR from the sampled deleted and inexistent edges.
+/// The returned report gathers per pass metrics and their confidence intervals. See [LinkPredictionReport].
+///
+/// The first pass is run alone and gives an estimation of the memory a pass needs. The following passes are then run in parallel
+/// as long as they use at most half of the available memory.
+/// Each pass has its own random generator stream, obtained by jumps from a generator initialized with seed,
+/// so results do not depend on passes being run serially or in parallel.
+///
 /// type G is necessary beccause we embed in a possibly different type than F. (for example in Array\<usize\> with nodesketch)
 pub fn estimate_auc<F, G, E>(
     csmat: &CsMatI<F, usize>,
     nbiter: usize,
     delete_proba: f64,
     symetric: bool,
+    seed: u64,
     embedder: &(dyn Fn(TriMatI<F, usize>) -> E + Sync),
-) -> Vec<f64>
+) -> LinkPredictionReport
 where
     F: Default + Copy + std::marker::Sync,
     G: std::fmt::Debug,
@@ -551,44 +831,167 @@ where
     log::info!("=======================================");
     log::info!("in estimate_auc, symetric mode : {:?}", symetric);
     log::info!("=======================================");
-    // we allocate as many random generator we need for each iteration for future // of iterations
-    let mut rng = Xoshiro256PlusPlus::seed_from_u64(456231);
-    let mut rngs = Vec::<Xoshiro256PlusPlus>::with_capacity(nbiter);
-    for _ in 0..nbiter {
-        let new_rng = rng.clone();
-        rngs.push(new_rng);
-        // now we reinitialize rng by cloning it and shifting it with a large jump
-        rng.jump();
+    let passes = run_passes(nbiter, seed, |rng| {
+        one_auc_iteration(csmat, delete_proba, symetric, embedder, rng)
+    });
+    let report = LinkPredictionReport::from_passes(symetric, delete_proba, passes);
+    let auc = report.get_auc().unwrap();
+    log::info!(
+        "estimate_auc : mean auc : {:.3e}, std dev : {:.3e}, 95% confidence interval : {:.3e} {:.3e}",
+        auc.get_mean(),
+        auc.get_std_dev(),
+        auc.get_confidence_interval().0,
+        auc.get_confidence_interval().1
+    );
+    log::info!(
+        "estimate_auc : mean average precision : {:.3e}, mean mrr : {:.3e}",
+        report.get_average_precision().unwrap().get_mean(),
+        report.get_mrr().unwrap().get_mean()
+    );
+    log::debug!("exiting estimate_auc");
+    //
+    report
+} // end of estimate_auc
+
+/// source to target dissimilarity of an asymetric embedding : distance from source vector of node_rank1 to target vector of node_rank2.
+///
+/// It can be passed as score to [estimate_direction_auc()] instead of [EmbeddedAsym::get_noderank_distance()]
+/// which averages source-source, target-target and source-target distances.
+pub fn source_target_distance<G>(
+    embedded: &EmbeddedAsym<G>,
+    node_rank1: usize,
+    node_rank2: usize,
+) -> f64 {
+    embedded.get_vec_distance(
+        embedded
+            .get_embedded_source()
+            .row(node_rank1)
+            .as_slice()
+            .unwrap(),
+        embedded
+            .get_embedded_target()
+            .row(node_rank2)
+            .as_slice()
+            .unwrap(),
+    )
+} // end of source_target_distance
+
+// One pass of direction auc estimation.
+// returns the metrics of standard auc with score, the direction auc (None if no deleted edge has an inexistent reversal)
+// and an estimation of memory needed by the pass
+fn one_direction_iteration<F, G>(
+    csmat: &CsMatI<F, usize>,
+    delete_proba: f64,
+    embedder: &dyn Fn(TriMatI<F, usize>) -> EmbeddedAsym<G>,
+    score: &dyn Fn(&EmbeddedAsym<G>, usize, usize) -> f64,
+    mut rng: Xoshiro256PlusPlus,
+) -> (PassMetrics, Option<f64>, u64)
+where
+    F: Default + Copy + std::marker::Sync,
+{
+    //
+    let (trimat, deleted_edges) = filter_csmat(csmat, delete_proba, false, &mut rng);
+    let mut trimat_set = HashSet::<(usize, usize)>::with_capacity(trimat.nnz());
+    for triplet in trimat.triplet_iter() {
+        trimat_set.insert((triplet.1.0, triplet.1.1));
     }
+    let embedded = &embedder(trimat);
     //
-    let mut auc = Vec::<f64>::with_capacity(nbiter);
-    // switch in case of debugging
-    let parallel = false;
-    if parallel {
-        auc = (0..nbiter)
-            .into_par_iter()
-            .map(|i| one_auc_iteration(csmat, delete_proba, symetric, embedder, rngs[i].clone()))
-            .collect();
-    } else {
-        for i in 0..nbiter {
-            let iter_auc =
-                one_auc_iteration(csmat, delete_proba, symetric, embedder, rngs[i].clone());
-            auc.push(iter_auc);
+    let couples = sample_auc_couples(
+        csmat.shape().0,
+        false,
+        &trimat_set,
+        &deleted_edges,
+        NB_AUC_SAMPLE,
+        &mut rng,
+    );
+    let metrics = score_auc_couples(&couples, &|i, j| score(embedded, i, j));
+    // each deleted edge i->j whose reversal j->i is not an edge of the original graph is compared with its reversal
+    let mut good = 0.;
+    let mut nb_compared: usize = 0;
+    for (i, j) in deleted_edges.iter() {
+        if csmat.get(*j, *i).is_some() {
+            continue;
+        }
+        let dist_edge = score(embedded, *i, *j);
+        let dist_reversed = score(embedded, *j, *i);
+        if dist_edge < dist_reversed {
+            good += 1.;
+        } else if dist_edge <= dist_reversed {
+            good += 0.5;
         }
+        nb_compared += 1;
     }
-    let mean_auc: f64 = auc.iter().sum::<f64>() / (auc.len() as f64);
-    let sigma2 = auc.iter().fold(0.0f64, |var: f64, x| {
-        var + (*x - mean_auc) * (*x - mean_auc)
-    }) / (auc.len() as f64);
+    let direction_auc = if nb_compared > 0 {
+        let direction_auc = good / nb_compared as f64;
+        log::info!(
+            " direction auc = {:.3e}, nb edges compared with reversal : {}",
+            direction_auc,
+            nb_compared
+        );
+        Some(direction_auc)
+    } else {
+        log::warn!("one_direction_iteration : no deleted edge has an inexistent reversal");
+        None
+    };
+    //
+    let pass_memory = estimate_pass_memory::<F, G, EmbeddedAsym<G>>(trimat_set.len(), embedded);
+    //
+    (metrics, direction_auc, pass_memory)
+} // end of one_direction_iteration
+
+/// Estimates how well an asymetric embedding distinguishes an edge i->j from its reversal j->i.
+///
+/// Each pass deletes directed edges with probability delete_proba and embeds the remaining graph.
+/// Each deleted edge i->j such that j->i is not an edge of the original graph is compared with its reversal:
+/// the direction auc is the probability that score(i,j) is smaller than score(j,i) (ties count for one half).
+///
+/// score is the dissimilarity from a source node to a target node, it can be [EmbeddedAsym::get_noderank_distance()],
+/// [source_target_distance()] or any function of the source and target vectors.
+/// The returned report also contains the standard (asymetric) auc metrics computed with score, see [estimate_auc()],
+/// passes are run as in [estimate_auc()].
+pub fn estimate_direction_auc<F, G>(
+    csmat: &CsMatI<F, usize>,
+    nbiter: usize,
+    delete_proba: f64,
+    seed: u64,
+    embedder: &(dyn Fn(TriMatI<F, usize>) -> EmbeddedAsym<G> + Sync),
+    score: &(dyn Fn(&EmbeddedAsym<G>, usize, usize) -> f64 + Sync),
+) -> LinkPredictionReport
+where
+    F: Default + Copy + std::marker::Sync,
+    G: std::marker::Sync,
+{
+    //
+    log::info!("=======================================");
+    log::info!("in estimate_direction_auc");
+    log::info!("=======================================");
+    //
+    let passes = run_passes(nbiter, seed, |rng| {
+        let (metrics, direction, pass_memory) =
+            one_direction_iteration(csmat, delete_proba, embedder, score, rng);
+        ((metrics, direction), pass_memory)
+    });
+    //
+    let direction_values: Vec<f64> = passes.iter().filter_map(|p| p.1).collect();
+    let mut report = LinkPredictionReport::from_passes(
+        false,
+        delete_proba,
+        passes.into_iter().map(|p| p.0).collect(),
+    );
+    report.set_estimator(LinkEstimator::DirectionAuc);
+    let direction_auc = MetricSummary::new(direction_values);
     log::info!(
-        "estimate_auc : mean auc : {:.3e}, std dev : {:.3e}",
-        mean_auc,
-        (sigma2 / (auc.len() as f64)).sqrt()
+        "estimate_direction_auc : mean direction auc : {:.3e}, std dev : {:.3e}, 95% confidence interval : {:.3e} {:.3e}",
+        direction_auc.get_mean(),
+        direction_auc.get_std_dev(),
+        direction_auc.get_confidence_interval().0,
+        direction_auc.get_confidence_interval().1
     );
-    log::debug!("exiting estimate_auc");
+    report.set_direction_auc(direction_auc);
     //
-    auc
-} // end of estimate_auc
+    report
+} // end of estimate_direction_auc
 
 //
 //
@@ -600,14 +1003,48 @@ where
 /// The method implies a sort of all predicted edges around sampled nodes so it comes at a cost in large ( >= 100_000 nodes) graphs.
 //
 /// See also the function [estimate_centric_auc()] which implements a centric Auc and avoid the sorting cost.
+///
+/// The returned report contains precision@nb_edges_check for each sampled node having a deleted edge.
+/// Nodes are sampled uniformly as in the paper, see [estimate_vcmpr_with_sampling()] for other samplings.
 pub fn estimate_vcmpr<F, G, E>(
+    csmat: &CsMatI<F, usize>,
+    nbiter: usize,
+    nb_edges_check: usize,
+    delete_proba: f64,
+    symetric: bool,
+    seed: u64,
+    embedder: &(dyn Fn(TriMatI<F, usize>) -> E + Sync),
+) -> LinkPredictionReport
+where
+    F: Default + Copy + std::marker::Sync,
+    G: std::fmt::Debug,
+    E: EmbeddedT<G> + std::marker::Sync,
+{
+    estimate_vcmpr_with_sampling(
+        csmat,
+        nbiter,
+        nb_edges_check,
+        delete_proba,
+        symetric,
+        seed,
+        NodeSampling::Uniform,
+        embedder,
+    )
+} // end of estimate_vcmpr
+
+/// Same as [estimate_vcmpr()], the examined nodes being sampled as specified by sampling.
+#[allow(clippy::too_many_arguments)]
+pub fn estimate_vcmpr_with_sampling<F, G, E>(
     csmat: &CsMatI<F, usize>,
     _nbiter: usize,
     nb_edges_check: usize,
     delete_proba: f64,
     symetric: bool,
+    seed: u64,
+    sampling: NodeSampling,
     embedder: &(dyn Fn(TriMatI<F, usize>) -> E + Sync),
-) where
+) -> LinkPredictionReport
+where
     F: Default + Copy + std::marker::Sync,
     G: std::fmt::Debug,
     E: EmbeddedT<G> + std::marker::Sync,
@@ -640,7 +1077,7 @@ pub fn estimate_vcmpr<F, G, E>(
     }
     //
     let histogram = std::sync::Arc::new(std::sync::RwLock::new(CKMS::<f64>::new(0.001)));
-    let mut rng = Xoshiro256PlusPlus::seed_from_u64(456231);
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
     // split edges into kept and discarded
     let (trimat, deleted_edges) = filter_csmat(csmat, delete_proba, symetric, &mut rng);
     // need to store trimat index before move to embedding
@@ -660,21 +1097,12 @@ pub fn estimate_vcmpr<F, G, E>(
     if !embedded.is_symetric() {
         log::warn!("method estimate_vcmpr only possible for symetric embeddings");
     }
-    // sample nodes according to paper algo or mine
+    // sample nodes uniformly as in paper or with another sampling
     let nb_nodes = csmat.shape().0;
     let nb_to_sample = 2000;
-    // do we choose paper algorithm or my modification.
-    let uniform_as_paper: bool = true;
-    log::info!("=======================================");
-    log::info!("sampling uniform : {:?}", uniform_as_paper,);
-    log::info!("=======================================\n");
     //
     //=====================================================
-    let selected_nodes = if uniform_as_paper {
-        sample_nodes_uniform(csmat, nb_to_sample)
-    } else {
-        sample_nodes_by_degrees(csmat, nb_to_sample)
-    };
+    let selected_nodes = sample_validation_nodes(csmat, nb_to_sample, symetric, sampling, &mut rng);
     //
     let degrees = csmat.degrees();
     let mut mean_degree: f64 = 0.;
@@ -828,7 +1256,12 @@ pub fn estimate_vcmpr<F, G, E>(
         cpu_start.elapsed().as_secs()
     );
     //
-} // end of estimate_vcmpr
+    let mut report = LinkPredictionReport::new(LinkEstimator::Vcmpr, symetric, delete_proba, 1);
+    report.set_precision(MetricSummary::new(precision));
+    report.set_nb_edges_check(nb_edges_check);
+    report.set_degree_correlation(rho);
+    report
+} // end of estimate_vcmpr_with_sampling
 
 //
 //
@@ -870,13 +1303,44 @@ pub fn estimate_vcmpr<F, G, E>(
 /// -  correlation coefficients between degrees and centric auc.
 /// -  time spent in the function (as the sorting of larges arrays cost cpu time)
 ///
+///    The returned report contains the centric auc of each sampled node having a deleted edge.
+///
+/// Nodes are sampled uniformly, see [estimate_centric_auc_with_sampling()] for other samplings.
 pub fn estimate_centric_auc<F, G, E>(
+    csmat: &CsMatI<F, usize>,
+    nbiter: usize,
+    delete_proba: f64,
+    symetric: bool,
+    seed: u64,
+    embedder: &(dyn Fn(TriMatI<F, usize>) -> E + Sync),
+) -> LinkPredictionReport
+where
+    F: Default + Copy + std::marker::Sync,
+    G: std::fmt::Debug,
+    E: EmbeddedT<G> + std::marker::Sync,
+{
+    estimate_centric_auc_with_sampling(
+        csmat,
+        nbiter,
+        delete_proba,
+        symetric,
+        seed,
+        NodeSampling::Uniform,
+        embedder,
+    )
+} // end of estimate_centric_auc
+
+/// Same as [estimate_centric_auc()], the examined nodes being sampled as specified by sampling.
+pub fn estimate_centric_auc_with_sampling<F, G, E>(
     csmat: &CsMatI<F, usize>,
     _nbiter: usize,
     delete_proba: f64,
     symetric: bool,
+    seed: u64,
+    sampling: NodeSampling,
     embedder: &(dyn Fn(TriMatI<F, usize>) -> E + Sync),
-) where
+) -> LinkPredictionReport
+where
     F: Default + Copy + std::marker::Sync,
     G: std::fmt::Debug,
     E: EmbeddedT<G> + std::marker::Sync,
@@ -921,7 +1385,7 @@ pub fn estimate_centric_auc<F, G, E>(
     }
     println!("\n");
     //
-    let mut rng = Xoshiro256PlusPlus::seed_from_u64(456231);
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
     // split edges into kept and discarded
     let (trimat, deleted_edges) = filter_csmat(csmat, delete_proba, symetric, &mut rng);
     // need to store trimat index before move to embedding
@@ -943,16 +1407,7 @@ pub fn estimate_centric_auc<F, G, E>(
     }
     let nb_nodes = csmat.shape().0;
     let nb_to_sample = 2000;
-    // sample nodes according to paper algo or mine
-    let uniform: bool = true;
-    log::info!("sampling uniform : {:?}", uniform);
-    log::info!("==================================");
-    //
-    let selected_nodes = if uniform {
-        sample_nodes_uniform(csmat, nb_to_sample)
-    } else {
-        sample_nodes_by_degrees(csmat, nb_to_sample)
-    };
+    let selected_nodes = sample_validation_nodes(csmat, nb_to_sample, symetric, sampling, &mut rng);
     //
     // select nodes we will test
     let nb_sampled = selected_nodes.len();
@@ -1087,7 +1542,12 @@ pub fn estimate_centric_auc<F, G, E>(
         cpu_start.elapsed().as_secs()
     );
     //
-} // end of estimate_centric_auc
+    let mut report =
+        LinkPredictionReport::new(LinkEstimator::CentricAuc, symetric, delete_proba, 1);
+    report.set_auc(MetricSummary::new(selected_auc));
+    report.set_degree_correlation(rho);
+    report
+} // end of estimate_centric_auc_with_sampling
 
 //================================================================================================================
 
@@ -1174,9 +1634,16 @@ mod tests {
             let trimat_indexed = res.unwrap();
             let csrmat: CsMatI<f64, usize> = trimat_indexed.0.to_csr();
             let symetric = true;
-            let precision = estimate_precision(&csrmat, 3, 0.2, symetric, &nodesketch_get_embedded);
-            log::debug!("precision : {:?}", precision.0);
-            log::debug!("recall : {:?}", precision.1);
+            let report = estimate_precision(
+                &csrmat,
+                3,
+                0.2,
+                symetric,
+                DEFAULT_VALIDATION_SEED,
+                &nodesketch_get_embedded,
+            );
+            log::debug!("precision : {:?}", report.get_precision());
+            log::debug!("recall : {:?}", report.get_recall());
         };
     } // end of test_link_precision_nodesketch_lesmiserables
 
@@ -1201,7 +1668,14 @@ mod tests {
             let trimat_indexed = res.unwrap();
             let csrmat: CsMatI<f64, usize> = trimat_indexed.0.to_csr();
             let symetric = true;
-            let auc = estimate_auc(&csrmat, 3, 0.2, symetric, &nodesketch_get_embedded);
+            let auc = estimate_auc(
+                &csrmat,
+                3,
+                0.2,
+                symetric,
+                DEFAULT_VALIDATION_SEED,
+                &nodesketch_get_embedded,
+            );
             log::info!("auc : {:?}", auc);
         };
     } // end of test_link_auc_nodesketch_lesmiserables
@@ -1229,11 +1703,54 @@ mod tests {
             let trimat_indexed = res.unwrap();
             let csrmat: CsMatI<f64, usize> = trimat_indexed.0.to_csr();
             let symetric = false;
-            let auc = estimate_auc(&csrmat, 5, 0.1, symetric, &nodesketchasym_get_embedded);
+            let auc = estimate_auc(
+                &csrmat,
+                5,
+                0.1,
+                symetric,
+                DEFAULT_VALIDATION_SEED,
+                &nodesketchasym_get_embedded,
+            );
             log::info!("auc : {:?}", auc);
         };
     } // end of test_link_auc_nodesketchasym_lesmiserables
 
+    // the lesmiserables file lists each edge once, loaded as directed no edge has its reversal
+    #[test]
+    fn test_link_direction_auc_nodesketchasym_lesmiserables() {
+        //
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n test_link_direction_auc_nodesketchasym_lesmiserables, loading file {:?}",
+            path
+        );
+        let res = csv_to_trimat::<f64>(&path, true, b' ');
+        if res.is_err() {
+            log::error!(
+                "test_link_direction_auc_nodesketchasym_lesmiserables failed in csv_to_trimat"
+            );
+            assert_eq!(1, 0);
+        }
+        let csrmat: CsMatI<f64, usize> = res.unwrap().0.to_csr();
+        let report = estimate_direction_auc(
+            &csrmat,
+            3,
+            0.1,
+            DEFAULT_VALIDATION_SEED,
+            &nodesketchasym_get_embedded,
+            &source_target_distance,
+        );
+        log::info!("report : {:?}", report);
+        assert_eq!(report.get_estimator(), LinkEstimator::DirectionAuc);
+        let direction_auc = report.get_direction_auc().unwrap();
+        assert_eq!(direction_auc.get_values().len(), 3);
+        assert!(direction_auc.get_mean() >= 0. && direction_auc.get_mean() <= 1.);
+    } // end of test_link_direction_auc_nodesketchasym_lesmiserables
+
     // ============================  auc testing for hope ==========================  //
 
     // functon to pass to auc methods
@@ -1274,8 +1791,75 @@ mod tests {
             let trimat_indexed = res.unwrap();
             let csrmat: CsMatI<f64, usize> = trimat_indexed.0.to_csr();
             let symetric = true;
-            let auc = estimate_auc(&csrmat, 5, 0.1, symetric, &hope_ada_get_embedded);
+            let auc = estimate_auc(
+                &csrmat,
+                5,
+                0.1,
+                symetric,
+                DEFAULT_VALIDATION_SEED,
+                &hope_ada_get_embedded,
+            );
             log::info!("auc : {:?}", auc);
         }
     } // end of test_link_auc_hope_ada_lesmiserables
+
+    #[test]
+    fn test_ranking_metrics_nan() {
+        log_init_test();
+        // deleted edges all nearer than inexistent edges
+        let dist_pos = [0.1, 0.2, 0.3];
+        let dist_neg = [0.5, 0.6, 0.7, 0.8];
+        let metrics = ranking_metrics(1., &dist_pos, &dist_neg);
+        assert!((metrics.average_precision - 1.).abs() < 1.0e-10);
+        assert!((metrics.hits_at_1 - 1.).abs() < 1.0e-10);
+        // a NaN distance must not panic, it is ranked last
+        let dist_pos = [0.1, f64::NAN, 0.3];
+        let metrics = ranking_metrics(1., &dist_pos, &dist_neg);
+        log::info!("metrics with a NaN distance : {:?}", metrics);
+        assert!(metrics.average_precision < 1.);
+        assert!((metrics.hits_at_1 - 2. / 3.).abs() < 1.0e-10);
+        // a negative NaN is also ranked last, and gets the same metrics
+        let dist_pos = [0.1, -f64::NAN, 0.3];
+        let neg_metrics = ranking_metrics(1., &dist_pos, &dist_neg);
+        log::info!("metrics with a negative NaN distance : {:?}", neg_metrics);
+        assert_eq!(neg_metrics.average_precision, metrics.average_precision);
+        assert_eq!(neg_metrics.hits_at_1, metrics.hits_at_1);
+        assert_eq!(neg_metrics.mrr, metrics.mrr);
+        // a NaN negative distance is ranked after all positives
+        let dist_neg = [0.5, -f64::NAN, 0.7, 0.8];
+        let metrics = ranking_metrics(1., &[0.1, 0.2, 0.3], &dist_neg);
+        assert!((metrics.average_precision - 1.).abs() < 1.0e-10);
+        assert!((metrics.mrr - 1.).abs() < 1.0e-10);
+    } // end of test_ranking_metrics_nan
+
+    // a path of 20 nodes (core 1) linked to a clique of 6 nodes (core 5).
+    // coreness sampling must take half of the nodes in the clique
+    #[test]
+    fn test_sample_validation_nodes_coreness() {
+        log_init_test();
+        //
+        let mut edges = Vec::<(usize, usize)>::new();
+        for i in 0..20 {
+            edges.push((i, i + 1));
+        }
+        for i in 20..26 {
+            for j in (i + 1)..26 {
+                edges.push((i, j));
+            }
+        }
+        let mut rows = Vec::<usize>::new();
+        let mut cols = Vec::<usize>::new();
+        for (i, j) in edges {
+            rows.extend([i, j]);
+            cols.extend([j, i]);
+        }
+        let values = vec![1.; rows.len()];
+        let csrmat: CsMatI<f64, usize> =
+            TriMatI::<f64, usize>::from_triplets((26, 26), rows, cols, values).to_csr();
+        //
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(DEFAULT_VALIDATION_SEED);
+        let sampled = sample_validation_nodes(&csrmat, 10, true, NodeSampling::Coreness, &mut rng);
+        assert_eq!(sampled.len(), 10);
+        assert_eq!(sampled.iter().filter(|n| **n >= 20).count(), 5);
+    } // end of test_sample_validation_nodes_coreness
 } // end of mod tests
@@ -0,0 +1,250 @@
+//! Paired comparison of embedders on link prediction.
+//!
+//! When comparing two embedders (or two parameter settings of the same embedder) with [super::link::estimate_auc()]
+//! each run draws its own edge deletions so differences can come from the splits and not from the embedders.
+//! Here all embedders are evaluated pass by pass on exactly the same train graph and on the same sampled couples
+//! (deleted edge, inexistent edge). The per pass metrics are then compared with a paired t-test and a Wilcoxon signed-rank test.
+//! See [compare_embedders()] and [EmbedderComparison].
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use sprs::{CsMatI, TriMatI};
+
+use super::link::*;
+use super::linkreport::*;
+use crate::embedding::EmbeddedT;
+
+/// Access to node dissimilarities of an embedding, whatever the type of embedded vectors.
+///
+/// This makes it possible to compare embedders with different outputs (for example Hope giving EmbeddedAsym\<f64\>
+/// and NodeSketch giving Embedded\<usize\>).
+pub trait NodeDissimilarity: Sync {
+    /// dissimilarity between nodes of rank rank1 and rank2 (an edge from rank1 to rank2 in asymetric case)
+    fn get_noderank_distance(&self, rank1: usize, rank2: usize) -> f64;
+    /// estimation of memory in bytes needed by the pass that computed the embedded data
+    fn get_pass_memory(&self) -> u64;
+}
+
+// wraps an embedded data to erase the type of its vectors
+struct ErasedEmbedded<G, E> {
+    embedded: E,
+    pass_memory: u64,
+    _g: PhantomData<fn() -> G>,
+}
+
+impl<G, E> NodeDissimilarity for ErasedEmbedded<G, E>
+where
+    E: EmbeddedT<G> + Sync,
+{
+    fn get_noderank_distance(&self, rank1: usize, rank2: usize) -> f64 {
+        self.embedded.get_noderank_distance(rank1, rank2)
+    }
+
+    fn get_pass_memory(&self) -> u64 {
+        self.pass_memory
+    }
+} // end of impl NodeDissimilarity for ErasedEmbedded
+
+// an embedder returning type erased embedded data
+type ErasedEmbedder<'a, F> =
+    Box<dyn Fn(TriMatI<F, usize>) -> Box<dyn NodeDissimilarity + 'a> + Sync + 'a>;
+
+/// An embedder with a name to be used in [compare_embedders()]
+pub struct NamedEmbedder<'a, F> {
+    name: String,
+    embedder: ErasedEmbedder<'a, F>,
+}
+
+impl<'a, F: 'a> NamedEmbedder<'a, F> {
+    /// embedder is a closure as in [estimate_auc()]
+    pub fn new<G, E, Emb>(name: &str, embedder: Emb) -> Self
+    where
+        G: 'a,
+        E: EmbeddedT<G> + Sync + 'a,
+        Emb: Fn(TriMatI<F, usize>) -> E + Sync + 'a,
+    {
+        let erased = move |trimat: TriMatI<F, usize>| -> Box<dyn NodeDissimilarity + 'a> {
+            let nb_edges = trimat.nnz();
+            let embedded = embedder(trimat);
+            let pass_memory = estimate_pass_memory::<F, G, E>(nb_edges, &embedded);
+            Box::new(ErasedEmbedded {
+                embedded,
+                pass_memory,
+                _g: PhantomData::<fn() -> G>,
+            })
+        };
+        NamedEmbedder {
+            name: name.to_string(),
+            embedder: Box::new(erased),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+} // end of impl NamedEmbedder
+
+// runs one pass for all embedders on the same split and the same sampled couples.
+// returns the metrics of each embedder and an estimation of memory needed by the pass
+fn one_comparison_iteration<F>(
+    csmat: &CsMatI<F, usize>,
+    delete_proba: f64,
+    symetric: bool,
+    embedders: &[NamedEmbedder<F>],
+    mut rng: Xoshiro256PlusPlus,
+) -> (Vec<PassMetrics>, u64)
+where
+    F: Default + Copy + Sync,
+{
+    //
+    let (trimat, deleted_edges) = filter_csmat(csmat, delete_proba, symetric, &mut rng);
+    let mut trimat_set = HashSet::<(usize, usize)>::with_capacity(trimat.nnz());
+    for triplet in trimat.triplet_iter() {
+        trimat_set.insert((triplet.1.0, triplet.1.1));
+    }
+    let couples = sample_auc_couples(
+        csmat.shape().0,
+        symetric,
+        &trimat_set,
+        &deleted_edges,
+        NB_AUC_SAMPLE,
+        &mut rng,
+    );
+    //
+    let mut pass_memory = 0;
+    let mut metrics = Vec::<PassMetrics>::with_capacity(embedders.len());
+    for embedder in embedders {
+        log::info!("one_comparison_iteration, embedder : {}", embedder.name);
+        // TriMatI is not Clone
+        let train = TriMatI::<F, usize>::from_triplets(
+            trimat.shape(),
+            trimat.row_inds().to_vec(),
+            trimat.col_inds().to_vec(),
+            trimat.data().to_vec(),
+        );
+        let embedded = (embedder.embedder)(train);
+        pass_memory = pass_memory.max(embedded.get_pass_memory());
+        metrics.push(score_auc_couples(&couples, &|i, j| {
+            embedded.get_noderank_distance(i, j)
+        }));
+    }
+    //
+    (metrics, pass_memory)
+} // end of one_comparison_iteration
+
+/// Compares embedders on nbiter passes of link prediction.
+///
+/// Each pass deletes edges with probability delete_proba (as in [estimate_auc()]), then each embedder is run on the same train graph
+/// and evaluated on the same couples (deleted edge, inexistent edge).
+/// The returned [EmbedderComparison] contains a [LinkPredictionReport] for each embedder and the paired tests of all couples of embedders.
+///
+/// Passes are run in parallel as in [estimate_auc()] and results depend only on seed.
+pub fn compare_embedders<F>(
+    csmat: &CsMatI<F, usize>,
+    nbiter: usize,
+    delete_proba: f64,
+    symetric: bool,
+    seed: u64,
+    embedders: &[NamedEmbedder<F>],
+) -> EmbedderComparison
+where
+    F: Default + Copy + Sync,
+{
+    //
+    log::info!("=======================================");
+    log::info!(
+        "in compare_embedders, symetric mode : {:?}, nb embedders : {}",
+        symetric,
+        embedders.len()
+    );
+    log::info!("=======================================");
+    //
+    // passes[i][j] is metrics of pass i for embedder j
+    let passes = run_passes(nbiter, seed, |rng| {
+        one_comparison_iteration(csmat, delete_proba, symetric, embedders, rng)
+    });
+    //
+    let names: Vec<String> = embedders.iter().map(|e| e.name.clone()).collect();
+    let reports: Vec<LinkPredictionReport> = (0..embedders.len())
+        .map(|j| {
+            let embedder_passes: Vec<PassMetrics> = passes.iter().map(|p| p[j]).collect();
+            LinkPredictionReport::from_passes(symetric, delete_proba, embedder_passes)
+        })
+        .collect();
+    let comparison = EmbedderComparison::new(names, reports);
+    for c in comparison.get_comparisons() {
+        let (first, second) = c.get_names();
+        let auc = c.get_auc();
+        log::info!(
+            "{} vs {} : auc difference {:.3e}, 95% interval : {:.3e} {:.3e}, t-test p-value : {:.3e}, wilcoxon p-value : {:.3e}",
+            first,
+            second,
+            auc.get_mean_diff(),
+            auc.get_confidence_interval().0,
+            auc.get_confidence_interval().1,
+            auc.get_t_test().1,
+            auc.get_wilcoxon().1
+        );
+    }
+    //
+    comparison
+} // end of compare_embedders
+
+//================================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::io::csv::*;
+    use crate::prelude::*;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // compare 2 decay values of nodesketch
+    #[test]
+    fn test_compare_nodesketch_decay_lesmiserables() {
+        //
+        log_init_test();
+        //
+        let path = std::path::Path::new(crate::DATADIR)
+            .join("moreno_lesmis")
+            .join("out.moreno_lesmis_lesmis");
+        log::info!(
+            "\n\n test_compare_nodesketch_decay_lesmiserables, loading file {:?}",
+            path
+        );
+        let res = csv_to_trimat::<f64>(&path, false, b' ');
+        if res.is_err() {
+            log::error!("test_compare_nodesketch_decay_lesmiserables failed in csv_to_trimat");
+            assert_eq!(1, 0);
+        }
+        let csrmat: CsMatI<f64, usize> = res.unwrap().0.to_csr();
+        let embedders: Vec<NamedEmbedder<f64>> = [0.1, 0.5]
+            .iter()
+            .map(|decay| {
+                let params = NodeSketchParams {
+                    sketch_size: 300,
+                    decay: *decay,
+                    nb_iter: 5,
+                    symetric: true,
+                    parallel: true,
+                };
+                NamedEmbedder::new(&format!("decay {}", decay), move |trimat| {
+                    NodeSketch::new(params, trimat).embed().unwrap()
+                })
+            })
+            .collect();
+        let comparison =
+            compare_embedders(&csrmat, 5, 0.1, true, DEFAULT_VALIDATION_SEED, &embedders);
+        assert_eq!(comparison.get_reports().len(), 2);
+        assert_eq!(comparison.get_comparisons().len(), 1);
+        log::info!("comparison : {:?}", comparison.get_comparison(0, 1));
+    } // end of test_compare_nodesketch_decay_lesmiserables
+} // end of mod tests
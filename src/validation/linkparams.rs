@@ -1,9 +1,16 @@
 //! Validation parameters
 
+use super::link::{NodeSampling, ValidationMode};
+
+/// The seed used by default for the random generators of validation passes
+pub const DEFAULT_VALIDATION_SEED: u64 = 456231;
+
+/// The number of neighbours examined by default in label-homophily@k validation
+pub const DEFAULT_LABEL_KNBN: usize = 10;
+
 /// A structure describing validation strategy.
-/// At present time only link prediction is implemented
-///
-//  TODO nodelabeling
+/// The validation mode is link prediction by default, see [Self::with_mode].
+/// The delete fraction and the number of passes are not used in [ValidationMode::NODELABEL] mode
 #[derive(Copy, Clone, Debug)]
 pub struct ValidationParams {
     /// The fraction of edges to skip when construction the train set
@@ -14,6 +21,14 @@ pub struct ValidationParams {
     symetric: bool,
     /// centric flag to ask for centric auc computation
     centric: bool,
+    /// seed of the random generators used for edge deletion and sampling.
+    seed: u64,
+    /// validation mode
+    mode: ValidationMode,
+    /// number of neighbours examined in label-homophily@k
+    label_knbn: usize,
+    /// sampling of nodes in centric auc and vcmpr estimations
+    sampling: NodeSampling,
 } // end of ValidationParams
 
 impl ValidationParams {
@@ -23,9 +38,20 @@ impl ValidationParams {
             nbpass,
             symetric,
             centric,
+            seed: DEFAULT_VALIDATION_SEED,
+            mode: ValidationMode::LINK,
+            label_knbn: DEFAULT_LABEL_KNBN,
+            sampling: NodeSampling::Uniform,
         }
     }
 
+    /// sets the seed of random generators, so that different experiments can be run or reproduced.
+    /// Default is [DEFAULT_VALIDATION_SEED]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
     /// number of pass in validation
     pub fn get_nbpass(&self) -> usize {
         self.nbpass
@@ -45,4 +71,42 @@ impl ValidationParams {
     pub fn do_centric(&self) -> bool {
         self.centric
     }
+
+    /// returns the seed of random generators
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// sets the validation mode. Default is [ValidationMode::LINK]
+    pub fn with_mode(mut self, mode: ValidationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// returns the validation mode
+    pub fn get_mode(&self) -> ValidationMode {
+        self.mode
+    }
+
+    /// sets the number of neighbours examined in label-homophily@k. Default is [DEFAULT_LABEL_KNBN]
+    pub fn with_label_knbn(mut self, knbn: usize) -> Self {
+        self.label_knbn = knbn;
+        self
+    }
+
+    /// returns the number of neighbours examined in label-homophily@k
+    pub fn get_label_knbn(&self) -> usize {
+        self.label_knbn
+    }
+
+    /// sets the sampling of nodes examined in centric auc and vcmpr estimations. Default is [NodeSampling::Uniform]
+    pub fn with_sampling(mut self, sampling: NodeSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// returns the sampling of nodes examined in centric auc and vcmpr estimations
+    pub fn get_sampling(&self) -> NodeSampling {
+        self.sampling
+    }
 } // end of ValidationParams
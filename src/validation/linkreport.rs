@@ -0,0 +1,642 @@
+//! This module gathers the results of the link prediction estimators of [super::link] in a structure
+//! that can be dumped in json format.
+//!
+//! Each metric is summarized by its mean, standard deviation and a 95% confidence interval on the mean.
+//! The values from which the summary is computed are kept (they are per pass values for [super::link::estimate_auc()]
+//! and [super::link::estimate_precision()], and per sampled node values for the node centric estimators).
+
+use anyhow::*;
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::embed::tools::pairedtest::{paired_t_test, wilcoxon_signed_rank};
+use crate::io::jsonio::{dump_json, nonfinite, nonfinite_vec, reload_json};
+
+/// The metrics computed in one pass of AUC estimation.
+///
+/// Deleted edges are positive samples and inexistent edges are negative samples,
+/// samples are ranked by increasing distance in the embedding.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PassMetrics {
+    /// probability a deleted edge is ranked before an inexistent edge
+    pub auc: f64,
+    /// average precision (area under precision-recall curve) of deleted edges among positive and negative samples
+    pub average_precision: f64,
+    /// fraction of deleted edges ranked before the first inexistent edge
+    pub hits_at_1: f64,
+    /// fraction of deleted edges ranked before the 10th inexistent edge
+    pub hits_at_10: f64,
+    /// fraction of deleted edges ranked before the 50th inexistent edge
+    pub hits_at_50: f64,
+    /// mean reciprocal rank of a deleted edge among a set of inexistent edges
+    pub mrr: f64,
+} // end of PassMetrics
+
+/// Summary of a metric : mean, standard deviation, 95% confidence interval of the mean and values.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MetricSummary {
+    #[serde(with = "nonfinite")]
+    mean: f64,
+    /// standard deviation of values
+    #[serde(with = "nonfinite")]
+    std_dev: f64,
+    /// lower bound of 95% confidence interval of the mean
+    #[serde(with = "nonfinite")]
+    ci_low: f64,
+    /// upper bound of 95% confidence interval of the mean
+    #[serde(with = "nonfinite")]
+    ci_high: f64,
+    /// values the summary is computed from
+    #[serde(with = "nonfinite_vec")]
+    values: Vec<f64>,
+} // end of MetricSummary
+
+impl MetricSummary {
+    /// computes mean, std deviation and confidence interval (based on Student quantiles) from values
+    pub fn new(values: Vec<f64>) -> Self {
+        let nb_values = values.len();
+        if nb_values == 0 {
+            return MetricSummary {
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+                ci_low: f64::NAN,
+                ci_high: f64::NAN,
+                values,
+            };
+        }
+        let mean = values.iter().sum::<f64>() / nb_values as f64;
+        if nb_values == 1 {
+            return MetricSummary {
+                mean,
+                std_dev: 0.,
+                ci_low: mean,
+                ci_high: mean,
+                values,
+            };
+        }
+        let var = values
+            .iter()
+            .fold(0., |acc, x| acc + (x - mean) * (x - mean))
+            / (nb_values - 1) as f64;
+        let std_dev = var.sqrt();
+        let half_width = student_quantile_975(nb_values - 1) * std_dev / (nb_values as f64).sqrt();
+        MetricSummary {
+            mean,
+            std_dev,
+            ci_low: mean - half_width,
+            ci_high: mean + half_width,
+            values,
+        }
+    } // end of new
+
+    pub fn get_mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// returns standard deviation of values (not of the mean)
+    pub fn get_std_dev(&self) -> f64 {
+        self.std_dev
+    }
+
+    /// returns 95% confidence interval of mean
+    pub fn get_confidence_interval(&self) -> (f64, f64) {
+        (self.ci_low, self.ci_high)
+    }
+
+    pub fn get_values(&self) -> &[f64] {
+        &self.values
+    }
+} // end of impl MetricSummary
+
+// quantile at 0.975 of Student distribution with df degrees of freedom
+pub(crate) fn student_quantile_975(df: usize) -> f64 {
+    const T975: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::NAN,
+        1..=30 => T975[df - 1],
+        31..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+} // end of student_quantile_975
+
+/// The estimator that produced a [LinkPredictionReport]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkEstimator {
+    Auc,
+    Precision,
+    Vcmpr,
+    CentricAuc,
+    DirectionAuc,
+}
+
+/// The result of a link prediction estimation.
+///
+/// Metrics not computed by an estimator are set to None:
+/// - [super::link::estimate_auc()] fills auc, average_precision, hits_at_k, mrr and passes
+/// - [super::link::estimate_precision()] fills precision and recall
+/// - [super::link::estimate_vcmpr()] fills precision (precision@k for each sampled node) and degree_correlation
+/// - [super::link::estimate_centric_auc()] fills auc (centric auc for each sampled node) and degree_correlation
+/// - [super::link::estimate_direction_auc()] fills the same metrics as estimate_auc and direction_auc
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinkPredictionReport {
+    estimator: LinkEstimator,
+    symetric: bool,
+    delete_proba: f64,
+    /// number of passes (edge deletion and embedding) done
+    nb_pass: usize,
+    auc: Option<MetricSummary>,
+    average_precision: Option<MetricSummary>,
+    /// summary of Hits@k for k in [1, 10, 50]
+    hits_at_k: Option<Vec<(usize, MetricSummary)>>,
+    mrr: Option<MetricSummary>,
+    precision: Option<MetricSummary>,
+    recall: Option<MetricSummary>,
+    /// the k in precision@k in vcmpr mode
+    nb_edges_check: Option<usize>,
+    /// correlation between node degree and node metric in node centric modes
+    degree_correlation: Option<f64>,
+    /// probability a deleted edge is ranked before its reversal in direction mode
+    #[serde(default)]
+    direction_auc: Option<MetricSummary>,
+    /// detail of metrics for each pass
+    passes: Vec<PassMetrics>,
+} // end of LinkPredictionReport
+
+impl LinkPredictionReport {
+    /// allocates a report with no metric
+    pub fn new(
+        estimator: LinkEstimator,
+        symetric: bool,
+        delete_proba: f64,
+        nb_pass: usize,
+    ) -> Self {
+        LinkPredictionReport {
+            estimator,
+            symetric,
+            delete_proba,
+            nb_pass,
+            auc: None,
+            average_precision: None,
+            hits_at_k: None,
+            mrr: None,
+            precision: None,
+            recall: None,
+            nb_edges_check: None,
+            degree_correlation: None,
+            direction_auc: None,
+            passes: Vec::new(),
+        }
+    }
+
+    /// builds the report of auc estimation from per pass metrics
+    pub(crate) fn from_passes(symetric: bool, delete_proba: f64, passes: Vec<PassMetrics>) -> Self {
+        let mut report = Self::new(LinkEstimator::Auc, symetric, delete_proba, passes.len());
+        let summary = |f: &dyn Fn(&PassMetrics) -> f64| -> MetricSummary {
+            MetricSummary::new(passes.iter().map(f).collect())
+        };
+        report.auc = Some(summary(&|p| p.auc));
+        report.average_precision = Some(summary(&|p| p.average_precision));
+        report.hits_at_k = Some(vec![
+            (1, summary(&|p| p.hits_at_1)),
+            (10, summary(&|p| p.hits_at_10)),
+            (50, summary(&|p| p.hits_at_50)),
+        ]);
+        report.mrr = Some(summary(&|p| p.mrr));
+        report.passes = passes;
+        report
+    } // end of from_passes
+
+    pub(crate) fn set_estimator(&mut self, estimator: LinkEstimator) {
+        self.estimator = estimator;
+    }
+
+    pub(crate) fn set_auc(&mut self, auc: MetricSummary) {
+        self.auc = Some(auc);
+    }
+
+    pub(crate) fn set_precision(&mut self, precision: MetricSummary) {
+        self.precision = Some(precision);
+    }
+
+    pub(crate) fn set_recall(&mut self, recall: MetricSummary) {
+        self.recall = Some(recall);
+    }
+
+    pub(crate) fn set_nb_edges_check(&mut self, nb_edges_check: usize) {
+        self.nb_edges_check = Some(nb_edges_check);
+    }
+
+    pub(crate) fn set_degree_correlation(&mut self, rho: f64) {
+        self.degree_correlation = Some(rho);
+    }
+
+    pub(crate) fn set_direction_auc(&mut self, direction_auc: MetricSummary) {
+        self.direction_auc = Some(direction_auc);
+    }
+
+    pub fn get_estimator(&self) -> LinkEstimator {
+        self.estimator
+    }
+
+    pub fn is_symetric(&self) -> bool {
+        self.symetric
+    }
+
+    pub fn get_delete_proba(&self) -> f64 {
+        self.delete_proba
+    }
+
+    pub fn get_nb_pass(&self) -> usize {
+        self.nb_pass
+    }
+
+    pub fn get_auc(&self) -> Option<&MetricSummary> {
+        self.auc.as_ref()
+    }
+
+    /// returns average precision, i.e an estimation of the area under precision-recall curve
+    pub fn get_average_precision(&self) -> Option<&MetricSummary> {
+        self.average_precision.as_ref()
+    }
+
+    /// returns Hits@k summary if computed for k
+    pub fn get_hits_at(&self, k: usize) -> Option<&MetricSummary> {
+        self.hits_at_k
+            .as_ref()?
+            .iter()
+            .find(|(kh, _)| *kh == k)
+            .map(|(_, s)| s)
+    }
+
+    pub fn get_mrr(&self) -> Option<&MetricSummary> {
+        self.mrr.as_ref()
+    }
+
+    pub fn get_precision(&self) -> Option<&MetricSummary> {
+        self.precision.as_ref()
+    }
+
+    pub fn get_recall(&self) -> Option<&MetricSummary> {
+        self.recall.as_ref()
+    }
+
+    pub fn get_nb_edges_check(&self) -> Option<usize> {
+        self.nb_edges_check
+    }
+
+    pub fn get_degree_correlation(&self) -> Option<f64> {
+        self.degree_correlation
+    }
+
+    /// returns direction auc summary (only for direction auc estimation)
+    pub fn get_direction_auc(&self) -> Option<&MetricSummary> {
+        self.direction_auc.as_ref()
+    }
+
+    /// returns metrics for each pass (only for auc estimation)
+    pub fn get_passes(&self) -> &[PassMetrics] {
+        &self.passes
+    }
+
+    /// dump in json format LinkPredictionReport structure
+    pub fn dump_json(&self, filepath: &Path) -> Result<()> {
+        dump_reports_json(std::slice::from_ref(self), filepath)
+    }
+
+    /// reload a report from a json dump done by [Self::dump_json]
+    pub fn reload_json(filepath: &Path) -> Result<Self> {
+        let mut reports = reload_reports_json(filepath)?;
+        if reports.len() != 1 {
+            return Err(anyhow!(
+                "LinkPredictionReport::reload_json expected one report, got {}",
+                reports.len()
+            ));
+        }
+        Ok(reports.pop().unwrap())
+    }
+} // end of impl LinkPredictionReport
+
+/// dumps a slice of reports (as a json array) in file filepath
+pub fn dump_reports_json(reports: &[LinkPredictionReport], filepath: &Path) -> Result<()> {
+    dump_json(reports, filepath)
+} // end of dump_reports_json
+
+/// reloads reports dumped by [dump_reports_json]
+pub fn reload_reports_json(filepath: &Path) -> Result<Vec<LinkPredictionReport>> {
+    reload_json(filepath)
+} // end of reload_reports_json
+
+//========================================================================================
+
+/// Result of the paired comparison of a metric between two embedders evaluated on the same passes.
+///
+/// Differences are computed as first - second. The confidence interval is a 95% Student interval of the mean difference.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PairedTest {
+    nb_pairs: usize,
+    #[serde(with = "nonfinite")]
+    mean_diff: f64,
+    #[serde(with = "nonfinite")]
+    ci_low: f64,
+    #[serde(with = "nonfinite")]
+    ci_high: f64,
+    /// paired t-test statistic, infinite if all differences are equal and non null
+    #[serde(with = "nonfinite")]
+    t_statistic: f64,
+    /// two sided p-value of paired t-test
+    #[serde(with = "nonfinite")]
+    t_pvalue: f64,
+    /// sum of ranks of positive differences
+    wilcoxon_statistic: f64,
+    /// two sided p-value of Wilcoxon signed-rank test
+    wilcoxon_pvalue: f64,
+} // end of PairedTest
+
+impl PairedTest {
+    /// runs paired t-test and Wilcoxon signed-rank test on values of first and second
+    pub fn new(first: &[f64], second: &[f64]) -> Self {
+        let diffs: Vec<f64> = first
+            .iter()
+            .zip(second.iter())
+            .map(|(a, b)| a - b)
+            .collect();
+        let summary = MetricSummary::new(diffs);
+        let (t_statistic, t_pvalue) = paired_t_test(first, second);
+        let (wilcoxon_statistic, wilcoxon_pvalue) = wilcoxon_signed_rank(first, second);
+        PairedTest {
+            nb_pairs: first.len(),
+            mean_diff: summary.get_mean(),
+            ci_low: summary.get_confidence_interval().0,
+            ci_high: summary.get_confidence_interval().1,
+            t_statistic,
+            t_pvalue,
+            wilcoxon_statistic,
+            wilcoxon_pvalue,
+        }
+    } // end of new
+
+    pub fn get_nb_pairs(&self) -> usize {
+        self.nb_pairs
+    }
+
+    /// mean of differences first - second
+    pub fn get_mean_diff(&self) -> f64 {
+        self.mean_diff
+    }
+
+    /// returns 95% confidence interval of mean difference
+    pub fn get_confidence_interval(&self) -> (f64, f64) {
+        (self.ci_low, self.ci_high)
+    }
+
+    /// returns t statistic and two sided p-value of paired t-test
+    pub fn get_t_test(&self) -> (f64, f64) {
+        (self.t_statistic, self.t_pvalue)
+    }
+
+    /// returns statistic and two sided p-value of Wilcoxon signed-rank test
+    pub fn get_wilcoxon(&self) -> (f64, f64) {
+        (self.wilcoxon_statistic, self.wilcoxon_pvalue)
+    }
+} // end of impl PairedTest
+
+/// Paired comparison of two embedders on auc, average precision, Hits@10 and mrr.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PairedComparison {
+    first: String,
+    second: String,
+    auc: PairedTest,
+    average_precision: PairedTest,
+    hits_at_10: PairedTest,
+    mrr: PairedTest,
+} // end of PairedComparison
+
+impl PairedComparison {
+    pub(crate) fn new(
+        first: &str,
+        first_passes: &[PassMetrics],
+        second: &str,
+        second_passes: &[PassMetrics],
+    ) -> Self {
+        let test = |f: &dyn Fn(&PassMetrics) -> f64| -> PairedTest {
+            let v1: Vec<f64> = first_passes.iter().map(f).collect();
+            let v2: Vec<f64> = second_passes.iter().map(f).collect();
+            PairedTest::new(&v1, &v2)
+        };
+        PairedComparison {
+            first: first.to_string(),
+            second: second.to_string(),
+            auc: test(&|p| p.auc),
+            average_precision: test(&|p| p.average_precision),
+            hits_at_10: test(&|p| p.hits_at_10),
+            mrr: test(&|p| p.mrr),
+        }
+    } // end of new
+
+    /// returns names of compared embedders
+    pub fn get_names(&self) -> (&str, &str) {
+        (&self.first, &self.second)
+    }
+
+    pub fn get_auc(&self) -> &PairedTest {
+        &self.auc
+    }
+
+    pub fn get_average_precision(&self) -> &PairedTest {
+        &self.average_precision
+    }
+
+    pub fn get_hits_at_10(&self) -> &PairedTest {
+        &self.hits_at_10
+    }
+
+    pub fn get_mrr(&self) -> &PairedTest {
+        &self.mrr
+    }
+} // end of impl PairedComparison
+
+/// The result of [super::linkcompare::compare_embedders()].  
+/// It contains the report of each embedder and the paired comparisons of all couples of embedders.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmbedderComparison {
+    names: Vec<String>,
+    reports: Vec<LinkPredictionReport>,
+    comparisons: Vec<PairedComparison>,
+} // end of EmbedderComparison
+
+impl EmbedderComparison {
+    pub(crate) fn new(names: Vec<String>, reports: Vec<LinkPredictionReport>) -> Self {
+        let mut comparisons = Vec::<PairedComparison>::new();
+        for i in 0..names.len() {
+            for j in i + 1..names.len() {
+                comparisons.push(PairedComparison::new(
+                    &names[i],
+                    reports[i].get_passes(),
+                    &names[j],
+                    reports[j].get_passes(),
+                ));
+            }
+        }
+        EmbedderComparison {
+            names,
+            reports,
+            comparisons,
+        }
+    } // end of new
+
+    /// returns names of embedders in the order they were given
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// returns the link prediction report of each embedder
+    pub fn get_reports(&self) -> &[LinkPredictionReport] {
+        &self.reports
+    }
+
+    /// returns the comparison of embedders of rank i and j (i < j)
+    pub fn get_comparison(&self, i: usize, j: usize) -> Option<&PairedComparison> {
+        let (first, second) = (self.names.get(i)?, self.names.get(j)?);
+        self.comparisons
+            .iter()
+            .find(|c| c.first == *first && c.second == *second)
+    }
+
+    /// returns all paired comparisons
+    pub fn get_comparisons(&self) -> &[PairedComparison] {
+        &self.comparisons
+    }
+
+    /// dump in json format EmbedderComparison structure
+    pub fn dump_json(&self, filepath: &Path) -> Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reloads a comparison dumped by [Self::dump_json]
+    pub fn reload_json(filepath: &Path) -> Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl EmbedderComparison
+
+//========================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn test_report_json() {
+        log_init_test();
+        //
+        let passes = vec![
+            PassMetrics {
+                auc: 0.8,
+                average_precision: 0.7,
+                hits_at_1: 0.1,
+                hits_at_10: 0.3,
+                hits_at_50: 0.5,
+                mrr: 0.2,
+            },
+            PassMetrics {
+                auc: 0.9,
+                average_precision: 0.75,
+                hits_at_1: 0.15,
+                hits_at_10: 0.35,
+                hits_at_50: 0.55,
+                mrr: 0.25,
+            },
+        ];
+        let report = LinkPredictionReport::from_passes(true, 0.1, passes);
+        let auc = report.get_auc().unwrap();
+        assert!((auc.get_mean() - 0.85).abs() < 1.0e-10);
+        let (low, high) = auc.get_confidence_interval();
+        assert!(low < 0.85 && high > 0.85);
+        assert!(report.get_hits_at(10).is_some());
+        assert!(report.get_hits_at(5).is_none());
+        //
+        let path = std::env::temp_dir().join("linkreport_test.json");
+        report.dump_json(&path).unwrap();
+        let reloaded = LinkPredictionReport::reload_json(&path).unwrap();
+        assert_eq!(reloaded.get_estimator(), LinkEstimator::Auc);
+        assert_eq!(reloaded.get_passes().len(), 2);
+        assert_eq!(
+            reloaded.get_mrr().unwrap().get_values(),
+            report.get_mrr().unwrap().get_values()
+        );
+    } // end of test_report_json
+
+    #[test]
+    fn test_paired_comparison() {
+        log_init_test();
+        //
+        let first: Vec<PassMetrics> = (0..6)
+            .map(|i| PassMetrics {
+                auc: 0.8 + 0.01 * i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let second: Vec<PassMetrics> = (0..6)
+            .map(|i| PassMetrics {
+                auc: 0.7 + 0.011 * i as f64,
+                ..Default::default()
+            })
+            .collect();
+        let names = vec!["first".to_string(), "second".to_string()];
+        let reports = vec![
+            LinkPredictionReport::from_passes(true, 0.1, first),
+            LinkPredictionReport::from_passes(true, 0.1, second),
+        ];
+        let comparison = EmbedderComparison::new(names, reports);
+        let auc_test = comparison.get_comparison(0, 1).unwrap().get_auc();
+        assert!(auc_test.get_mean_diff() > 0.09);
+        assert!(auc_test.get_t_test().1 < 0.01);
+        // all differences positive on 6 pairs
+        assert!((auc_test.get_wilcoxon().1 - 2. / 64.).abs() < 1.0e-10);
+        // null differences on other metrics
+        let mrr_test = comparison.get_comparison(0, 1).unwrap().get_mrr();
+        assert_eq!(mrr_test.get_wilcoxon().1, 1.);
+        assert!(comparison.get_comparison(1, 0).is_none());
+    } // end of test_paired_comparison
+
+    // an empty summary has NaN mean and bounds, constant differences give an infinite t statistic.
+    // Both must survive a json dump and reload.
+    #[test]
+    fn test_nonfinite_json() {
+        log_init_test();
+        //
+        let path = std::env::temp_dir().join("linkreport_nonfinite_test.json");
+        let summary = MetricSummary::new(Vec::new());
+        dump_json(&summary, &path).unwrap();
+        let reloaded: MetricSummary = reload_json(&path).unwrap();
+        assert!(reloaded.get_mean().is_nan());
+        assert!(reloaded.get_std_dev().is_nan());
+        assert!(reloaded.get_confidence_interval().0.is_nan());
+        assert!(reloaded.get_values().is_empty());
+        //
+        let summary = MetricSummary::new(vec![0.5, f64::NAN, f64::NEG_INFINITY]);
+        dump_json(&summary, &path).unwrap();
+        let reloaded: MetricSummary = reload_json(&path).unwrap();
+        assert_eq!(reloaded.get_values()[0], 0.5);
+        assert!(reloaded.get_values()[1].is_nan());
+        assert_eq!(reloaded.get_values()[2], f64::NEG_INFINITY);
+        //
+        let test = PairedTest::new(&[1., 2., 3.], &[0.5, 1.5, 2.5]);
+        assert_eq!(test.get_t_test().0, f64::INFINITY);
+        dump_json(&test, &path).unwrap();
+        let reloaded: PairedTest = reload_json(&path).unwrap();
+        assert_eq!(reloaded.get_t_test(), (f64::INFINITY, 0.));
+        assert!((reloaded.get_mean_diff() - 0.5).abs() < 1.0e-10);
+    } // end of test_nonfinite_json
+} // end of mod tests
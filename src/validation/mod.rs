@@ -1,6 +1,11 @@
 //! This module implements some validation tools
 //!  We use:
 //! - the standard link prediction [link::estimate_auc()] based and a centric auc [link::estimate_centric_auc]
+//!   Results are returned in a [linkreport::LinkPredictionReport]
+//! - a paired comparison of embedders on identical edge splits [linkcompare::compare_embedders()]
+//! - a label consistency check for graphs with labeled nodes, label-homophily@k [nodelabel::estimate_label_homophily()]
+//! - prediction of hidden node labels from labeled graph sketches [labelprediction::estimate_label_prediction()]
+//! - graph classification by knn on global graph sketches [graphclass::knn_graph_classification()]
 //! - A decomposition in stable blocks to evaluate preservation of "communities" [anndensity]
 
 pub mod link;
@@ -18,4 +23,19 @@ pub mod link;
 ///     Aziz, Gul Nature Scientific reports 2020.
 pub mod linkparams;
 
+/// structured results of link prediction estimators, dumpable in json
+pub mod linkreport;
+
+/// paired comparison of embedders on identical edge splits
+pub mod linkcompare;
+
+/// label-homophily@k of embeddings of graphs with labeled nodes
+pub mod nodelabel;
+
+/// prediction of hidden node labels from labeled graph sketches
+pub mod labelprediction;
+
+/// knn classification of graph collections from global sketches
+pub mod graphclass;
+
 pub mod anndensity;
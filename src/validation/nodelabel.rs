@@ -0,0 +1,446 @@
+//! Node label consistency validation (see [super::link::ValidationMode::NODELABEL]).
+//!
+//! For graphs with labels attached to nodes we measure how often the k nearest neighbours of a node in the embedded space
+//! share at least one label with the node. This is the label-homophily@k of the embedding.
+//! It is compared with the label-homophily@k of the graph neighbourhood: the first k labeled nodes reached
+//! by a breadth first search from the node, and with the homophily of a random node (the fraction of labeled nodes sharing a label with the node).
+//!
+//! Any embedding implementing [EmbeddedT] can be checked, so it works with embedders that do not see labels
+//! (Hope, NodeSketch) and with [MgraphSketch](crate::embed::gkernel::psketch::MgraphSketch) output.
+//! Nearest neighbours in the embedded space are searched in an Hnsw structure as in [anndensity](super::anndensity).
+//! It is built by [embeddedtohnsw] for a symetric embedding; for an asymetric embedding it must be given by the caller,
+//! for example by [embeddedasymtohnsw](super::anndensity::embeddedasymtohnsw).
+//! The graph can be given as a compressed matrix (as for link prediction) with [estimate_label_homophily()]
+//! or as a petgraph Graph (as for gkernel) with [estimate_label_homophily_graph()].
+//! In both cases nodes are identified by their rank in the embedding (the row in the matrix or the NodeIndex in the Graph).
+
+use anyhow::*;
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rand_xoshiro::rand_core::SeedableRng;
+use rayon::prelude::*;
+
+use petgraph::EdgeType;
+use petgraph::graph::{Graph, IndexType};
+
+use serde::{Deserialize, Serialize};
+
+use sprs::CsMatI;
+
+use hnsw_rs::prelude::*;
+
+use super::anndensity::{HnswDistance, embeddedtohnsw};
+use super::linkreport::MetricSummary;
+use crate::embed::gkernel::pgraph::{HasNweight, LabelT};
+use crate::embedding::EmbeddedT;
+use crate::io::jsonio::{dump_json, reload_json};
+
+// max number of connections of the Hnsw built when none is given
+const HNSW_MAX_NB_CONNECTION: usize = 32;
+// ef used in Hnsw construction and search
+const HNSW_EF: usize = 64;
+// max number of labeled nodes in the sample used for the random baseline
+const NB_RANDOM_SAMPLE: usize = 1000;
+
+/// Results of label-homophily@k estimation.
+///
+/// Values are per examined node. Nodes without labels are not examined and are not counted as neighbours.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LabelHomophilyReport {
+    /// number of neighbours examined
+    k: usize,
+    /// number of labeled nodes examined
+    nb_nodes: usize,
+    /// fraction of the k nearest neighbours in embedded space sharing a label
+    embedded: MetricSummary,
+    /// fraction of the k first nodes of the graph neighbourhood sharing a label.
+    /// Nodes with no labeled node reachable are not in the summary
+    graph: MetricSummary,
+    /// fraction of a random sample of labeled nodes sharing a label
+    random: MetricSummary,
+} // end of LabelHomophilyReport
+
+impl LabelHomophilyReport {
+    /// returns k, the number of neighbours examined
+    pub fn get_k(&self) -> usize {
+        self.k
+    }
+
+    /// returns the number of nodes examined
+    pub fn get_nb_nodes(&self) -> usize {
+        self.nb_nodes
+    }
+
+    /// label-homophily@k of the embedding
+    pub fn get_embedded_homophily(&self) -> &MetricSummary {
+        &self.embedded
+    }
+
+    /// label-homophily@k of the graph neighbourhood
+    pub fn get_graph_homophily(&self) -> &MetricSummary {
+        &self.graph
+    }
+
+    /// homophily of randomly chosen nodes, the baseline
+    pub fn get_random_homophily(&self) -> &MetricSummary {
+        &self.random
+    }
+
+    /// dump in json format
+    pub fn dump_json(&self, filepath: &Path) -> Result<()> {
+        dump_json(self, filepath)
+    }
+
+    /// reload a report from a json dump done by [Self::dump_json]
+    pub fn reload_json(filepath: &Path) -> Result<Self> {
+        reload_json(filepath)
+    }
+} // end of impl LabelHomophilyReport
+
+//==================================================================================================
+
+/// Extracts labels of nodes of a gkernel graph, indexed by NodeIndex.
+pub fn get_graph_node_labels<Nlabel, N, E, Ty, Ix>(graph: &Graph<N, E, Ty, Ix>) -> Vec<Vec<Nlabel>>
+where
+    Nlabel: LabelT,
+    N: HasNweight<Nlabel>,
+    Ty: EdgeType,
+    Ix: IndexType,
+{
+    graph
+        .node_weights()
+        .map(|n| n.get_nweight().get_labels().to_vec())
+        .collect()
+} // end of get_graph_node_labels
+
+/// Estimates label-homophily@k of an embedding of the graph given by csmat.
+///
+/// - labels : labels\[i\] are the labels of node of rank i (possibly empty).
+/// - hnsw_opt : Hnsw structure on the embedded nodes, identified by their rank. If None it is built by [embeddedtohnsw],
+///   which returns an error for an asymetric embedding.
+/// - k : number of neighbours examined.
+/// - nb_sample : number of labeled nodes examined (all labeled nodes if nb_sample is larger).
+/// - seed : seed of the sampling of examined nodes.
+///
+/// The graph neighbourhood of a node is made of its out-neighbours (row of csmat) and their out-neighbours recursively.
+pub fn estimate_label_homophily<F, G, E, L>(
+    csmat: &CsMatI<F, usize>,
+    labels: &[Vec<L>],
+    embedded: &E,
+    hnsw_opt: Option<Hnsw<G, HnswDistance<G>>>,
+    k: usize,
+    nb_sample: usize,
+    seed: u64,
+) -> Result<LabelHomophilyReport>
+where
+    G: Copy + Clone + Send + Sync,
+    E: EmbeddedT<G>,
+    L: PartialEq + Sync,
+{
+    let neighbours: Vec<Vec<usize>> = csmat
+        .outer_iterator()
+        .map(|row| row.indices().to_vec())
+        .collect();
+    label_homophily::<G, E, L>(&neighbours, labels, embedded, hnsw_opt, k, nb_sample, seed)
+} // end of estimate_label_homophily
+
+/// Estimates label-homophily@k of an embedding of a petgraph graph, as obtained by gkernel sketching.
+///
+/// Arguments are the same as in [estimate_label_homophily()]. Labels of a gkernel graph can be obtained with [get_graph_node_labels()].
+/// The graph neighbourhood of a node is made of its outgoing neighbours (all neighbours for an undirected graph).
+pub fn estimate_label_homophily_graph<N, Ed, Ty, Ix, G, E, L>(
+    graph: &Graph<N, Ed, Ty, Ix>,
+    labels: &[Vec<L>],
+    embedded: &E,
+    hnsw_opt: Option<Hnsw<G, HnswDistance<G>>>,
+    k: usize,
+    nb_sample: usize,
+    seed: u64,
+) -> Result<LabelHomophilyReport>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    G: Copy + Clone + Send + Sync,
+    E: EmbeddedT<G>,
+    L: PartialEq + Sync,
+{
+    let neighbours: Vec<Vec<usize>> = graph
+        .node_indices()
+        .map(|n| {
+            let mut neighbours_n: Vec<usize> = graph.neighbors(n).map(|m| m.index()).collect();
+            neighbours_n.sort_unstable();
+            neighbours_n.dedup();
+            neighbours_n
+        })
+        .collect();
+    label_homophily::<G, E, L>(&neighbours, labels, embedded, hnsw_opt, k, nb_sample, seed)
+} // end of estimate_label_homophily_graph
+
+// true if the 2 nodes share at least one label
+fn share_label<L: PartialEq>(labels1: &[L], labels2: &[L]) -> bool {
+    labels1.iter().any(|l| labels2.contains(l))
+}
+
+// returns the k first labeled nodes reached by a breadth first search from node (node excluded)
+fn graph_neighbourhood<L>(
+    neighbours: &[Vec<usize>],
+    labels: &[Vec<L>],
+    node: usize,
+    k: usize,
+) -> Vec<usize> {
+    let mut found = Vec::<usize>::with_capacity(k);
+    let mut visited = vec![false; neighbours.len()];
+    let mut queue = VecDeque::<usize>::new();
+    visited[node] = true;
+    queue.push_back(node);
+    while let Some(current) = queue.pop_front() {
+        for &next in &neighbours[current] {
+            if visited[next] {
+                continue;
+            }
+            visited[next] = true;
+            if !labels[next].is_empty() {
+                found.push(next);
+                if found.len() >= k {
+                    return found;
+                }
+            }
+            queue.push_back(next);
+        }
+    }
+    found
+} // end of graph_neighbourhood
+
+// returns (embedded homophily, graph homophily if some labeled node is reachable, random homophily) for node.
+// query is the vector of node in the hnsw, random is a sample of labeled nodes
+#[allow(clippy::too_many_arguments)]
+fn node_homophily<G, L>(
+    neighbours: &[Vec<usize>],
+    labels: &[Vec<L>],
+    hnsw: &Hnsw<G, HnswDistance<G>>,
+    query: &[G],
+    random: &[usize],
+    node: usize,
+    k: usize,
+) -> (f64, Option<f64>, f64)
+where
+    G: Copy + Clone + Send + Sync,
+    L: PartialEq,
+{
+    let node_labels = &labels[node];
+    // random baseline
+    let nb_random = random.iter().filter(|j| **j != node).count();
+    if nb_random == 0 {
+        return (0., None, 0.);
+    }
+    let nb_random_share = random
+        .iter()
+        .filter(|j| **j != node && share_label(node_labels, &labels[**j]))
+        .count();
+    let random_homophily = nb_random_share as f64 / nb_random as f64;
+    // k nearest labeled neighbours in the embedded space
+    let filter = |id: &DataId| *id != node && !labels[*id].is_empty();
+    let knn = hnsw.search_filter(query, k, HNSW_EF.max(k), Some(&filter));
+    let embedded_homophily = if knn.is_empty() {
+        0.
+    } else {
+        let nb_embedded_share = knn
+            .iter()
+            .filter(|n| share_label(node_labels, &labels[n.get_origin_id()]))
+            .count();
+        nb_embedded_share as f64 / knn.len() as f64
+    };
+    //
+    let graph_knn = graph_neighbourhood(neighbours, labels, node, k);
+    let graph_homophily = if graph_knn.is_empty() {
+        None
+    } else {
+        let nb_graph_share = graph_knn
+            .iter()
+            .filter(|j| share_label(node_labels, &labels[**j]))
+            .count();
+        Some(nb_graph_share as f64 / graph_knn.len() as f64)
+    };
+    //
+    (embedded_homophily, graph_homophily, random_homophily)
+} // end of node_homophily
+
+fn label_homophily<G, E, L>(
+    neighbours: &[Vec<usize>],
+    labels: &[Vec<L>],
+    embedded: &E,
+    hnsw_opt: Option<Hnsw<G, HnswDistance<G>>>,
+    k: usize,
+    nb_sample: usize,
+    seed: u64,
+) -> Result<LabelHomophilyReport>
+where
+    G: Copy + Clone + Send + Sync,
+    E: EmbeddedT<G>,
+    L: PartialEq + Sync,
+{
+    //
+    if k == 0 {
+        return Err(anyhow!("label homophily needs k > 0"));
+    }
+    let nb_nodes = embedded.get_nb_nodes();
+    if labels.len() != nb_nodes || neighbours.len() != nb_nodes {
+        return Err(anyhow!(
+            "labels, graph and embedding must have the same number of nodes"
+        ));
+    }
+    let hnsw = match hnsw_opt {
+        Some(hnsw) => hnsw,
+        None => embeddedtohnsw::<G>(embedded, HNSW_MAX_NB_CONNECTION, HNSW_EF)?,
+    };
+    if hnsw.get_nb_point() != nb_nodes {
+        return Err(anyhow!(
+            "hnsw has {} points, embedding has {} nodes",
+            hnsw.get_nb_point(),
+            nb_nodes
+        ));
+    }
+    //
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let labeled: Vec<usize> = (0..nb_nodes).filter(|i| !labels[*i].is_empty()).collect();
+    let examined: Vec<usize> = if nb_sample >= labeled.len() {
+        labeled.clone()
+    } else {
+        let mut sampled: Vec<usize> = rand::seq::index::sample(&mut rng, labeled.len(), nb_sample)
+            .into_iter()
+            .map(|i| labeled[i])
+            .collect();
+        sampled.sort_unstable();
+        sampled
+    };
+    let random: Vec<usize> = if NB_RANDOM_SAMPLE >= labeled.len() {
+        labeled.clone()
+    } else {
+        rand::seq::index::sample(&mut rng, labeled.len(), NB_RANDOM_SAMPLE)
+            .into_iter()
+            .map(|i| labeled[i])
+            .collect()
+    };
+    log::info!(
+        "label_homophily, k : {}, nb labeled nodes examined : {}",
+        k,
+        examined.len()
+    );
+    // queries are the vectors stored in the hnsw, so that they are consistent with its distance
+    let mut queries: Vec<Option<Vec<G>>> = vec![None; nb_nodes];
+    for rank in &examined {
+        queries[*rank] = Some(Vec::new());
+    }
+    for point in hnsw.get_point_indexation() {
+        if let Some(query) = queries
+            .get_mut(point.get_origin_id())
+            .and_then(|q| q.as_mut())
+        {
+            *query = point.get_v().to_vec();
+        }
+    }
+    //
+    let results: Vec<(f64, Option<f64>, f64)> = examined
+        .par_iter()
+        .map(|node| {
+            let query = queries[*node].as_ref().unwrap();
+            node_homophily::<G, L>(neighbours, labels, &hnsw, query, &random, *node, k)
+        })
+        .collect();
+    let embedded_values: Vec<f64> = results.iter().map(|r| r.0).collect();
+    let graph_values: Vec<f64> = results.iter().filter_map(|r| r.1).collect();
+    let random_values: Vec<f64> = results.iter().map(|r| r.2).collect();
+    //
+    let report = LabelHomophilyReport {
+        k,
+        nb_nodes: examined.len(),
+        embedded: MetricSummary::new(embedded_values),
+        graph: MetricSummary::new(graph_values),
+        random: MetricSummary::new(random_values),
+    };
+    log::info!(
+        "label homophily@{} embedded : {:.3e}, graph : {:.3e}, random : {:.3e}",
+        k,
+        report.embedded.get_mean(),
+        report.graph.get_mean(),
+        report.random.get_mean()
+    );
+    //
+    Ok(report)
+} // end of label_homophily
+
+//================================================================================================================
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::prelude::*;
+    use sprs::TriMatI;
+
+    fn log_init_test() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    // 2 cliques of 10 nodes linked by one edge, each clique having its own label.
+    // nodesketch must keep nodes of a clique close
+    #[test]
+    fn test_label_homophily_two_cliques() {
+        //
+        log_init_test();
+        //
+        let clique_size = 10;
+        let nb_nodes = 2 * clique_size;
+        let mut rows = Vec::<usize>::new();
+        let mut cols = Vec::<usize>::new();
+        for c in 0..2 {
+            for i in 0..clique_size {
+                for j in 0..clique_size {
+                    if i != j {
+                        rows.push(c * clique_size + i);
+                        cols.push(c * clique_size + j);
+                    }
+                }
+            }
+        }
+        rows.push(0);
+        cols.push(clique_size);
+        rows.push(clique_size);
+        cols.push(0);
+        let values = vec![1.; rows.len()];
+        let trimat = TriMatI::<f64, usize>::from_triplets((nb_nodes, nb_nodes), rows, cols, values);
+        let csrmat: CsMatI<f64, usize> = trimat.to_csr();
+        let labels: Vec<Vec<u8>> = (0..nb_nodes)
+            .map(|i| vec![(i / clique_size) as u8])
+            .collect();
+        //
+        let params = NodeSketchParams {
+            sketch_size: 100,
+            decay: 0.1,
+            nb_iter: 3,
+            symetric: true,
+            parallel: false,
+        };
+        let embedded = NodeSketch::new(params, trimat).embed().unwrap();
+        let report = estimate_label_homophily(
+            &csrmat,
+            &labels,
+            &embedded,
+            None,
+            5,
+            nb_nodes,
+            DEFAULT_VALIDATION_SEED,
+        )
+        .unwrap();
+        log::info!("report : {:?}", report);
+        assert_eq!(report.get_nb_nodes(), nb_nodes);
+        // in the graph only nodes 0 and clique_size see a node of the other clique among their 5 first neighbours
+        assert!(report.get_graph_homophily().get_mean() > 0.95);
+        assert!((report.get_random_homophily().get_mean() - 9. / 19.).abs() < 1.0e-10);
+        assert!(report.get_embedded_homophily().get_mean() > 0.9);
+    } // end of test_label_homophily_two_cliques
+} // end of mod tests